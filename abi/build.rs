@@ -1,10 +1,34 @@
-use std::process::Command;
+use std::{env, path::PathBuf, process::Command};
 
+use prost::Message;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, FileDescriptorSet,
+};
 use tonic_build::Builder;
 
 fn main() {
+    // the descriptors decide the json mapping of the messages, and are kept for the openapi
+    // document
+    let descriptors = PathBuf::from(env::var("OUT_DIR").unwrap()).join("reservation.bin");
+    let status = Command::new(prost_build::protoc_from_env())
+        .args([
+            "--include_imports",
+            "--include_source_info",
+            "-I",
+            "protos",
+            "-o",
+        ])
+        .arg(&descriptors)
+        .arg("protos/reservation.proto")
+        .status()
+        .unwrap();
+    assert!(status.success(), "protoc failed: {}", status);
+    let set = FileDescriptorSet::decode(std::fs::read(&descriptors).unwrap().as_slice()).unwrap();
+
     tonic_build::configure()
         .out_dir("src/pb")
+        .with_json(&set)
         .with_sql_type(&["reservation.ReservationStatus"])
         .with_builder(&[
             "reservation.ReservationQuery",
@@ -19,6 +43,15 @@ fn main() {
                 "page",
                 "page_size",
                 "desc",
+                "include_cancelled",
+                "match_mode",
+                "statuses",
+                "resource_ids",
+                "user_ids",
+                "page_token",
+                "sort",
+                "tags",
+                "attributes",
             ],
         )
         .with_builder_into(
@@ -30,6 +63,12 @@ fn main() {
                 "desc",
                 "page_size",
                 "cursor",
+                "include_cancelled",
+                "statuses",
+                "resource_ids",
+                "user_ids",
+                "tags",
+                "attributes",
             ],
         )
         .with_builder_option("reservation.ReservationQuery", &["start", "end"])
@@ -41,6 +80,7 @@ fn main() {
 }
 
 trait BuilderExt {
+    fn with_json(self, set: &FileDescriptorSet) -> Self;
     fn with_sql_type(self, paths: &[&str]) -> Self;
     fn with_builder(self, paths: &[&str]) -> Self;
     fn with_builder_into(self, paths: &str, field: &[&str]) -> Self;
@@ -48,6 +88,42 @@ trait BuilderExt {
 }
 
 impl BuilderExt for Builder {
+    /// serde for the messages of the reservation package, following the protobuf json mapping
+    fn with_json(self, set: &FileDescriptorSet) -> Self {
+        set.file
+            .iter()
+            .filter(|file| file.package() == "reservation")
+            .flat_map(|file| &file.message_type)
+            .fold(self, |builder, message| {
+                let path = format!("reservation.{}", message.name());
+                assert!(
+                    message.nested_type.iter().all(is_map_entry),
+                    "nested messages of {} have no json mapping",
+                    path
+                );
+                let builder = builder
+                    .type_attribute(&path, "#[derive(serde::Serialize, serde::Deserialize)]")
+                    .type_attribute(&path, "#[serde(rename_all = \"camelCase\", default)]");
+                let builder = message.oneof_decl.iter().fold(builder, |builder, oneof| {
+                    let oneof = format!("{}.{}", path, oneof.name());
+                    builder
+                        .field_attribute(&oneof, "#[serde(flatten)]")
+                        .type_attribute(&oneof, "#[derive(serde::Serialize, serde::Deserialize)]")
+                        .type_attribute(&oneof, "#[serde(rename_all = \"camelCase\")]")
+                });
+                message.field.iter().fold(builder, |builder, field| {
+                    let attrs = json_field_attributes(message, field);
+                    match attrs.is_empty() {
+                        true => builder,
+                        false => builder.field_attribute(
+                            format!("{}.{}", path, field.name()),
+                            format!("#[serde({})]", attrs.join(", ")),
+                        ),
+                    }
+                })
+            })
+    }
+
     fn with_sql_type(self, paths: &[&str]) -> Self {
         paths.iter().fold(self, |builder, path| {
             builder.type_attribute(path, "#[derive(sqlx::Type)]")
@@ -73,8 +149,55 @@ impl BuilderExt for Builder {
         field.iter().fold(self, |builder, field| {
             builder.field_attribute(
                 format!("{}.{}", paths, field).as_str(),
-                "#[builder(setter(strip_option))]",
+                "#[builder(setter(strip_option), default)]",
             )
         })
     }
 }
+
+/// serde arguments of a field, its json name is the camel case of its name
+fn json_field_attributes(message: &DescriptorProto, field: &FieldDescriptorProto) -> Vec<String> {
+    let in_oneof = field.oneof_index.is_some();
+    let repeated = field.label() == Label::Repeated;
+    let mut attrs = vec![];
+    // the original field names are accepted too
+    if !in_oneof && field.name().contains('_') {
+        attrs.push(format!("alias = \"{}\"", field.name()));
+    }
+    match (field.r#type(), field.type_name()) {
+        (Type::Message, ".google.protobuf.Timestamp") if !repeated => {
+            attrs.push("with = \"crate::json::timestamp\"".into());
+        }
+        (Type::Message, ".google.protobuf.FieldMask") if !repeated => {
+            attrs.push("with = \"crate::json::field_mask\"".into());
+        }
+        (Type::Message, name) if name.starts_with(".google.") => {
+            panic!("{} of {} has no json mapping", name, message.name())
+        }
+        (Type::Enum, name) => {
+            let name = name
+                .strip_prefix(".reservation.")
+                .expect("enums are of the reservation package");
+            let module = if repeated { "enums" } else { "enumeration" };
+            attrs.push(format!(
+                "serialize_with = \"crate::json::{0}::serialize::<crate::{1}, _>\", \
+                 deserialize_with = \"crate::json::{0}::deserialize::<crate::{1}, _>\"",
+                module, name
+            ));
+        }
+        (Type::Bytes, _) if !repeated => attrs.push("with = \"crate::json::bytes\"".into()),
+        _ => {}
+    }
+    // an unset message field is left out instead of being null
+    if !in_oneof && !repeated && field.r#type() == Type::Message {
+        attrs.push("skip_serializing_if = \"Option::is_none\"".into());
+    }
+    attrs
+}
+
+fn is_map_entry(message: &DescriptorProto) -> bool {
+    message
+        .options
+        .as_ref()
+        .is_some_and(|options| options.map_entry())
+}
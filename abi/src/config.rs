@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fmt, fs};
 
 use anyhow::{Ok, Result};
 use serde::{Deserialize, Serialize};
@@ -7,9 +7,12 @@ use serde::{Deserialize, Serialize};
 pub struct Config {
     pub db: DbConfig,
     pub server: ServerConfig,
+    /// resources mirrored into caldav collections by the sync worker
+    #[serde(default)]
+    pub caldav: Option<CalDavConfig>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DbConfig {
     pub host: String,
     pub port: u16,
@@ -24,10 +27,114 @@ fn default_pool_size() -> u32 {
     5
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// how long the result of a call is kept for its idempotency key, in seconds
+    #[serde(default = "default_idempotency_window")]
+    pub idempotency_window: u64,
+    /// secret which signs query page tokens, the server refuses to start without it. Every
+    /// instance behind the same clients needs the same secret
+    #[serde(default)]
+    pub page_token_secret: String,
+    /// port of the http server which serves the calendar feeds, no http server if not given
+    #[serde(default)]
+    pub http_port: Option<u16>,
+    /// origins of the browser apps which may call the grpc server through grpc-web, "*" allows
+    /// any origin. Calls from other origins are refused by the browser
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+    /// token of the admin calls, sent as "authorization: Bearer <token>". The admin calls are
+    /// refused if it is empty
+    #[serde(default)]
+    pub admin_token: String,
+}
+
+fn default_idempotency_window() -> u64 {
+    24 * 60 * 60
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalDavConfig {
+    /// basic auth of the caldav server, none if empty
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// seconds between two syncs
+    #[serde(default = "default_caldav_interval")]
+    pub interval: u64,
+    /// user of the reservations made from events without an organizer
+    #[serde(default = "default_caldav_user")]
+    pub user_id: String,
+    pub mirrors: Vec<CalDavMirror>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalDavMirror {
+    pub resource_id: String,
+    /// url of the calendar collection, with a trailing slash
+    pub url: String,
+}
+
+fn default_caldav_interval() -> u64 {
+    60
+}
+
+fn default_caldav_user() -> String {
+    "caldav".to_string()
+}
+
+/// a secret of the config in debug output, only whether it is set
+struct Redacted<'a>(&'a str);
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.is_empty() {
+            true => write!(f, "\"\""),
+            false => write!(f, "\"***\""),
+        }
+    }
+}
+
+impl fmt::Debug for DbConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DbConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &Redacted(&self.password))
+            .field("dbname", &self.dbname)
+            .field("max_connections", &self.max_connections)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("idempotency_window", &self.idempotency_window)
+            .field("page_token_secret", &Redacted(&self.page_token_secret))
+            .field("http_port", &self.http_port)
+            .field("cors_origins", &self.cors_origins)
+            .field("admin_token", &Redacted(&self.admin_token))
+            .finish()
+    }
+}
+
+impl fmt::Debug for CalDavConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CalDavConfig")
+            .field("username", &self.username)
+            .field("password", &Redacted(&self.password))
+            .field("interval", &self.interval)
+            .field("user_id", &self.user_id)
+            .field("mirrors", &self.mirrors)
+            .finish()
+    }
 }
 
 impl Config {
@@ -86,8 +193,39 @@ mod tests {
                 server: ServerConfig {
                     host: "localhost".to_string(),
                     port: 50001,
+                    idempotency_window: 86400,
+                    page_token_secret: "fixture-secret".to_string(),
+                    http_port: None,
+                    cors_origins: vec![],
+                    admin_token: "".to_string(),
                 },
+                caldav: None,
             }
         )
     }
+
+    #[test]
+    fn config_debug_should_redact_secrets() {
+        let mut config = Config::load("../service/fixtures/config.yml").unwrap();
+        config.db.password = "db-secret".into();
+        config.server.admin_token = "admin-secret".into();
+        config.caldav = Some(CalDavConfig {
+            username: "alice".into(),
+            password: "caldav-secret".into(),
+            interval: 60,
+            user_id: "caldav".into(),
+            mirrors: vec![],
+        });
+        let debug = format!("{:?}", config);
+        for secret in [
+            "db-secret",
+            "fixture-secret",
+            "admin-secret",
+            "caldav-secret",
+        ] {
+            assert!(!debug.contains(secret), "{} in {}", secret, debug);
+        }
+        assert!(debug.contains("admin_token: \"***\""));
+        assert!(debug.contains("user: \"postgres\""));
+    }
 }
@@ -21,7 +21,8 @@ pub struct ReservationConflict {
 pub struct ReservationWindow {
     pub rid: String,
     pub start: DateTime<Utc>,
-    pub end: DateTime<Utc>,
+    /// None for an open ended reservation
+    pub end: Option<DateTime<Utc>>,
 }
 
 impl FromStr for ReservationConflictInfo {
@@ -64,7 +65,10 @@ impl TryFrom<HashMap<String, String>> for ReservationWindow {
 
         let mut split = timespan_str.splitn(2, ',');
         let start = parse_datetime(split.next().ok_or(())?)?;
-        let end = parse_datetime(split.next().ok_or(())?)?;
+        let end = match split.next().ok_or(())? {
+            "" => None,
+            end => Some(parse_datetime(end)?),
+        };
 
         Ok(Self {
             rid: value.get("resource_id").ok_or(())?.to_string(),
@@ -106,8 +110,8 @@ impl FromStr for ParsedInfo {
     }
 }
 
-fn parse_datetime(s: &str) -> Result<DateTime<Utc>, ()> {
-    Ok(DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%#z")
+pub(crate) fn parse_datetime(s: &str) -> Result<DateTime<Utc>, ()> {
+    Ok(DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%#z")
         .map_err(|_| ())?
         .with_timezone(&Utc))
 }
@@ -122,6 +126,8 @@ mod tests {
     fn parse_datetime_should_work() {
         let dt = parse_datetime("2022-12-25 19:00:00+00").unwrap();
         assert_eq!(dt.to_rfc3339(), "2022-12-25T19:00:00+00:00");
+        let dt = parse_datetime("2022-12-25 19:00:00.25+08").unwrap();
+        assert_eq!(dt.to_rfc3339(), "2022-12-25T11:00:00.250+00:00");
     }
 
     #[test]
@@ -156,7 +162,19 @@ mod tests {
         let window: ReservationWindow = map.try_into().unwrap();
         assert_eq!(window.rid, "ocean-view-room-731");
         assert_eq!(window.start.to_rfc3339(), "2022-12-25T19:00:00+00:00");
-        assert_eq!(window.end.to_rfc3339(), "2022-12-27T19:00:00+00:00");
+        assert_eq!(
+            window.end.unwrap().to_rfc3339(),
+            "2022-12-27T19:00:00+00:00"
+        );
+
+        let mut map = HashMap::new();
+        map.insert("resource_id".to_string(), "ocean-view-room-731".to_string());
+        map.insert(
+            "timespan".to_string(),
+            "\"2022-12-25 19:00:00+00\",".to_string(),
+        );
+        let window: ReservationWindow = map.try_into().unwrap();
+        assert_eq!(window.end, None);
     }
 
     #[test]
@@ -166,10 +184,16 @@ mod tests {
             ReservationConflictInfo::Parsed(conflict) => {
                 assert_eq!(conflict.new.rid, "ocean-view-room-731");
                 assert_eq!(conflict.new.start.to_rfc3339(), "2022-12-25T19:00:00+00:00");
-                assert_eq!(conflict.new.end.to_rfc3339(), "2022-12-27T19:00:00+00:00");
+                assert_eq!(
+                    conflict.new.end.unwrap().to_rfc3339(),
+                    "2022-12-27T19:00:00+00:00"
+                );
                 assert_eq!(conflict.old.rid, "ocean-view-room-731");
                 assert_eq!(conflict.old.start.to_rfc3339(), "2022-12-24T19:00:00+00:00");
-                assert_eq!(conflict.old.end.to_rfc3339(), "2022-12-28T19:00:00+00:00");
+                assert_eq!(
+                    conflict.old.end.unwrap().to_rfc3339(),
+                    "2022-12-28T19:00:00+00:00"
+                );
             }
             ReservationConflictInfo::UnParsed(s) => {
                 assert_eq!(s, "Key (resource_id, timespan)=(ocean-view-room-731, [\"2022-12-25 19:00:00+00\",\"2022-12-27 19:00:00+00\")) conflicts with existing key (resource_id, timespan)=(ocean-view-room-731, [\"2022-12-24 19:00:00+00\",\"2022-12-28 19:00:00+00\"])).");
@@ -2,6 +2,8 @@ mod conflict;
 
 use thiserror::Error;
 
+use crate::ReservationStatus;
+
 pub use conflict::*;
 
 #[derive(Error, Debug)]
@@ -21,6 +23,69 @@ pub enum ReservationError {
     #[error("invalid reservation id: {0}")]
     InvalidReservationId(i64),
 
+    #[error("invalid reservation status: {0}")]
+    InvalidStatus(i32),
+
+    #[error("invalid status transition: {0} -> {1}")]
+    InvalidTransition(ReservationStatus, ReservationStatus),
+
+    #[error("reservations are cancelled with cancel, not by a status change")]
+    CancelWithStatus,
+
+    #[error("invalid update mask: {0}")]
+    InvalidUpdateMask(String),
+
+    #[error("invalid match mode: {0}")]
+    InvalidMatchMode(i32),
+
+    #[error("invalid page size: {0}")]
+    InvalidPageSize(i64),
+
+    #[error("invalid page token")]
+    InvalidPageToken,
+
+    #[error("invalid sort field: {0}")]
+    InvalidSortField(i32),
+
+    #[error("invalid tag: {0}")]
+    InvalidTag(String),
+
+    #[error("invalid attribute: {0}")]
+    InvalidAttribute(String),
+
+    #[error("invalid search query: {0}")]
+    InvalidSearchQuery(String),
+
+    #[error("invalid analytics window: {0}")]
+    InvalidAnalyticsWindow(String),
+
+    #[error("invalid bucket size: {0}")]
+    InvalidBucketSize(i32),
+
+    #[error("invalid time zone: {0}")]
+    InvalidTimeZone(String),
+
+    #[error("invalid export format: {0}")]
+    InvalidExportFormat(i32),
+
+    #[error("export failed: {0}")]
+    ExportFailed(String),
+
+    #[error("invalid import format: {0}")]
+    InvalidImportFormat(i32),
+
+    #[error("invalid import mode: {0}")]
+    InvalidImportMode(i32),
+
+    #[error("invalid import batch size: {0}")]
+    InvalidImportBatchSize(i32),
+
+    #[error("invalid import data: {0}")]
+    InvalidImportData(String),
+
+    #[error("field is immutable: {0}")]
+    ImmutableField(String),
+
     #[error("reservation conflict")]
     ConflictReservation(ReservationConflictInfo),
 
@@ -32,6 +97,55 @@ pub enum ReservationError {
 
     #[error("Not Found Row")]
     NotFoundRow,
+
+    #[error("invalid group id: {0}")]
+    InvalidGroupId(String),
+
+    #[error("reservation group is empty")]
+    EmptyReservationGroup,
+
+    #[error("reservation group not found: {0}")]
+    GroupNotFound(String),
+
+    #[error("failed to reserve {} item(s) of the group", .0.len())]
+    GroupReservationFailed(Vec<ReservationItemError>),
+
+    #[error("reservation {id} is at version {actual}, expected {expected}")]
+    VersionMismatch { id: i64, expected: i64, actual: i64 },
+
+    #[error("invalid idempotency key: {0}")]
+    InvalidIdempotencyKey(String),
+
+    #[error("idempotency key is used by another call: {0}")]
+    IdempotencyKeyReused(String),
+
+    #[error("a call with the idempotency key is in progress: {0}")]
+    IdempotencyKeyInProgress(String),
+
+    #[error("invalid calendar feed kind: {0}")]
+    InvalidFeedKind(i32),
+
+    #[error("invalid calendar feed token")]
+    InvalidFeedToken,
+
+    #[error("calendar feed not found: {0}")]
+    FeedNotFound(i64),
+
+    #[error("caldav error: {0}")]
+    CalDavError(String),
+
+    #[error("invalid caldav sync token")]
+    InvalidSyncToken,
+
+    #[error("admin token required")]
+    AdminTokenRequired,
+}
+
+/// error for a single item of a reservation group
+#[derive(Debug, PartialEq)]
+pub struct ReservationItemError {
+    pub index: usize,
+    pub error: ReservationError,
 }
 
 impl PartialEq for ReservationError {
@@ -43,10 +157,58 @@ impl PartialEq for ReservationError {
             (Self::InvalidResourceId(v1), Self::InvalidResourceId(v2)) => v1 == v2,
             (Self::InvalidReservationId(v1), Self::InvalidReservationId(v2)) => v1 == v2,
             (Self::InvalidTimespan, Self::InvalidTimespan) => true,
+            (Self::InvalidStatus(v1), Self::InvalidStatus(v2)) => v1 == v2,
+            (Self::InvalidTransition(f1, t1), Self::InvalidTransition(f2, t2)) => {
+                f1 == f2 && t1 == t2
+            }
+            (Self::CancelWithStatus, Self::CancelWithStatus) => true,
+            (Self::InvalidUpdateMask(v1), Self::InvalidUpdateMask(v2)) => v1 == v2,
+            (Self::InvalidMatchMode(v1), Self::InvalidMatchMode(v2)) => v1 == v2,
+            (Self::InvalidPageSize(v1), Self::InvalidPageSize(v2)) => v1 == v2,
+            (Self::InvalidPageToken, Self::InvalidPageToken) => true,
+            (Self::InvalidSortField(v1), Self::InvalidSortField(v2)) => v1 == v2,
+            (Self::InvalidTag(v1), Self::InvalidTag(v2)) => v1 == v2,
+            (Self::InvalidAttribute(v1), Self::InvalidAttribute(v2)) => v1 == v2,
+            (Self::InvalidSearchQuery(v1), Self::InvalidSearchQuery(v2)) => v1 == v2,
+            (Self::InvalidAnalyticsWindow(v1), Self::InvalidAnalyticsWindow(v2)) => v1 == v2,
+            (Self::InvalidBucketSize(v1), Self::InvalidBucketSize(v2)) => v1 == v2,
+            (Self::InvalidTimeZone(v1), Self::InvalidTimeZone(v2)) => v1 == v2,
+            (Self::InvalidExportFormat(v1), Self::InvalidExportFormat(v2)) => v1 == v2,
+            (Self::ExportFailed(v1), Self::ExportFailed(v2)) => v1 == v2,
+            (Self::InvalidImportFormat(v1), Self::InvalidImportFormat(v2)) => v1 == v2,
+            (Self::InvalidImportMode(v1), Self::InvalidImportMode(v2)) => v1 == v2,
+            (Self::InvalidImportBatchSize(v1), Self::InvalidImportBatchSize(v2)) => v1 == v2,
+            (Self::InvalidImportData(v1), Self::InvalidImportData(v2)) => v1 == v2,
+            (Self::ImmutableField(v1), Self::ImmutableField(v2)) => v1 == v2,
             (Self::InvalidUserId(v1), Self::InvalidUserId(v2)) => v1 == v2,
             (Self::ReservationNotFound(v1), Self::ReservationNotFound(v2)) => v1 == v2,
             (Self::Unknown, Self::Unknown) => true,
             (Self::NotFoundRow, Self::NotFoundRow) => true,
+            (Self::InvalidGroupId(v1), Self::InvalidGroupId(v2)) => v1 == v2,
+            (Self::EmptyReservationGroup, Self::EmptyReservationGroup) => true,
+            (Self::GroupNotFound(v1), Self::GroupNotFound(v2)) => v1 == v2,
+            (Self::GroupReservationFailed(v1), Self::GroupReservationFailed(v2)) => v1 == v2,
+            (
+                Self::VersionMismatch {
+                    id: i1,
+                    expected: e1,
+                    actual: a1,
+                },
+                Self::VersionMismatch {
+                    id: i2,
+                    expected: e2,
+                    actual: a2,
+                },
+            ) => i1 == i2 && e1 == e2 && a1 == a2,
+            (Self::InvalidIdempotencyKey(v1), Self::InvalidIdempotencyKey(v2)) => v1 == v2,
+            (Self::IdempotencyKeyReused(v1), Self::IdempotencyKeyReused(v2)) => v1 == v2,
+            (Self::IdempotencyKeyInProgress(v1), Self::IdempotencyKeyInProgress(v2)) => v1 == v2,
+            (Self::InvalidFeedKind(v1), Self::InvalidFeedKind(v2)) => v1 == v2,
+            (Self::InvalidFeedToken, Self::InvalidFeedToken) => true,
+            (Self::FeedNotFound(v1), Self::FeedNotFound(v2)) => v1 == v2,
+            (Self::CalDavError(v1), Self::CalDavError(v2)) => v1 == v2,
+            (Self::InvalidSyncToken, Self::InvalidSyncToken) => true,
+            (Self::AdminTokenRequired, Self::AdminTokenRequired) => true,
             _ => false,
         }
     }
@@ -85,6 +247,69 @@ impl From<ReservationError> for tonic::Status {
             ReservationError::InvalidReservationId(v) => {
                 tonic::Status::invalid_argument(format!("invalid reservation id: {}", v))
             }
+            ReservationError::InvalidStatus(v) => {
+                tonic::Status::invalid_argument(format!("invalid reservation status: {}", v))
+            }
+            ReservationError::InvalidTransition(from, to) => tonic::Status::failed_precondition(
+                format!("invalid status transition: {} -> {}", from, to),
+            ),
+            ReservationError::CancelWithStatus => tonic::Status::invalid_argument(
+                "reservations are cancelled with cancel, not by a status change",
+            ),
+            ReservationError::InvalidUpdateMask(v) => {
+                tonic::Status::invalid_argument(format!("invalid update mask: {}", v))
+            }
+            ReservationError::InvalidMatchMode(v) => {
+                tonic::Status::invalid_argument(format!("invalid match mode: {}", v))
+            }
+            ReservationError::InvalidPageSize(v) => {
+                tonic::Status::invalid_argument(format!("invalid page size: {}", v))
+            }
+            ReservationError::InvalidPageToken => {
+                tonic::Status::invalid_argument("invalid page token")
+            }
+            ReservationError::InvalidSortField(v) => {
+                tonic::Status::invalid_argument(format!("invalid sort field: {}", v))
+            }
+            ReservationError::InvalidTag(v) => {
+                tonic::Status::invalid_argument(format!("invalid tag: {}", v))
+            }
+            ReservationError::InvalidAttribute(v) => {
+                tonic::Status::invalid_argument(format!("invalid attribute: {}", v))
+            }
+            ReservationError::InvalidSearchQuery(v) => {
+                tonic::Status::invalid_argument(format!("invalid search query: {}", v))
+            }
+            ReservationError::InvalidAnalyticsWindow(v) => {
+                tonic::Status::invalid_argument(format!("invalid analytics window: {}", v))
+            }
+            ReservationError::InvalidBucketSize(v) => {
+                tonic::Status::invalid_argument(format!("invalid bucket size: {}", v))
+            }
+            ReservationError::InvalidTimeZone(v) => {
+                tonic::Status::invalid_argument(format!("invalid time zone: {}", v))
+            }
+            ReservationError::InvalidExportFormat(v) => {
+                tonic::Status::invalid_argument(format!("invalid export format: {}", v))
+            }
+            ReservationError::ExportFailed(v) => {
+                tonic::Status::internal(format!("export failed: {}", v))
+            }
+            ReservationError::InvalidImportFormat(v) => {
+                tonic::Status::invalid_argument(format!("invalid import format: {}", v))
+            }
+            ReservationError::InvalidImportMode(v) => {
+                tonic::Status::invalid_argument(format!("invalid import mode: {}", v))
+            }
+            ReservationError::InvalidImportBatchSize(v) => {
+                tonic::Status::invalid_argument(format!("invalid import batch size: {}", v))
+            }
+            ReservationError::InvalidImportData(v) => {
+                tonic::Status::invalid_argument(format!("invalid import data: {}", v))
+            }
+            ReservationError::ImmutableField(v) => {
+                tonic::Status::invalid_argument(format!("field is immutable: {}", v))
+            }
             ReservationError::ConflictReservation(v) => {
                 tonic::Status::failed_precondition(format!("reservation conflict: {:?}", v))
             }
@@ -93,6 +318,55 @@ impl From<ReservationError> for tonic::Status {
                 tonic::Status::not_found(format!("reservation not found: {}", v))
             }
             ReservationError::NotFoundRow => tonic::Status::not_found("Not Found Row"),
+            ReservationError::InvalidGroupId(v) => {
+                tonic::Status::invalid_argument(format!("invalid group id: {}", v))
+            }
+            ReservationError::EmptyReservationGroup => {
+                tonic::Status::invalid_argument("reservation group is empty")
+            }
+            ReservationError::GroupNotFound(v) => {
+                tonic::Status::not_found(format!("reservation group not found: {}", v))
+            }
+            ReservationError::GroupReservationFailed(v) => tonic::Status::failed_precondition(
+                format!("failed to reserve {} item(s) of the group", v.len()),
+            ),
+            ReservationError::VersionMismatch {
+                id,
+                expected,
+                actual,
+            } => tonic::Status::aborted(format!(
+                "reservation {} is at version {}, expected {}",
+                id, actual, expected
+            )),
+            ReservationError::InvalidIdempotencyKey(v) => {
+                tonic::Status::invalid_argument(format!("invalid idempotency key: {}", v))
+            }
+            ReservationError::IdempotencyKeyReused(v) => tonic::Status::invalid_argument(format!(
+                "idempotency key is used by another call: {}",
+                v
+            )),
+            ReservationError::IdempotencyKeyInProgress(v) => tonic::Status::aborted(format!(
+                "a call with the idempotency key is in progress: {}",
+                v
+            )),
+            ReservationError::InvalidFeedKind(v) => {
+                tonic::Status::invalid_argument(format!("invalid calendar feed kind: {}", v))
+            }
+            ReservationError::InvalidFeedToken => {
+                tonic::Status::permission_denied("invalid calendar feed token")
+            }
+            ReservationError::FeedNotFound(v) => {
+                tonic::Status::not_found(format!("calendar feed not found: {}", v))
+            }
+            ReservationError::CalDavError(v) => {
+                tonic::Status::unavailable(format!("caldav error: {}", v))
+            }
+            ReservationError::InvalidSyncToken => {
+                tonic::Status::failed_precondition("invalid caldav sync token")
+            }
+            ReservationError::AdminTokenRequired => {
+                tonic::Status::permission_denied("admin token required")
+            }
         }
     }
 }
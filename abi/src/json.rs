@@ -0,0 +1,306 @@
+//! the protobuf json mapping of the fields serde can't derive, used by the attributes build.rs
+//! puts on the generated messages
+
+use crate::{
+    AnalyticsBucketSize, CalendarFeedKind, ExportFormat, ImportFormat, ImportMode, ImportRowStatus,
+    ReservationMatchMode, ReservationSortField, ReservationStatus, ReservationUpdateType,
+};
+use serde::{Deserialize, Deserializer};
+
+/// a generated enum, written as the name of its value
+pub trait ProtoEnum: Sized {
+    fn from_i32(value: i32) -> Option<Self>;
+    fn as_str_name(&self) -> &'static str;
+    fn from_str_name(value: &str) -> Option<Self>;
+    fn value(self) -> i32;
+}
+
+macro_rules! proto_enum {
+    ($($name:ident),*) => {
+        $(
+            impl ProtoEnum for $name {
+                fn from_i32(value: i32) -> Option<Self> {
+                    $name::from_i32(value)
+                }
+
+                fn as_str_name(&self) -> &'static str {
+                    $name::as_str_name(self)
+                }
+
+                fn from_str_name(value: &str) -> Option<Self> {
+                    $name::from_str_name(value)
+                }
+
+                fn value(self) -> i32 {
+                    self as i32
+                }
+            }
+        )*
+    };
+}
+
+proto_enum!(
+    ReservationStatus,
+    ReservationUpdateType,
+    ReservationMatchMode,
+    ReservationSortField,
+    AnalyticsBucketSize,
+    ExportFormat,
+    ImportFormat,
+    ImportMode,
+    ImportRowStatus,
+    CalendarFeedKind
+);
+
+/// the json name of a field
+pub(crate) fn camel_case(path: &str) -> String {
+    let mut upper = false;
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '_' => upper = true,
+            c if upper => {
+                out.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// an enum value may be given by name or by number
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnumValue {
+    Name(String),
+    Number(i32),
+    Null(()),
+}
+
+impl EnumValue {
+    fn into_i32<E: ProtoEnum, Err: serde::de::Error>(self) -> Result<i32, Err> {
+        match self {
+            EnumValue::Name(name) => E::from_str_name(&name)
+                .map(E::value)
+                .ok_or_else(|| Err::custom(format!("unknown enum value: {}", name))),
+            EnumValue::Number(value) => Ok(value),
+            EnumValue::Null(()) => Ok(0),
+        }
+    }
+}
+
+pub mod enumeration {
+    use super::*;
+    use serde::Serializer;
+
+    pub fn serialize<E: ProtoEnum, S: Serializer>(value: &i32, s: S) -> Result<S::Ok, S::Error> {
+        // values unknown to this version are kept as numbers
+        match E::from_i32(*value) {
+            Some(v) => s.serialize_str(v.as_str_name()),
+            None => s.serialize_i32(*value),
+        }
+    }
+
+    pub fn deserialize<'de, E: ProtoEnum, D: Deserializer<'de>>(d: D) -> Result<i32, D::Error> {
+        EnumValue::deserialize(d)?.into_i32::<E, _>()
+    }
+}
+
+pub mod enums {
+    use super::*;
+    use serde::{ser::SerializeSeq, Serializer};
+
+    pub fn serialize<E: ProtoEnum, S: Serializer>(values: &[i32], s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(values.len()))?;
+        for value in values {
+            match E::from_i32(*value) {
+                Some(v) => seq.serialize_element(v.as_str_name())?,
+                None => seq.serialize_element(value)?,
+            }
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, E: ProtoEnum, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Vec<i32>, D::Error> {
+        let values = Option::<Vec<EnumValue>>::deserialize(d)?.unwrap_or_default();
+        values.into_iter().map(|v| v.into_i32::<E, _>()).collect()
+    }
+}
+
+/// RFC 3339 in UTC, with 0, 3, 6 or 9 fractional digits
+pub mod timestamp {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use prost_types::Timestamp;
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    use crate::convert_to_timestamp;
+
+    pub fn serialize<S: Serializer>(ts: &Option<Timestamp>, s: S) -> Result<S::Ok, S::Error> {
+        let Some(ts) = ts else {
+            return s.serialize_none();
+        };
+        let time = DateTime::<Utc>::from_timestamp(ts.seconds, ts.nanos as u32)
+            .ok_or_else(|| S::Error::custom("timestamp out of range"))?;
+        s.serialize_str(&time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Timestamp>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(v) => DateTime::parse_from_rfc3339(&v)
+                .map(|time| Some(convert_to_timestamp(time.with_timezone(&Utc))))
+                .map_err(|e| D::Error::custom(format!("invalid timestamp {}: {}", v, e))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// the paths in camel case, separated by commas
+pub mod field_mask {
+    use prost_types::FieldMask;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::camel_case;
+
+    pub fn serialize<S: Serializer>(mask: &Option<FieldMask>, s: S) -> Result<S::Ok, S::Error> {
+        match mask {
+            Some(mask) => {
+                let paths: Vec<_> = mask.paths.iter().map(|p| camel_case(p)).collect();
+                s.serialize_str(&paths.join(","))
+            }
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<FieldMask>, D::Error> {
+        Ok(Option::<String>::deserialize(d)?.map(|v| FieldMask {
+            paths: v
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(snake_case)
+                .collect(),
+        }))
+    }
+
+    fn snake_case(path: &str) -> String {
+        let mut out = String::with_capacity(path.len() + 4);
+        for c in path.chars() {
+            if c.is_ascii_uppercase() {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        }
+        out
+    }
+}
+
+/// standard base64 with padding, url safe base64 and missing padding are accepted too
+pub mod bytes {
+    use base64::{
+        engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+        Engine,
+    };
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let v = Option::<String>::deserialize(d)?.unwrap_or_default();
+        let standard = v.trim_end_matches('=').replace('-', "+").replace('_', "/");
+        STANDARD_NO_PAD
+            .decode(standard)
+            .map_err(|e| D::Error::custom(format!("invalid base64: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::FieldMask;
+    use serde_json::json;
+
+    use crate::{
+        attribute_value, AttributeValue, ImportRequest, Reservation, ReservationQuery,
+        UpdateRequest,
+    };
+
+    use super::*;
+
+    #[test]
+    fn reservation_should_follow_protobuf_json_mapping() {
+        let mut rsvp = Reservation::new_pending(
+            "alice",
+            "room-1",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00.5-0700".parse().unwrap(),
+            "note",
+        );
+        rsvp.attributes.insert(
+            "seats".into(),
+            AttributeValue {
+                value: Some(attribute_value::Value::IntValue(12)),
+            },
+        );
+        let value = serde_json::to_value(&rsvp).unwrap();
+        assert_eq!(value["userId"], "alice");
+        assert_eq!(value["status"], "RESERVATION_STATUS_PENDING");
+        assert_eq!(value["start"], "2022-12-25T22:00:00Z");
+        assert_eq!(value["end"], "2022-12-28T19:00:00.500Z");
+        assert_eq!(value["attributes"]["seats"], json!({"intValue": 12}));
+        assert!(value.get("cancelledAt").is_none());
+
+        let back: Reservation = serde_json::from_value(value).unwrap();
+        assert_eq!(back, rsvp);
+    }
+
+    #[test]
+    fn json_should_accept_proto_names_numbers_and_missing_fields() {
+        let query: ReservationQuery = serde_json::from_value(json!({
+            "resource_id": "room-1",
+            "statuses": ["RESERVATION_STATUS_CONFIRMED", 3],
+            "start": "2022-12-25T15:00:00-07:00",
+        }))
+        .unwrap();
+        assert_eq!(query.resource_id, "room-1");
+        assert_eq!(
+            query.statuses,
+            [
+                ReservationStatus::Confirmed as i32,
+                ReservationStatus::Blocked as i32
+            ]
+        );
+        assert_eq!(query.start.unwrap().seconds, 1672005600);
+        assert_eq!(query.page_size, 0);
+
+        let err = serde_json::from_value::<ReservationQuery>(json!({"status": "BOOKED"}));
+        assert!(err.unwrap_err().to_string().contains("unknown enum value"));
+    }
+
+    #[test]
+    fn field_mask_and_bytes_should_be_strings() {
+        let req = UpdateRequest {
+            update_mask: Some(FieldMask {
+                paths: vec!["note".into(), "resource_id".into()],
+            }),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(value["updateMask"], "note,resourceId");
+        let back: UpdateRequest = serde_json::from_value(value).unwrap();
+        assert_eq!(back, req);
+
+        let req = ImportRequest {
+            data: b"a,b\n".to_vec(),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(value["data"], "YSxiCg==");
+        assert_eq!(value["format"], "IMPORT_FORMAT_CSV");
+        let back: ImportRequest = serde_json::from_value(json!({"data": "YSxiCg"})).unwrap();
+        assert_eq!(back.data, req.data);
+    }
+}
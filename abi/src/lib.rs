@@ -1,5 +1,7 @@
 mod config;
 mod error;
+mod json;
+mod openapi;
 #[allow(clippy::all, non_camel_case_types)]
 mod pb;
 mod types;
@@ -7,6 +9,7 @@ mod utils;
 
 pub use config::*;
 pub use error::*;
+pub use openapi::*;
 pub use pb::*;
 pub use types::*;
 pub use utils::*;
@@ -14,18 +17,41 @@ pub use utils::*;
 pub type ReservationId = i64;
 pub type UserId = String;
 pub type ResourceId = String;
+pub type GroupId = String;
 
 pub trait Validator {
     fn validate(&self) -> Result<(), ReservationError>;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
-#[sqlx(type_name = "reservation_status", rename_all = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, serde::Deserialize)]
+#[sqlx(type_name = "reservation_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum RsvpStatus {
     Unknown,
     Pending,
     Confirmed,
     Blocked,
+    CheckedIn,
+    Completed,
+    Cancelled,
+    Expired,
+    NoShow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "reservation_update_type", rename_all = "lowercase")]
+pub enum RsvpUpdateType {
+    Unknown,
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "feed_kind", rename_all = "lowercase")]
+pub enum RsvpFeedKind {
+    Resource,
+    User,
 }
 
 impl Validator for ReservationId {
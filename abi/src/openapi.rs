@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use prost::Message;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+};
+use serde_json::{json, Map, Value};
+
+use crate::json::camel_case;
+
+const DESCRIPTORS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/reservation.bin"));
+
+/// the metadata a call may carry, sent as http headers
+const HEADERS: [(&str, &str); 3] = [
+    (
+        "x-actor",
+        "the acting principal, recorded in the change log",
+    ),
+    (
+        "x-change-reason",
+        "the reason of the change, recorded in the change log",
+    ),
+    (
+        "x-idempotency-key",
+        "a retried call with the same key and request returns the response of the first successful call",
+    ),
+];
+
+/// calls which need the admin token, they are only served on the grpc port and are left out of
+/// the http api
+pub const ADMIN_METHODS: [&str; 6] = [
+    "purge",
+    "import",
+    "create_feed",
+    "revoke_feed",
+    "list_feeds",
+    "set_time_zone",
+];
+
+/// http path of a call of ReservationService
+pub fn rest_path(method: &str) -> String {
+    format!("/v1/{}", method)
+}
+
+/// OpenAPI 3 document of the http api, generated from the proto descriptors. Every unary call
+/// is a POST of its request message, a stream of bytes chunks is served as one binary body, and
+/// other streams and the admin calls are left out
+pub fn openapi() -> Value {
+    let set = FileDescriptorSet::decode(DESCRIPTORS).expect("descriptors are built by build.rs");
+    let file = set
+        .file
+        .iter()
+        .find(|file| file.package() == "reservation")
+        .expect("descriptors have the reservation package");
+    let comments = Comments::new(file);
+
+    let mut schemas = Map::new();
+    for (i, message) in file.message_type.iter().enumerate() {
+        let path = [4, i as i32];
+        schemas.insert(
+            message.name().to_string(),
+            message_schema(message, &comments, &path),
+        );
+    }
+    for (i, e) in file.enum_type.iter().enumerate() {
+        let names: Vec<_> = e.value.iter().map(|v| v.name()).collect();
+        let schema = json!({ "type": "string", "enum": names });
+        schemas.insert(
+            e.name().to_string(),
+            comments.describe(schema, &[5, i as i32]),
+        );
+    }
+    schemas.insert(
+        "Status".into(),
+        json!({
+            "type": "object",
+            "description": "error of a failed call",
+            "properties": {
+                "code": { "type": "integer", "format": "int32", "description": "the grpc status code" },
+                "message": { "type": "string" },
+            },
+        }),
+    );
+
+    let mut parameters = Map::new();
+    for (name, description) in HEADERS {
+        parameters.insert(
+            name.into(),
+            json!({
+                "name": name,
+                "in": "header",
+                "required": false,
+                "description": description,
+                "schema": { "type": "string" },
+            }),
+        );
+    }
+    let parameter_refs: Vec<_> = HEADERS
+        .iter()
+        .map(|(name, _)| json!({ "$ref": format!("#/components/parameters/{}", name) }))
+        .collect();
+
+    let service = &file.service[0];
+    let mut paths = Map::new();
+    for (i, method) in service.method.iter().enumerate() {
+        if method.client_streaming() || ADMIN_METHODS.contains(&method.name()) {
+            continue;
+        }
+        let content = match method.server_streaming() {
+            false => json!({ "application/json": { "schema": schema_ref(method.output_type()) } }),
+            true if is_chunk(file, method.output_type()) => {
+                json!({ "application/octet-stream": { "schema": { "type": "string", "format": "binary" } } })
+            }
+            true => continue,
+        };
+        let operation = json!({
+            "operationId": method.name(),
+            "tags": [service.name()],
+            "parameters": parameter_refs,
+            "requestBody": {
+                "required": true,
+                "content": { "application/json": { "schema": schema_ref(method.input_type()) } },
+            },
+            "responses": {
+                "200": { "description": type_name(method.output_type()), "content": content },
+                "default": {
+                    "description": "error",
+                    "content": { "application/json": { "schema": schema_ref(".reservation.Status") } },
+                },
+            },
+        });
+        paths.insert(
+            rest_path(method.name()),
+            json!({ "post": comments.describe(operation, &[6, 0, 2, i as i32]) }),
+        );
+    }
+
+    let info = json!({ "title": "reservation", "version": env!("CARGO_PKG_VERSION") });
+    json!({
+        "openapi": "3.0.3",
+        "info": comments.describe(info, &[6, 0]),
+        "paths": paths,
+        "components": { "schemas": schemas, "parameters": parameters },
+    })
+}
+
+/// leading comments of the descriptors, by their location path
+struct Comments(HashMap<Vec<i32>, String>);
+
+impl Comments {
+    fn new(file: &FileDescriptorProto) -> Self {
+        let locations = file
+            .source_code_info
+            .iter()
+            .flat_map(|info| &info.location)
+            .filter(|location| !location.leading_comments().is_empty());
+        Self(
+            locations
+                .map(|location| {
+                    let lines: Vec<_> = location
+                        .leading_comments()
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .collect();
+                    (location.path.clone(), lines.join("\n"))
+                })
+                .collect(),
+        )
+    }
+
+    fn describe(&self, mut schema: Value, path: &[i32]) -> Value {
+        if let Some(comment) = self.0.get(path) {
+            schema["description"] = comment.clone().into();
+        }
+        schema
+    }
+}
+
+fn message_schema(message: &DescriptorProto, comments: &Comments, path: &[i32]) -> Value {
+    let mut properties = Map::new();
+    for (i, field) in message.field.iter().enumerate() {
+        let path = [path, &[2, i as i32]].concat();
+        let mut schema = field_schema(message, field);
+        // siblings of a $ref are ignored, so a described reference is wrapped
+        if schema.get("$ref").is_some() && comments.0.contains_key(&path) {
+            schema = json!({ "allOf": [schema] });
+        }
+        properties.insert(camel_case(field.name()), comments.describe(schema, &path));
+    }
+    let schema = json!({ "type": "object", "properties": properties });
+    comments.describe(schema, path)
+}
+
+fn field_schema(message: &DescriptorProto, field: &FieldDescriptorProto) -> Value {
+    let schema = match field.r#type() {
+        Type::Double => json!({ "type": "number", "format": "double" }),
+        Type::Float => json!({ "type": "number", "format": "float" }),
+        Type::Int64 | Type::Sint64 | Type::Sfixed64 | Type::Uint64 | Type::Fixed64 => {
+            json!({ "type": "integer", "format": "int64" })
+        }
+        Type::Int32 | Type::Sint32 | Type::Sfixed32 | Type::Uint32 | Type::Fixed32 => {
+            json!({ "type": "integer", "format": "int32" })
+        }
+        Type::Bool => json!({ "type": "boolean" }),
+        Type::String => json!({ "type": "string" }),
+        Type::Bytes => json!({ "type": "string", "format": "byte" }),
+        Type::Enum => schema_ref(field.type_name()),
+        Type::Message | Type::Group => match field.type_name() {
+            ".google.protobuf.Timestamp" => json!({ "type": "string", "format": "date-time" }),
+            ".google.protobuf.FieldMask" => {
+                json!({ "type": "string", "description": "comma separated field paths" })
+            }
+            name => match map_entry(message, name) {
+                Some(entry) => {
+                    let value = field_schema(entry, &entry.field[1]);
+                    return json!({ "type": "object", "additionalProperties": value });
+                }
+                None => schema_ref(name),
+            },
+        },
+    };
+    match field.label() {
+        Label::Repeated => json!({ "type": "array", "items": schema }),
+        _ => schema,
+    }
+}
+
+/// the entry type of a map field, nested in the message of the field
+fn map_entry<'a>(message: &'a DescriptorProto, type_name: &str) -> Option<&'a DescriptorProto> {
+    let name = type_name.rsplit('.').next()?;
+    message.nested_type.iter().find(|t| {
+        t.name() == name
+            && t.options
+                .as_ref()
+                .is_some_and(|options| options.map_entry())
+    })
+}
+
+/// a message made of a single bytes field
+fn is_chunk(file: &FileDescriptorProto, full_name: &str) -> bool {
+    file.message_type
+        .iter()
+        .find(|m| m.name() == type_name(full_name))
+        .is_some_and(|m| m.field.len() == 1 && m.field[0].r#type() == Type::Bytes)
+}
+
+fn type_name(full_name: &str) -> &str {
+    full_name.rsplit('.').next().unwrap_or(full_name)
+}
+
+fn schema_ref(full_name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", type_name(full_name)) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openapi_should_cover_unary_calls_and_export() {
+        let doc = openapi();
+        assert_eq!(doc["openapi"], "3.0.3");
+        let paths = doc["paths"].as_object().unwrap();
+        assert_eq!(
+            paths["/v1/reserve"]["post"]["requestBody"]["content"]["application/json"]["schema"]
+                ["$ref"],
+            "#/components/schemas/ReserveRequest"
+        );
+        assert_eq!(
+            paths["/v1/get_history"]["post"]["description"],
+            "get the change history of a reservation"
+        );
+        assert!(paths["/v1/export"]["post"]["responses"]["200"]["content"]
+            .get("application/octet-stream")
+            .is_some());
+        assert!(paths.get("/v1/listen").is_none());
+        assert!(paths.get("/v1/purge").is_none());
+    }
+
+    #[test]
+    fn openapi_schemas_should_follow_json_mapping() {
+        let doc = openapi();
+        let schemas = &doc["components"]["schemas"];
+        let reservation = &schemas["Reservation"]["properties"];
+        assert_eq!(reservation["userId"]["type"], "string");
+        assert_eq!(reservation["start"]["format"], "date-time");
+        assert_eq!(
+            reservation["status"]["allOf"][0]["$ref"],
+            "#/components/schemas/ReservationStatus"
+        );
+        assert_eq!(
+            reservation["attributes"]["additionalProperties"]["$ref"],
+            "#/components/schemas/AttributeValue"
+        );
+        assert_eq!(
+            schemas["UpdateRequest"]["properties"]["updateMask"]["type"],
+            "string"
+        );
+        assert_eq!(
+            schemas["ReservationStatus"]["enum"][1],
+            "RESERVATION_STATUS_PENDING"
+        );
+    }
+}
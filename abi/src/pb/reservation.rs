@@ -1,5 +1,6 @@
 /// Core reservation object. Contains all the information for a reservation
-/// if ListenResponse op is DELETE, only id will be populated
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Reservation {
@@ -8,82 +9,343 @@ pub struct Reservation {
     pub id: i64,
     /// user id for the reservation
     #[prost(string, tag = "2")]
+    #[serde(alias = "user_id")]
     pub user_id: ::prost::alloc::string::String,
     /// reservation status, used for differentating purpose
     #[prost(enumeration = "ReservationStatus", tag = "3")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationStatus, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationStatus, _>"
+    )]
     pub status: i32,
     /// resource id for the reservation
     #[prost(string, tag = "4")]
+    #[serde(alias = "resource_id")]
     pub resource_id: ::prost::alloc::string::String,
     /// start time for the reservation
     #[prost(message, optional, tag = "5")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub start: ::core::option::Option<::prost_types::Timestamp>,
-    /// end time for the reservation
+    /// end time for the reservation, if empty, the reservation is open ended, e.g. an indefinite block
     #[prost(message, optional, tag = "6")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub end: ::core::option::Option<::prost_types::Timestamp>,
     /// extra note
     #[prost(string, tag = "7")]
     pub note: ::prost::alloc::string::String,
+    /// group id shared by the reservations made in one batch, empty otherwise
+    #[prost(string, tag = "8")]
+    #[serde(alias = "group_id")]
+    pub group_id: ::prost::alloc::string::String,
+    /// why the reservation was cancelled, only set when status is CANCELLED
+    #[prost(string, tag = "9")]
+    #[serde(alias = "cancel_reason")]
+    pub cancel_reason: ::prost::alloc::string::String,
+    /// who cancelled the reservation
+    #[prost(string, tag = "10")]
+    #[serde(alias = "cancelled_by")]
+    pub cancelled_by: ::prost::alloc::string::String,
+    /// when the reservation was cancelled
+    #[prost(message, optional, tag = "11")]
+    #[serde(
+        alias = "cancelled_at",
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cancelled_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// version of the reservation, starts from 1 and increases with every change
+    #[prost(int64, tag = "12")]
+    pub version: i64,
+    /// when the reservation was made
+    #[prost(message, optional, tag = "13")]
+    #[serde(
+        alias = "created_at",
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// free form labels, kept sorted and without duplicates
+    #[prost(string, repeated, tag = "14")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// typed metadata, e.g. cost center, attendee count or project code
+    #[prost(map = "string, message", tag = "15")]
+    pub attributes: ::std::collections::HashMap<::prost::alloc::string::String, AttributeValue>,
+}
+/// value of a reservation attribute
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttributeValue {
+    #[prost(oneof = "attribute_value::Value", tags = "1, 2, 3, 4")]
+    #[serde(flatten)]
+    pub value: ::core::option::Option<attribute_value::Value>,
+}
+/// Nested message and enum types in `AttributeValue`.
+pub mod attribute_value {
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Value {
+        #[prost(string, tag = "1")]
+        StringValue(::prost::alloc::string::String),
+        #[prost(int64, tag = "2")]
+        IntValue(i64),
+        #[prost(double, tag = "3")]
+        DoubleValue(f64),
+        #[prost(bool, tag = "4")]
+        BoolValue(bool),
+    }
+}
+/// condition on a reservation attribute, all the values set must hold
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttributeFilter {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    /// the attribute equals the value
+    #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eq: ::core::option::Option<AttributeValue>,
+    /// the attribute is at least the value, compared with values of the same type only
+    #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gte: ::core::option::Option<AttributeValue>,
+    /// the attribute is below the value, compared with values of the same type only
+    #[prost(message, optional, tag = "4")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lt: ::core::option::Option<AttributeValue>,
 }
 /// To make a reservation, send a ReservationRequest with Reservation object (id should be empty)
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReserveRequest {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
 }
 /// Created reservation will be returned in ReserveResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReserveResponse {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
 }
-/// To update a reservation, send an UpdateRequest. Only note is updatable.
+/// To reserve several resources at once, send a ReserveBatchRequest. Either all of them are reserved or none
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveBatchRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+/// why an item of a batch could not be reserved
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationFailure {
+    /// index of the failed item in ReserveBatchRequest.reservations
+    #[prost(int32, tag = "1")]
+    pub index: i32,
+    /// error message for the item
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+    /// the existing reservation window the item conflicts with, if known
+    #[prost(message, optional, tag = "3")]
+    #[serde(
+        alias = "conflict_start",
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_start: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "4")]
+    #[serde(
+        alias = "conflict_end",
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_end: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// Reserved reservations will be returned in ReserveBatchResponse.
+/// If any item failed, nothing is reserved and failures contains every failed item
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveBatchResponse {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "group_id")]
+    pub group_id: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+    #[prost(message, repeated, tag = "3")]
+    pub failures: ::prost::alloc::vec::Vec<ReservationFailure>,
+}
+/// To confirm or get all the reservations of a group, send a GroupRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupRequest {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "group_id")]
+    pub group_id: ::prost::alloc::string::String,
+}
+/// To cancel all the reservations of a group, send a CancelGroupRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelGroupRequest {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "group_id")]
+    pub group_id: ::prost::alloc::string::String,
+    /// why the reservations are cancelled
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+    /// who cancels the reservations
+    #[prost(string, tag = "3")]
+    pub actor: ::prost::alloc::string::String,
+}
+/// Reservations of the group will be returned in GroupResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupResponse {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "group_id")]
+    pub group_id: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+}
+/// To update a reservation, send an UpdateRequest with a partial Reservation and a field mask.
+/// Updatable paths: user_id, status, resource_id, start, end, note, tags, attributes. tags and
+/// attributes are replaced as a whole. id, version and created_at are immutable.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdateRequest {
     #[prost(int64, tag = "1")]
     pub id: i64,
-    #[prost(string, tag = "2")]
-    pub note: ::prost::alloc::string::String,
+    /// partial reservation, only the fields listed in update_mask are read
+    #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reservation: ::core::option::Option<Reservation>,
+    /// fields to update, e.g. ["note", "start", "end"]
+    #[prost(message, optional, tag = "4")]
+    #[serde(
+        alias = "update_mask",
+        with = "crate::json::field_mask",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub update_mask: ::core::option::Option<::prost_types::FieldMask>,
+    /// if not 0, the update is aborted when the reservation is at another version
+    #[prost(int64, tag = "5")]
+    #[serde(alias = "expected_version")]
+    pub expected_version: i64,
 }
 /// Updated reservation will be returned in UpdateResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdateResponse {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
 }
 /// To change a reservation from pending to confirmed, send a ConfirmRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ConfirmRequest {
     #[prost(int64, tag = "1")]
     pub id: i64,
+    /// if not 0, the confirmation is aborted when the reservation is at another version
+    #[prost(int64, tag = "2")]
+    #[serde(alias = "expected_version")]
+    pub expected_version: i64,
 }
 /// Confirmed reservation will be returned in ConfirmResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ConfirmResponse {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
 }
-/// To cancel a reservation, send a CancelRequest
+/// To cancel a reservation, send a CancelRequest. The reservation is kept with CANCELLED status
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CancelRequest {
     #[prost(int64, tag = "1")]
     pub id: i64,
+    /// why the reservation is cancelled
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+    /// who cancels the reservation
+    #[prost(string, tag = "3")]
+    pub actor: ::prost::alloc::string::String,
+    /// if not 0, the cancellation is aborted when the reservation is at another version
+    #[prost(int64, tag = "4")]
+    #[serde(alias = "expected_version")]
+    pub expected_version: i64,
 }
 /// Canceled reservation will be returned in CancelResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CancelResponse {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reservation: ::core::option::Option<Reservation>,
+}
+/// To remove a reservation physically, send a PurgeRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PurgeRequest {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+}
+/// Purged reservation will be returned in PurgeResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PurgeResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
 }
 /// To get a reservation, send a GetRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetRequest {
@@ -91,94 +353,241 @@ pub struct GetRequest {
     pub id: i64,
 }
 /// Reservation will be returned in GetResponse
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetResponse {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
 }
 /// query reservations with user id, resource id, start time, end time, and status
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[derive(derive_builder::Builder)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReservationQuery {
     /// resource id for the reservation query. If empty, query all resources
     #[prost(string, tag = "1")]
+    #[serde(alias = "resource_id")]
     #[builder(setter(into), default)]
     pub resource_id: ::prost::alloc::string::String,
     /// user id for the reservation query. If empty, query all users
     #[prost(string, tag = "2")]
+    #[serde(alias = "user_id")]
     #[builder(setter(into), default)]
     pub user_id: ::prost::alloc::string::String,
     /// use status to filter result. If UNKNOWN, return all reservations
     #[prost(enumeration = "ReservationStatus", tag = "3")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationStatus, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationStatus, _>"
+    )]
     #[builder(setter(into), default)]
     pub status: i32,
-    /// start time for the reservation query, if 0, use Infinity for start time
+    /// start time for the reservation query, if empty or 0, the query window has no start
     #[prost(message, optional, tag = "4")]
-    #[builder(setter(strip_option))]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[builder(setter(strip_option), default)]
     pub start: ::core::option::Option<::prost_types::Timestamp>,
-    /// end time for the reservation query, if 0, use Infinity for end time
+    /// end time for the reservation query, if empty or 0, the query window has no end
     #[prost(message, optional, tag = "5")]
-    #[builder(setter(strip_option))]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[builder(setter(strip_option), default)]
     pub end: ::core::option::Option<::prost_types::Timestamp>,
-    /// current page for the query
+    /// current page for the query, starting at 1. Ignored if page_token is set
     #[prost(int32, tag = "6")]
     #[builder(setter(into), default)]
     pub page: i32,
-    /// page size for the query
+    /// page size for the query, 10 if 0, at most 100
     #[prost(int32, tag = "7")]
+    #[serde(alias = "page_size")]
     #[builder(setter(into), default)]
     pub page_size: i32,
-    /// sort direction
+    /// sort direction, used when sort is empty
     #[prost(bool, tag = "8")]
     #[builder(setter(into), default)]
     pub desc: bool,
+    /// cancelled reservations are hidden unless include_cancelled is set or status is CANCELLED
+    #[prost(bool, tag = "9")]
+    #[serde(alias = "include_cancelled")]
+    #[builder(setter(into), default)]
+    pub include_cancelled: bool,
+    /// how reservations are matched against the query window, WITHIN by default
+    #[prost(enumeration = "ReservationMatchMode", tag = "10")]
+    #[serde(
+        alias = "match_mode",
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationMatchMode, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationMatchMode, _>"
+    )]
+    #[builder(setter(into), default)]
+    pub match_mode: i32,
+    /// more statuses to match, together with status. If all are empty or UNKNOWN, return all statuses
+    #[prost(enumeration = "ReservationStatus", repeated, tag = "11")]
+    #[serde(
+        serialize_with = "crate::json::enums::serialize::<crate::ReservationStatus, _>",
+        deserialize_with = "crate::json::enums::deserialize::<crate::ReservationStatus, _>"
+    )]
+    #[builder(setter(into), default)]
+    pub statuses: ::prost::alloc::vec::Vec<i32>,
+    /// more resource ids to match, together with resource_id
+    #[prost(string, repeated, tag = "12")]
+    #[serde(alias = "resource_ids")]
+    #[builder(setter(into), default)]
+    pub resource_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// more user ids to match, together with user_id
+    #[prost(string, repeated, tag = "13")]
+    #[serde(alias = "user_ids")]
+    #[builder(setter(into), default)]
+    pub user_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// next_page_token of the previous page, the query must be sent with the same sort
+    #[prost(string, tag = "14")]
+    #[serde(alias = "page_token")]
+    #[builder(setter(into), default)]
+    pub page_token: ::prost::alloc::string::String,
+    /// sort keys in order of precedence, ties are broken by id. If empty, sort by start in the
+    /// direction of desc
+    #[prost(message, repeated, tag = "15")]
+    #[builder(setter(into), default)]
+    pub sort: ::prost::alloc::vec::Vec<ReservationSort>,
+    /// the reservation must have all the tags
+    #[prost(string, repeated, tag = "16")]
+    #[builder(setter(into), default)]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// the reservation must match all the attribute conditions
+    #[prost(message, repeated, tag = "17")]
+    #[builder(setter(into), default)]
+    pub attributes: ::prost::alloc::vec::Vec<AttributeFilter>,
+}
+/// a sort key of a query
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationSort {
+    #[prost(enumeration = "ReservationSortField", tag = "1")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationSortField, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationSortField, _>"
+    )]
+    pub field: i32,
+    #[prost(bool, tag = "2")]
+    pub desc: bool,
 }
 /// To query reservations, send a QueryRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct QueryRequest {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub query: ::core::option::Option<ReservationQuery>,
 }
+/// a page of the query result
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+    /// token to get the next page, empty if this is the last page
+    #[prost(string, tag = "2")]
+    #[serde(alias = "next_page_token")]
+    pub next_page_token: ::prost::alloc::string::String,
+}
 /// query reservations, order by reservation id
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[derive(derive_builder::Builder)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ReservationFilter {
     /// resource id for the reservation query. If empty, query all resources
     #[prost(string, tag = "1")]
+    #[serde(alias = "resource_id")]
     #[builder(setter(into), default)]
     pub resource_id: ::prost::alloc::string::String,
     /// user id for the reservation query. If empty, query all users
     #[prost(string, tag = "2")]
+    #[serde(alias = "user_id")]
     #[builder(setter(into), default)]
     pub user_id: ::prost::alloc::string::String,
     /// use status to filter result. If UNKNOWN, return all reservations
     #[prost(enumeration = "ReservationStatus", tag = "3")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationStatus, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationStatus, _>"
+    )]
     #[builder(setter(into), default)]
     pub status: i32,
+    /// id of the reservation to start from, 0 to start from the first one in the sort direction
     #[prost(int64, tag = "4")]
     #[builder(setter(into), default)]
     pub cursor: i64,
-    /// page size for the query
+    /// page size for the query, 10 if 0, at most 100
     #[prost(int64, tag = "5")]
+    #[serde(alias = "page_size")]
     #[builder(setter(into), default)]
     pub page_size: i64,
     /// sort direction
     #[prost(bool, tag = "6")]
     #[builder(setter(into), default)]
     pub desc: bool,
+    /// cancelled reservations are hidden unless include_cancelled is set or status is CANCELLED
+    #[prost(bool, tag = "7")]
+    #[serde(alias = "include_cancelled")]
+    #[builder(setter(into), default)]
+    pub include_cancelled: bool,
+    /// more statuses to match, together with status. If all are empty or UNKNOWN, return all statuses
+    #[prost(enumeration = "ReservationStatus", repeated, tag = "8")]
+    #[serde(
+        serialize_with = "crate::json::enums::serialize::<crate::ReservationStatus, _>",
+        deserialize_with = "crate::json::enums::deserialize::<crate::ReservationStatus, _>"
+    )]
+    #[builder(setter(into), default)]
+    pub statuses: ::prost::alloc::vec::Vec<i32>,
+    /// more resource ids to match, together with resource_id
+    #[prost(string, repeated, tag = "9")]
+    #[serde(alias = "resource_ids")]
+    #[builder(setter(into), default)]
+    pub resource_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// more user ids to match, together with user_id
+    #[prost(string, repeated, tag = "10")]
+    #[serde(alias = "user_ids")]
+    #[builder(setter(into), default)]
+    pub user_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// the reservation must have all the tags
+    #[prost(string, repeated, tag = "11")]
+    #[builder(setter(into), default)]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// the reservation must match all the attribute conditions
+    #[prost(message, repeated, tag = "12")]
+    #[builder(setter(into), default)]
+    pub attributes: ::prost::alloc::vec::Vec<AttributeFilter>,
 }
 /// To query reservations, send a QueryRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FilterRequest {
     #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub filter: ::core::option::Option<ReservationFilter>,
 }
 /// filter page info
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FilterPager {
@@ -189,30 +598,525 @@ pub struct FilterPager {
     #[prost(int32, tag = "3")]
     pub total: i32,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FilterResponse {
     #[prost(message, repeated, tag = "1")]
     pub reservations: ::prost::alloc::vec::Vec<Reservation>,
     #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pager: ::core::option::Option<FilterPager>,
 }
+/// full text search over the notes, tags and string attribute values of reservations
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationSearch {
+    /// words to search for in web search syntax: "quoted phrases", OR, and -word to exclude.
+    /// chinese, japanese and korean words match anywhere in the text and must all be found
+    #[prost(string, tag = "1")]
+    pub query: ::prost::alloc::string::String,
+    /// current page, starting at 1
+    #[prost(int32, tag = "2")]
+    pub page: i32,
+    /// page size, 10 if 0, at most 100
+    #[prost(int32, tag = "3")]
+    #[serde(alias = "page_size")]
+    pub page_size: i32,
+    /// cancelled reservations are hidden unless include_cancelled is set
+    #[prost(bool, tag = "4")]
+    #[serde(alias = "include_cancelled")]
+    pub include_cancelled: bool,
+}
+/// To search reservations, send a SearchRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchRequest {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: ::core::option::Option<ReservationSearch>,
+}
+/// a reservation matching the search
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResult {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reservation: ::core::option::Option<Reservation>,
+    /// relevance, higher is better. A match in the note ranks above one in tags or attributes
+    #[prost(float, tag = "2")]
+    pub rank: f32,
+    /// html escaped part of the note around the first match, matched words are wrapped in <b></b>
+    #[prost(string, tag = "3")]
+    pub snippet: ::prost::alloc::string::String,
+}
+/// results of a search, best match first
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<SearchResult>,
+}
+/// utilization of resources in a time window, reported per resource and bucket
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnalyticsQuery {
+    /// resources to report on. If empty, every resource with a reservation in the window
+    #[prost(string, repeated, tag = "1")]
+    #[serde(alias = "resource_ids")]
+    pub resource_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// start of the window, required
+    #[prost(message, optional, tag = "2")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub start: ::core::option::Option<::prost_types::Timestamp>,
+    /// end of the window, required, at most 400 days after start
+    #[prost(message, optional, tag = "3")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end: ::core::option::Option<::prost_types::Timestamp>,
+    /// DAY by default
+    #[prost(enumeration = "AnalyticsBucketSize", tag = "4")]
+    #[serde(
+        alias = "bucket_size",
+        serialize_with = "crate::json::enumeration::serialize::<crate::AnalyticsBucketSize, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::AnalyticsBucketSize, _>"
+    )]
+    pub bucket_size: i32,
+    /// IANA time zone the buckets and hours of day are aligned to, UTC if empty
+    #[prost(string, tag = "5")]
+    #[serde(alias = "time_zone")]
+    pub time_zone: ::prost::alloc::string::String,
+}
+/// To get utilization analytics, send an AnalyticsRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnalyticsRequest {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: ::core::option::Option<AnalyticsQuery>,
+}
+/// usage of a resource in a bucket, the first and last bucket are cut to the window
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnalyticsBucket {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "resource_id")]
+    pub resource_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub start: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "3")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end: ::core::option::Option<::prost_types::Timestamp>,
+    /// minutes of the bucket covered by reservations which aren't cancelled
+    #[prost(int64, tag = "4")]
+    #[serde(alias = "booked_minutes")]
+    pub booked_minutes: i64,
+    /// booked minutes as a percentage of the bucket length
+    #[prost(double, tag = "5")]
+    pub utilization: f64,
+    /// reservations overlapping the bucket which aren't cancelled
+    #[prost(int64, tag = "6")]
+    pub reservations: i64,
+    /// reservations overlapping the bucket which were cancelled, including purged ones
+    #[prost(int64, tag = "7")]
+    pub cancelled: i64,
+    /// cancelled as a fraction of all reservations overlapping the bucket, 0 if there are none
+    #[prost(double, tag = "8")]
+    #[serde(alias = "cancellation_rate")]
+    pub cancellation_rate: f64,
+    /// hour of day in the time zone with the most booked minutes, -1 if nothing is booked
+    #[prost(int32, tag = "9")]
+    #[serde(alias = "peak_hour")]
+    pub peak_hour: i32,
+}
+/// buckets ordered by resource id and start
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnalyticsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub buckets: ::prost::alloc::vec::Vec<AnalyticsBucket>,
+}
+/// To export reservations as a file, send an ExportRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportRequest {
+    /// reservations to export, in the order of its sort. page, page_size and page_token are ignored,
+    /// every matching reservation is exported
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: ::core::option::Option<ReservationQuery>,
+    /// CSV by default
+    #[prost(enumeration = "ExportFormat", tag = "2")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ExportFormat, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ExportFormat, _>"
+    )]
+    pub format: i32,
+}
+/// a piece of an exported file, the file is the concatenation of the chunks in order
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportChunk {
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(with = "crate::json::bytes")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// To load reservations from a file, send an ImportRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportRequest {
+    /// content of the file
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(with = "crate::json::bytes")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+    /// CSV by default
+    #[prost(enumeration = "ImportFormat", tag = "2")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ImportFormat, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ImportFormat, _>"
+    )]
+    pub format: i32,
+    /// ALL_OR_NOTHING by default
+    #[prost(enumeration = "ImportMode", tag = "3")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ImportMode, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ImportMode, _>"
+    )]
+    pub mode: i32,
+    /// only check the rows, nothing is imported
+    #[prost(bool, tag = "4")]
+    #[serde(alias = "dry_run")]
+    pub dry_run: bool,
+    /// rows checked against the database per query, and inserted per transaction when skipping
+    /// invalid rows. 1000 if 0, at most 10000
+    #[prost(int32, tag = "5")]
+    #[serde(alias = "batch_size")]
+    pub batch_size: i32,
+    /// user of the rows without one
+    #[prost(string, tag = "6")]
+    #[serde(alias = "user_id")]
+    pub user_id: ::prost::alloc::string::String,
+    /// resource of the rows without one
+    #[prost(string, tag = "7")]
+    #[serde(alias = "resource_id")]
+    pub resource_id: ::prost::alloc::string::String,
+}
+/// report of a row of an import
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportRowReport {
+    /// position of the row in the file, the first row after the csv header is 1
+    #[prost(int32, tag = "1")]
+    pub row: i32,
+    #[prost(enumeration = "ImportRowStatus", tag = "2")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ImportRowStatus, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ImportRowStatus, _>"
+    )]
+    pub status: i32,
+    /// why the row is invalid or conflicts
+    #[prost(string, tag = "3")]
+    pub reason: ::prost::alloc::string::String,
+    /// id of the imported reservation, 0 if the row is not imported
+    #[prost(int64, tag = "4")]
+    #[serde(alias = "reservation_id")]
+    pub reservation_id: i64,
+    /// the earlier row a CONFLICT_IN_FILE row overlaps
+    #[prost(int32, tag = "5")]
+    #[serde(alias = "conflict_row")]
+    pub conflict_row: i32,
+    /// id of the reservation a CONFLICT row overlaps
+    #[prost(int64, tag = "6")]
+    #[serde(alias = "conflict_id")]
+    pub conflict_id: i64,
+}
+/// a report for every row of the file, in file order
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub rows: ::prost::alloc::vec::Vec<ImportRowReport>,
+    /// number of imported reservations
+    #[prost(int32, tag = "2")]
+    pub imported: i32,
+    /// number of invalid or conflicting rows
+    #[prost(int32, tag = "3")]
+    pub failed: i32,
+}
+/// a live icalendar feed of the reservations of a resource or a user, served over http at
+/// /feeds/<token>.ics. Cancelled reservations are left out
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CalendarFeed {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(enumeration = "CalendarFeedKind", tag = "2")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::CalendarFeedKind, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::CalendarFeedKind, _>"
+    )]
+    pub kind: i32,
+    /// the resource id or user id of the feed
+    #[prost(string, tag = "3")]
+    pub subject: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    #[serde(
+        alias = "created_at",
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// set once the feed is revoked, its token is not accepted anymore
+    #[prost(message, optional, tag = "5")]
+    #[serde(
+        alias = "revoked_at",
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub revoked_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// To create a calendar feed, send a CreateFeedRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateFeedRequest {
+    #[prost(enumeration = "CalendarFeedKind", tag = "1")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::CalendarFeedKind, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::CalendarFeedKind, _>"
+    )]
+    pub kind: i32,
+    #[prost(string, tag = "2")]
+    pub subject: ::prost::alloc::string::String,
+}
+/// the token is only returned here, the server keeps a hash of it
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateFeedResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: ::core::option::Option<CalendarFeed>,
+    #[prost(string, tag = "2")]
+    pub token: ::prost::alloc::string::String,
+    /// path of the feed on the http server
+    #[prost(string, tag = "3")]
+    pub path: ::prost::alloc::string::String,
+}
+/// To revoke a calendar feed, send a RevokeFeedRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokeFeedRequest {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+}
+/// a revoked feed stays revoked, revoking it again returns it as is
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokeFeedResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: ::core::option::Option<CalendarFeed>,
+}
+/// To list calendar feeds, send a ListFeedsRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListFeedsRequest {
+    /// feeds of this resource or user, all feeds if empty
+    #[prost(string, tag = "1")]
+    pub subject: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    #[serde(alias = "include_revoked")]
+    pub include_revoked: bool,
+}
+/// feeds in creation order
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListFeedsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub feeds: ::prost::alloc::vec::Vec<CalendarFeed>,
+}
+/// To set the time zone of a resource, send a SetTimeZoneRequest. The local times of the exports
+/// and calendars of the resource are in its time zone, UTC until one is set
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetTimeZoneRequest {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "resource_id")]
+    pub resource_id: ::prost::alloc::string::String,
+    /// an IANA time zone name, e.g. Asia/Shanghai
+    #[prost(string, tag = "2")]
+    #[serde(alias = "time_zone")]
+    pub time_zone: ::prost::alloc::string::String,
+}
+/// the time zone now set for the resource
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetTimeZoneResponse {
+    #[prost(string, tag = "1")]
+    #[serde(alias = "resource_id")]
+    pub resource_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    #[serde(alias = "time_zone")]
+    pub time_zone: ::prost::alloc::string::String,
+}
 /// Client can listen to reservation updates by sending a ListenRequest
+/// To listen to the changes of reservations, send a ListenRequest. A change passes the filters if the
+/// reservation matches them before or after the change
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ListenRequest {}
+pub struct ListenRequest {
+    /// resources to listen to, all resources if empty
+    #[prost(string, repeated, tag = "1")]
+    #[serde(alias = "resource_ids")]
+    pub resource_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// users to listen to, all users if empty
+    #[prost(string, repeated, tag = "2")]
+    #[serde(alias = "user_ids")]
+    pub user_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// resume after the change at this position, only new changes are sent if 0
+    #[prost(int64, tag = "3")]
+    pub after: i64,
+}
 /// Server will send ListenResponse to client in streaming response
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListenResponse {
     /// update type
     #[prost(enumeration = "ReservationUpdateType", tag = "1")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationUpdateType, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationUpdateType, _>"
+    )]
     pub op: i32,
-    /// id for updated reservation
+    /// the reservation after the change, or before it for DELETE
     #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reservation: ::core::option::Option<Reservation>,
+    /// position of the change in the change log. Changes are sent in the order of their positions,
+    /// one whose transaction has not committed yet holds back the changes after it
+    #[prost(int64, tag = "3")]
+    pub seq: i64,
+}
+/// A change of a reservation, decoded from the change log
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationChange {
+    /// change type
+    #[prost(enumeration = "ReservationUpdateType", tag = "1")]
+    #[serde(
+        serialize_with = "crate::json::enumeration::serialize::<crate::ReservationUpdateType, _>",
+        deserialize_with = "crate::json::enumeration::deserialize::<crate::ReservationUpdateType, _>"
+    )]
+    pub op: i32,
+    /// reservation before the change, empty for CREATE
+    #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: ::core::option::Option<Reservation>,
+    /// reservation after the change, empty for DELETE
+    #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: ::core::option::Option<Reservation>,
+    /// names of the reservation fields changed by an UPDATE
+    #[prost(string, repeated, tag = "4")]
+    #[serde(alias = "changed_fields")]
+    pub changed_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// who made the change, empty if unknown
+    #[prost(string, tag = "5")]
+    pub actor: ::prost::alloc::string::String,
+    /// when the change was made
+    #[prost(message, optional, tag = "6")]
+    #[serde(
+        with = "crate::json::timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub at: ::core::option::Option<::prost_types::Timestamp>,
+    /// why the change was made, empty if not given
+    #[prost(string, tag = "7")]
+    pub reason: ::prost::alloc::string::String,
+}
+/// To get the change history of a reservation, send a GetHistoryRequest
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetHistoryRequest {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+}
+/// Changes of the reservation will be returned in GetHistoryResponse, oldest first
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetHistoryResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<ReservationChange>,
 }
-/// reservation status for a given time period
+/// reservation status for a given time period.
+/// lifecycle: pending -> confirmed -> checked_in -> completed,
+/// pending -> cancelled/expired, confirmed -> cancelled/no_show, blocked -> cancelled
 #[derive(
     sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration,
 )]
@@ -222,6 +1126,11 @@ pub enum ReservationStatus {
     Pending = 1,
     Confirmed = 2,
     Blocked = 3,
+    CheckedIn = 4,
+    Completed = 5,
+    Cancelled = 6,
+    Expired = 7,
+    NoShow = 8,
 }
 impl ReservationStatus {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -234,6 +1143,11 @@ impl ReservationStatus {
             ReservationStatus::Pending => "RESERVATION_STATUS_PENDING",
             ReservationStatus::Confirmed => "RESERVATION_STATUS_CONFIRMED",
             ReservationStatus::Blocked => "RESERVATION_STATUS_BLOCKED",
+            ReservationStatus::CheckedIn => "RESERVATION_STATUS_CHECKED_IN",
+            ReservationStatus::Completed => "RESERVATION_STATUS_COMPLETED",
+            ReservationStatus::Cancelled => "RESERVATION_STATUS_CANCELLED",
+            ReservationStatus::Expired => "RESERVATION_STATUS_EXPIRED",
+            ReservationStatus::NoShow => "RESERVATION_STATUS_NO_SHOW",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -243,6 +1157,11 @@ impl ReservationStatus {
             "RESERVATION_STATUS_PENDING" => Some(Self::Pending),
             "RESERVATION_STATUS_CONFIRMED" => Some(Self::Confirmed),
             "RESERVATION_STATUS_BLOCKED" => Some(Self::Blocked),
+            "RESERVATION_STATUS_CHECKED_IN" => Some(Self::CheckedIn),
+            "RESERVATION_STATUS_COMPLETED" => Some(Self::Completed),
+            "RESERVATION_STATUS_CANCELLED" => Some(Self::Cancelled),
+            "RESERVATION_STATUS_EXPIRED" => Some(Self::Expired),
+            "RESERVATION_STATUS_NO_SHOW" => Some(Self::NoShow),
             _ => None,
         }
     }
@@ -280,39 +1199,313 @@ impl ReservationUpdateType {
         }
     }
 }
-/// Generated client implementations.
-pub mod reservation_service_client {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::http::Uri;
-    use tonic::codegen::*;
-    /// Reservation service
-    #[derive(Debug, Clone)]
-    pub struct ReservationServiceClient<T> {
-        inner: tonic::client::Grpc<T>,
-    }
-    impl ReservationServiceClient<tonic::transport::Channel> {
-        /// Attempt to create a new client by connecting to a given endpoint.
-        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
-        where
-            D: std::convert::TryInto<tonic::transport::Endpoint>,
-            D::Error: Into<StdError>,
-        {
-            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
-            Ok(Self::new(conn))
+/// how reservations are matched against the time window of a query
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ReservationMatchMode {
+    /// the reservation lies within the query window
+    Within = 0,
+    /// the reservation overlaps the query window
+    Overlaps = 1,
+    /// the reservation contains the whole query window
+    Contains = 2,
+    /// the reservation starts within the query window
+    StartsIn = 3,
+}
+impl ReservationMatchMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ReservationMatchMode::Within => "RESERVATION_MATCH_MODE_WITHIN",
+            ReservationMatchMode::Overlaps => "RESERVATION_MATCH_MODE_OVERLAPS",
+            ReservationMatchMode::Contains => "RESERVATION_MATCH_MODE_CONTAINS",
+            ReservationMatchMode::StartsIn => "RESERVATION_MATCH_MODE_STARTS_IN",
         }
     }
-    impl<T> ReservationServiceClient<T>
-    where
-        T: tonic::client::GrpcService<tonic::body::BoxBody>,
-        T::Error: Into<StdError>,
-        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
-        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
-    {
-        pub fn new(inner: T) -> Self {
-            let inner = tonic::client::Grpc::new(inner);
-            Self { inner }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "RESERVATION_MATCH_MODE_WITHIN" => Some(Self::Within),
+            "RESERVATION_MATCH_MODE_OVERLAPS" => Some(Self::Overlaps),
+            "RESERVATION_MATCH_MODE_CONTAINS" => Some(Self::Contains),
+            "RESERVATION_MATCH_MODE_STARTS_IN" => Some(Self::StartsIn),
+            _ => None,
         }
-        pub fn with_origin(inner: T, origin: Uri) -> Self {
+    }
+}
+/// fields the result of a query could be sorted by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ReservationSortField {
+    Start = 0,
+    /// reservations without end come after all others in ascending order
+    End = 1,
+    CreatedAt = 2,
+    UserId = 3,
+    ResourceId = 4,
+    /// in the order of the lifecycle, as ReservationStatus is declared
+    Status = 5,
+}
+impl ReservationSortField {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ReservationSortField::Start => "RESERVATION_SORT_FIELD_START",
+            ReservationSortField::End => "RESERVATION_SORT_FIELD_END",
+            ReservationSortField::CreatedAt => "RESERVATION_SORT_FIELD_CREATED_AT",
+            ReservationSortField::UserId => "RESERVATION_SORT_FIELD_USER_ID",
+            ReservationSortField::ResourceId => "RESERVATION_SORT_FIELD_RESOURCE_ID",
+            ReservationSortField::Status => "RESERVATION_SORT_FIELD_STATUS",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "RESERVATION_SORT_FIELD_START" => Some(Self::Start),
+            "RESERVATION_SORT_FIELD_END" => Some(Self::End),
+            "RESERVATION_SORT_FIELD_CREATED_AT" => Some(Self::CreatedAt),
+            "RESERVATION_SORT_FIELD_USER_ID" => Some(Self::UserId),
+            "RESERVATION_SORT_FIELD_RESOURCE_ID" => Some(Self::ResourceId),
+            "RESERVATION_SORT_FIELD_STATUS" => Some(Self::Status),
+            _ => None,
+        }
+    }
+}
+/// length of the buckets of an analytics report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum AnalyticsBucketSize {
+    Day = 0,
+    Hour = 1,
+    /// weeks start on monday
+    Week = 2,
+}
+impl AnalyticsBucketSize {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            AnalyticsBucketSize::Day => "ANALYTICS_BUCKET_SIZE_DAY",
+            AnalyticsBucketSize::Hour => "ANALYTICS_BUCKET_SIZE_HOUR",
+            AnalyticsBucketSize::Week => "ANALYTICS_BUCKET_SIZE_WEEK",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ANALYTICS_BUCKET_SIZE_DAY" => Some(Self::Day),
+            "ANALYTICS_BUCKET_SIZE_HOUR" => Some(Self::Hour),
+            "ANALYTICS_BUCKET_SIZE_WEEK" => Some(Self::Week),
+            _ => None,
+        }
+    }
+}
+/// file format of an export
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ExportFormat {
+    Csv = 0,
+    Parquet = 1,
+    /// an RFC 5545 VCALENDAR with an event per reservation, its UID is <id>@reservation. Times are
+    /// local to the resource, the VTIMEZONEs come after the events
+    Icalendar = 2,
+}
+impl ExportFormat {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "EXPORT_FORMAT_CSV",
+            ExportFormat::Parquet => "EXPORT_FORMAT_PARQUET",
+            ExportFormat::Icalendar => "EXPORT_FORMAT_ICALENDAR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "EXPORT_FORMAT_CSV" => Some(Self::Csv),
+            "EXPORT_FORMAT_PARQUET" => Some(Self::Parquet),
+            "EXPORT_FORMAT_ICALENDAR" => Some(Self::Icalendar),
+            _ => None,
+        }
+    }
+}
+/// file format of an import
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ImportFormat {
+    /// a header row names the columns: user_id, resource_id and start_utc are required, status,
+    /// end_utc, note, tags (a json array) and attributes (a json object) are optional. Other columns
+    /// are ignored, so an export could be imported again
+    Csv = 0,
+    /// an array of objects with the keys of the csv columns, tags and attributes as json values
+    Json = 1,
+    /// the VEVENTs of an RFC 5545 file: LOCATION is the resource, X-RESERVATION-USER-ID the user,
+    /// DESCRIPTION or SUMMARY the note and CATEGORIES the tags. Recurring events are rejected
+    Icalendar = 2,
+}
+impl ImportFormat {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "IMPORT_FORMAT_CSV",
+            ImportFormat::Json => "IMPORT_FORMAT_JSON",
+            ImportFormat::Icalendar => "IMPORT_FORMAT_ICALENDAR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "IMPORT_FORMAT_CSV" => Some(Self::Csv),
+            "IMPORT_FORMAT_JSON" => Some(Self::Json),
+            "IMPORT_FORMAT_ICALENDAR" => Some(Self::Icalendar),
+            _ => None,
+        }
+    }
+}
+/// what happens to the valid rows of an import when some rows are invalid or conflict
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ImportMode {
+    /// nothing is imported unless every row could be
+    AllOrNothing = 0,
+    /// the invalid and conflicting rows are skipped, the others are imported
+    SkipInvalid = 1,
+}
+impl ImportMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ImportMode::AllOrNothing => "IMPORT_MODE_ALL_OR_NOTHING",
+            ImportMode::SkipInvalid => "IMPORT_MODE_SKIP_INVALID",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "IMPORT_MODE_ALL_OR_NOTHING" => Some(Self::AllOrNothing),
+            "IMPORT_MODE_SKIP_INVALID" => Some(Self::SkipInvalid),
+            _ => None,
+        }
+    }
+}
+/// outcome of a row of an import
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ImportRowStatus {
+    /// the row is valid and free of conflicts
+    Ok = 0,
+    /// the row could not be parsed or failed validation
+    Invalid = 1,
+    /// the row overlaps an earlier row of the file on the same resource
+    ConflictInFile = 2,
+    /// the row overlaps a reservation in the database
+    Conflict = 3,
+}
+impl ImportRowStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ImportRowStatus::Ok => "IMPORT_ROW_STATUS_OK",
+            ImportRowStatus::Invalid => "IMPORT_ROW_STATUS_INVALID",
+            ImportRowStatus::ConflictInFile => "IMPORT_ROW_STATUS_CONFLICT_IN_FILE",
+            ImportRowStatus::Conflict => "IMPORT_ROW_STATUS_CONFLICT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "IMPORT_ROW_STATUS_OK" => Some(Self::Ok),
+            "IMPORT_ROW_STATUS_INVALID" => Some(Self::Invalid),
+            "IMPORT_ROW_STATUS_CONFLICT_IN_FILE" => Some(Self::ConflictInFile),
+            "IMPORT_ROW_STATUS_CONFLICT" => Some(Self::Conflict),
+            _ => None,
+        }
+    }
+}
+/// whose reservations a calendar feed shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CalendarFeedKind {
+    Resource = 0,
+    User = 1,
+}
+impl CalendarFeedKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CalendarFeedKind::Resource => "CALENDAR_FEED_KIND_RESOURCE",
+            CalendarFeedKind::User => "CALENDAR_FEED_KIND_USER",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CALENDAR_FEED_KIND_RESOURCE" => Some(Self::Resource),
+            "CALENDAR_FEED_KIND_USER" => Some(Self::User),
+            _ => None,
+        }
+    }
+}
+/// Generated client implementations.
+pub mod reservation_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::http::Uri;
+    use tonic::codegen::*;
+    /// Reservation service. For mutating calls, clients could send the acting principal in the
+    /// `x-actor` metadata and the reason of the change in `x-change-reason`, both are recorded in the change log.
+    /// A retried mutating call could carry the same `x-idempotency-key` metadata, the response of the first
+    /// successful call with the key is returned instead of making the call again. A key is for one request,
+    /// the key with another request is rejected
+    #[derive(Debug, Clone)]
+    pub struct ReservationServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl ReservationServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: std::convert::TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> ReservationServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
             let inner = tonic::client::Grpc::with_origin(inner, origin);
             Self { inner }
         }
@@ -365,7 +1558,7 @@ pub mod reservation_service_client {
                 http::uri::PathAndQuery::from_static("/reservation.ReservationService/reserve");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// confirm a pending reservation, if reservation is not pending, do nothing
+        /// confirm a pending reservation, a confirmed one is left alone, other status are rejected
         pub async fn confirm(
             &mut self,
             request: impl tonic::IntoRequest<super::ConfirmRequest>,
@@ -381,11 +1574,261 @@ pub mod reservation_service_client {
                 http::uri::PathAndQuery::from_static("/reservation.ReservationService/confirm");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// update the reservation note
-        pub async fn update(
+        /// update the fields of a reservation listed in the field mask, status changes must follow the lifecycle and cancel is the only way to CANCELLED
+        pub async fn update(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateRequest>,
+        ) -> Result<tonic::Response<super::UpdateResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/update");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// cancel a reservation, the reservation is kept with CANCELLED status
+        pub async fn cancel(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelRequest>,
+        ) -> Result<tonic::Response<super::CancelResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/cancel");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// remove a reservation physically, for admin only
+        pub async fn purge(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PurgeRequest>,
+        ) -> Result<tonic::Response<super::PurgeResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/purge");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// get a reservation by id
+        pub async fn get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRequest>,
+        ) -> Result<tonic::Response<super::GetResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/reservation.ReservationService/get");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// get the change history of a reservation
+        pub async fn get_history(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetHistoryRequest>,
+        ) -> Result<tonic::Response<super::GetHistoryResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/get_history");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// reserve several resources atomically under a shared group id
+        pub async fn reserve_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReserveBatchRequest>,
+        ) -> Result<tonic::Response<super::ReserveBatchResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/reserve_batch",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// confirm all pending reservations of a group
+        pub async fn confirm_group(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GroupRequest>,
+        ) -> Result<tonic::Response<super::GroupResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/confirm_group",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// cancel all reservations of a group
+        pub async fn cancel_group(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelGroupRequest>,
+        ) -> Result<tonic::Response<super::GroupResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/cancel_group",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// get all reservations of a group
+        pub async fn get_group(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GroupRequest>,
+        ) -> Result<tonic::Response<super::GroupResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/get_group");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// query reservations by resource id, user id, status, start time, end time
+        pub async fn query(
+            &mut self,
+            request: impl tonic::IntoRequest<super::QueryRequest>,
+        ) -> Result<tonic::Response<super::QueryResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/query");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// stream all reservations matching a query in the order of its sort, read page by page on the
+        /// server. page and page_size are ignored, the stream starts after page_token if it is set
+        pub async fn query_stream(
+            &mut self,
+            request: impl tonic::IntoRequest<super::QueryRequest>,
+        ) -> Result<tonic::Response<tonic::codec::Streaming<super::Reservation>>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/query_stream",
+            );
+            self.inner
+                .server_streaming(request.into_request(), path, codec)
+                .await
+        }
+        /// filter reservations, order by reservation id
+        pub async fn filter(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FilterRequest>,
+        ) -> Result<tonic::Response<super::FilterResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/filter");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// full text search over notes, tags and attribute values, best match first
+        pub async fn search(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/search");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// booked minutes, utilization, reservation count, cancellation rate and peak hour per resource and bucket
+        pub async fn analytics(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AnalyticsRequest>,
+        ) -> Result<tonic::Response<super::AnalyticsResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/analytics");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// export the reservations matching a query as a csv or parquet file, streamed in chunks
+        pub async fn export(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ExportRequest>,
+        ) -> Result<tonic::Response<tonic::codec::Streaming<super::ExportChunk>>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/export");
+            self.inner
+                .server_streaming(request.into_request(), path, codec)
+                .await
+        }
+        /// check the reservations of a csv or json file and import them, for admin only
+        pub async fn import(
             &mut self,
-            request: impl tonic::IntoRequest<super::UpdateRequest>,
-        ) -> Result<tonic::Response<super::UpdateResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::ImportRequest>,
+        ) -> Result<tonic::Response<super::ImportResponse>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -394,14 +1837,14 @@ pub mod reservation_service_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/reservation.ReservationService/update");
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/import");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// cancel a reservation
-        pub async fn cancel(
+        /// create a calendar feed of a resource or user, for admin only
+        pub async fn create_feed(
             &mut self,
-            request: impl tonic::IntoRequest<super::CancelRequest>,
-        ) -> Result<tonic::Response<super::CancelResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::CreateFeedRequest>,
+        ) -> Result<tonic::Response<super::CreateFeedResponse>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -410,14 +1853,14 @@ pub mod reservation_service_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/reservation.ReservationService/cancel");
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/create_feed");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// get a reservation by id
-        pub async fn get(
+        /// revoke a calendar feed, for admin only
+        pub async fn revoke_feed(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetRequest>,
-        ) -> Result<tonic::Response<super::GetResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::RevokeFeedRequest>,
+        ) -> Result<tonic::Response<super::RevokeFeedResponse>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -425,15 +1868,15 @@ pub mod reservation_service_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/reservation.ReservationService/get");
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/revoke_feed");
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// query reservations by resource id, user id, status, start time, end time
-        pub async fn query(
+        /// list calendar feeds, for admin only
+        pub async fn list_feeds(
             &mut self,
-            request: impl tonic::IntoRequest<super::QueryRequest>,
-        ) -> Result<tonic::Response<tonic::codec::Streaming<super::Reservation>>, tonic::Status>
-        {
+            request: impl tonic::IntoRequest<super::ListFeedsRequest>,
+        ) -> Result<tonic::Response<super::ListFeedsResponse>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -442,16 +1885,14 @@ pub mod reservation_service_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/reservation.ReservationService/query");
-            self.inner
-                .server_streaming(request.into_request(), path, codec)
-                .await
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/list_feeds");
+            self.inner.unary(request.into_request(), path, codec).await
         }
-        /// filter reservations, order by reservation id
-        pub async fn filter(
+        /// set the time zone of a resource, for admin only
+        pub async fn set_time_zone(
             &mut self,
-            request: impl tonic::IntoRequest<super::FilterRequest>,
-        ) -> Result<tonic::Response<super::FilterResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::SetTimeZoneRequest>,
+        ) -> Result<tonic::Response<super::SetTimeZoneResponse>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -459,15 +1900,16 @@ pub mod reservation_service_client {
                 )
             })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path =
-                http::uri::PathAndQuery::from_static("/reservation.ReservationService/filter");
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/set_time_zone",
+            );
             self.inner.unary(request.into_request(), path, codec).await
         }
         /// another system could monitor newly added/confirmed/cancelled reservations
         pub async fn listen(
             &mut self,
             request: impl tonic::IntoRequest<super::ListenRequest>,
-        ) -> Result<tonic::Response<tonic::codec::Streaming<super::Reservation>>, tonic::Status>
+        ) -> Result<tonic::Response<tonic::codec::Streaming<super::ListenResponse>>, tonic::Status>
         {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
@@ -496,42 +1938,122 @@ pub mod reservation_service_server {
             &self,
             request: tonic::Request<super::ReserveRequest>,
         ) -> Result<tonic::Response<super::ReserveResponse>, tonic::Status>;
-        /// confirm a pending reservation, if reservation is not pending, do nothing
+        /// confirm a pending reservation, a confirmed one is left alone, other status are rejected
         async fn confirm(
             &self,
             request: tonic::Request<super::ConfirmRequest>,
         ) -> Result<tonic::Response<super::ConfirmResponse>, tonic::Status>;
-        /// update the reservation note
+        /// update the fields of a reservation listed in the field mask, status changes must follow the lifecycle and cancel is the only way to CANCELLED
         async fn update(
             &self,
             request: tonic::Request<super::UpdateRequest>,
         ) -> Result<tonic::Response<super::UpdateResponse>, tonic::Status>;
-        /// cancel a reservation
+        /// cancel a reservation, the reservation is kept with CANCELLED status
         async fn cancel(
             &self,
             request: tonic::Request<super::CancelRequest>,
         ) -> Result<tonic::Response<super::CancelResponse>, tonic::Status>;
+        /// remove a reservation physically, for admin only
+        async fn purge(
+            &self,
+            request: tonic::Request<super::PurgeRequest>,
+        ) -> Result<tonic::Response<super::PurgeResponse>, tonic::Status>;
         /// get a reservation by id
         async fn get(
             &self,
             request: tonic::Request<super::GetRequest>,
         ) -> Result<tonic::Response<super::GetResponse>, tonic::Status>;
-        /// Server streaming response type for the query method.
-        type queryStream: futures_core::Stream<Item = Result<super::Reservation, tonic::Status>>
-            + Send
-            + 'static;
+        /// get the change history of a reservation
+        async fn get_history(
+            &self,
+            request: tonic::Request<super::GetHistoryRequest>,
+        ) -> Result<tonic::Response<super::GetHistoryResponse>, tonic::Status>;
+        /// reserve several resources atomically under a shared group id
+        async fn reserve_batch(
+            &self,
+            request: tonic::Request<super::ReserveBatchRequest>,
+        ) -> Result<tonic::Response<super::ReserveBatchResponse>, tonic::Status>;
+        /// confirm all pending reservations of a group
+        async fn confirm_group(
+            &self,
+            request: tonic::Request<super::GroupRequest>,
+        ) -> Result<tonic::Response<super::GroupResponse>, tonic::Status>;
+        /// cancel all reservations of a group
+        async fn cancel_group(
+            &self,
+            request: tonic::Request<super::CancelGroupRequest>,
+        ) -> Result<tonic::Response<super::GroupResponse>, tonic::Status>;
+        /// get all reservations of a group
+        async fn get_group(
+            &self,
+            request: tonic::Request<super::GroupRequest>,
+        ) -> Result<tonic::Response<super::GroupResponse>, tonic::Status>;
         /// query reservations by resource id, user id, status, start time, end time
         async fn query(
             &self,
             request: tonic::Request<super::QueryRequest>,
-        ) -> Result<tonic::Response<Self::queryStream>, tonic::Status>;
+        ) -> Result<tonic::Response<super::QueryResponse>, tonic::Status>;
+        /// Server streaming response type for the query_stream method.
+        type query_streamStream: futures_core::Stream<Item = Result<super::Reservation, tonic::Status>>
+            + Send
+            + 'static;
+        /// stream all reservations matching a query in the order of its sort, read page by page on the
+        /// server. page and page_size are ignored, the stream starts after page_token if it is set
+        async fn query_stream(
+            &self,
+            request: tonic::Request<super::QueryRequest>,
+        ) -> Result<tonic::Response<Self::query_streamStream>, tonic::Status>;
         /// filter reservations, order by reservation id
         async fn filter(
             &self,
             request: tonic::Request<super::FilterRequest>,
         ) -> Result<tonic::Response<super::FilterResponse>, tonic::Status>;
+        /// full text search over notes, tags and attribute values, best match first
+        async fn search(
+            &self,
+            request: tonic::Request<super::SearchRequest>,
+        ) -> Result<tonic::Response<super::SearchResponse>, tonic::Status>;
+        /// booked minutes, utilization, reservation count, cancellation rate and peak hour per resource and bucket
+        async fn analytics(
+            &self,
+            request: tonic::Request<super::AnalyticsRequest>,
+        ) -> Result<tonic::Response<super::AnalyticsResponse>, tonic::Status>;
+        /// Server streaming response type for the export method.
+        type exportStream: futures_core::Stream<Item = Result<super::ExportChunk, tonic::Status>>
+            + Send
+            + 'static;
+        /// export the reservations matching a query as a csv or parquet file, streamed in chunks
+        async fn export(
+            &self,
+            request: tonic::Request<super::ExportRequest>,
+        ) -> Result<tonic::Response<Self::exportStream>, tonic::Status>;
+        /// check the reservations of a csv or json file and import them, for admin only
+        async fn import(
+            &self,
+            request: tonic::Request<super::ImportRequest>,
+        ) -> Result<tonic::Response<super::ImportResponse>, tonic::Status>;
+        /// create a calendar feed of a resource or user, for admin only
+        async fn create_feed(
+            &self,
+            request: tonic::Request<super::CreateFeedRequest>,
+        ) -> Result<tonic::Response<super::CreateFeedResponse>, tonic::Status>;
+        /// revoke a calendar feed, for admin only
+        async fn revoke_feed(
+            &self,
+            request: tonic::Request<super::RevokeFeedRequest>,
+        ) -> Result<tonic::Response<super::RevokeFeedResponse>, tonic::Status>;
+        /// list calendar feeds, for admin only
+        async fn list_feeds(
+            &self,
+            request: tonic::Request<super::ListFeedsRequest>,
+        ) -> Result<tonic::Response<super::ListFeedsResponse>, tonic::Status>;
+        /// set the time zone of a resource, for admin only
+        async fn set_time_zone(
+            &self,
+            request: tonic::Request<super::SetTimeZoneRequest>,
+        ) -> Result<tonic::Response<super::SetTimeZoneResponse>, tonic::Status>;
         /// Server streaming response type for the listen method.
-        type listenStream: futures_core::Stream<Item = Result<super::Reservation, tonic::Status>>
+        type listenStream: futures_core::Stream<Item = Result<super::ListenResponse, tonic::Status>>
             + Send
             + 'static;
         /// another system could monitor newly added/confirmed/cancelled reservations
@@ -540,7 +2062,11 @@ pub mod reservation_service_server {
             request: tonic::Request<super::ListenRequest>,
         ) -> Result<tonic::Response<Self::listenStream>, tonic::Status>;
     }
-    /// Reservation service
+    /// Reservation service. For mutating calls, clients could send the acting principal in the
+    /// `x-actor` metadata and the reason of the change in `x-change-reason`, both are recorded in the change log.
+    /// A retried mutating call could carry the same `x-idempotency-key` metadata, the response of the first
+    /// successful call with the key is returned instead of making the call again. A key is for one request,
+    /// the key with another request is rejected
     #[derive(Debug)]
     pub struct ReservationServiceServer<T: ReservationService> {
         inner: _Inner<T>,
@@ -596,16 +2122,239 @@ pub mod reservation_service_server {
             match req.uri().path() {
                 "/reservation.ReservationService/reserve" => {
                     #[allow(non_camel_case_types)]
-                    struct reserveSvc<T: ReservationService>(pub Arc<T>);
-                    impl<T: ReservationService> tonic::server::UnaryService<super::ReserveRequest> for reserveSvc<T> {
-                        type Response = super::ReserveResponse;
+                    struct reserveSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::ReserveRequest> for reserveSvc<T> {
+                        type Response = super::ReserveResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReserveRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).reserve(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = reserveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/confirm" => {
+                    #[allow(non_camel_case_types)]
+                    struct confirmSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::ConfirmRequest> for confirmSvc<T> {
+                        type Response = super::ConfirmResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ConfirmRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).confirm(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = confirmSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/update" => {
+                    #[allow(non_camel_case_types)]
+                    struct updateSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::UpdateRequest> for updateSvc<T> {
+                        type Response = super::UpdateResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).update(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = updateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/cancel" => {
+                    #[allow(non_camel_case_types)]
+                    struct cancelSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::CancelRequest> for cancelSvc<T> {
+                        type Response = super::CancelResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).cancel(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = cancelSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/purge" => {
+                    #[allow(non_camel_case_types)]
+                    struct purgeSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::PurgeRequest> for purgeSvc<T> {
+                        type Response = super::PurgeResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PurgeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).purge(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = purgeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/get" => {
+                    #[allow(non_camel_case_types)]
+                    struct getSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::GetRequest> for getSvc<T> {
+                        type Response = super::GetResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = getSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/get_history" => {
+                    #[allow(non_camel_case_types)]
+                    struct get_historySvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::GetHistoryRequest>
+                        for get_historySvc<T>
+                    {
+                        type Response = super::GetHistoryResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetHistoryRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_history(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = get_historySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/reserve_batch" => {
+                    #[allow(non_camel_case_types)]
+                    struct reserve_batchSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::ReserveBatchRequest>
+                        for reserve_batchSvc<T>
+                    {
+                        type Response = super::ReserveBatchResponse;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ReserveRequest>,
+                            request: tonic::Request<super::ReserveBatchRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).reserve(request).await };
+                            let fut = async move { (*inner).reserve_batch(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -614,7 +2363,7 @@ pub mod reservation_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = reserveSvc(inner);
+                        let method = reserve_batchSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -625,18 +2374,20 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
-                "/reservation.ReservationService/confirm" => {
+                "/reservation.ReservationService/confirm_group" => {
                     #[allow(non_camel_case_types)]
-                    struct confirmSvc<T: ReservationService>(pub Arc<T>);
-                    impl<T: ReservationService> tonic::server::UnaryService<super::ConfirmRequest> for confirmSvc<T> {
-                        type Response = super::ConfirmResponse;
+                    struct confirm_groupSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::GroupRequest>
+                        for confirm_groupSvc<T>
+                    {
+                        type Response = super::GroupResponse;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ConfirmRequest>,
+                            request: tonic::Request<super::GroupRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).confirm(request).await };
+                            let fut = async move { (*inner).confirm_group(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -645,7 +2396,7 @@ pub mod reservation_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = confirmSvc(inner);
+                        let method = confirm_groupSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -656,18 +2407,21 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
-                "/reservation.ReservationService/update" => {
+                "/reservation.ReservationService/cancel_group" => {
                     #[allow(non_camel_case_types)]
-                    struct updateSvc<T: ReservationService>(pub Arc<T>);
-                    impl<T: ReservationService> tonic::server::UnaryService<super::UpdateRequest> for updateSvc<T> {
-                        type Response = super::UpdateResponse;
+                    struct cancel_groupSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::CancelGroupRequest>
+                        for cancel_groupSvc<T>
+                    {
+                        type Response = super::GroupResponse;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::UpdateRequest>,
+                            request: tonic::Request<super::CancelGroupRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).update(request).await };
+                            let fut = async move { (*inner).cancel_group(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -676,7 +2430,7 @@ pub mod reservation_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = updateSvc(inner);
+                        let method = cancel_groupSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -687,18 +2441,18 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
-                "/reservation.ReservationService/cancel" => {
+                "/reservation.ReservationService/get_group" => {
                     #[allow(non_camel_case_types)]
-                    struct cancelSvc<T: ReservationService>(pub Arc<T>);
-                    impl<T: ReservationService> tonic::server::UnaryService<super::CancelRequest> for cancelSvc<T> {
-                        type Response = super::CancelResponse;
+                    struct get_groupSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::GroupRequest> for get_groupSvc<T> {
+                        type Response = super::GroupResponse;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::CancelRequest>,
+                            request: tonic::Request<super::GroupRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).cancel(request).await };
+                            let fut = async move { (*inner).get_group(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -707,7 +2461,7 @@ pub mod reservation_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = cancelSvc(inner);
+                        let method = get_groupSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -718,18 +2472,18 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
-                "/reservation.ReservationService/get" => {
+                "/reservation.ReservationService/query" => {
                     #[allow(non_camel_case_types)]
-                    struct getSvc<T: ReservationService>(pub Arc<T>);
-                    impl<T: ReservationService> tonic::server::UnaryService<super::GetRequest> for getSvc<T> {
-                        type Response = super::GetResponse;
+                    struct querySvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::QueryRequest> for querySvc<T> {
+                        type Response = super::QueryResponse;
                         type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetRequest>,
+                            request: tonic::Request<super::QueryRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).get(request).await };
+                            let fut = async move { (*inner).query(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -738,7 +2492,7 @@ pub mod reservation_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = getSvc(inner);
+                        let method = querySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -749,14 +2503,15 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
-                "/reservation.ReservationService/query" => {
+                "/reservation.ReservationService/query_stream" => {
                     #[allow(non_camel_case_types)]
-                    struct querySvc<T: ReservationService>(pub Arc<T>);
+                    struct query_streamSvc<T: ReservationService>(pub Arc<T>);
                     impl<T: ReservationService>
-                        tonic::server::ServerStreamingService<super::QueryRequest> for querySvc<T>
+                        tonic::server::ServerStreamingService<super::QueryRequest>
+                        for query_streamSvc<T>
                     {
                         type Response = super::Reservation;
-                        type ResponseStream = T::queryStream;
+                        type ResponseStream = T::query_streamStream;
                         type Future =
                             BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
                         fn call(
@@ -764,7 +2519,7 @@ pub mod reservation_service_server {
                             request: tonic::Request<super::QueryRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).query(request).await };
+                            let fut = async move { (*inner).query_stream(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -773,7 +2528,7 @@ pub mod reservation_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = querySvc(inner);
+                        let method = query_streamSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
                             accept_compression_encodings,
@@ -815,6 +2570,272 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/reservation.ReservationService/search" => {
+                    #[allow(non_camel_case_types)]
+                    struct searchSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::SearchRequest> for searchSvc<T> {
+                        type Response = super::SearchResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SearchRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).search(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = searchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/analytics" => {
+                    #[allow(non_camel_case_types)]
+                    struct analyticsSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::AnalyticsRequest>
+                        for analyticsSvc<T>
+                    {
+                        type Response = super::AnalyticsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AnalyticsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).analytics(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = analyticsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/export" => {
+                    #[allow(non_camel_case_types)]
+                    struct exportSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::ServerStreamingService<super::ExportRequest>
+                        for exportSvc<T>
+                    {
+                        type Response = super::ExportChunk;
+                        type ResponseStream = T::exportStream;
+                        type Future =
+                            BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExportRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).export(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = exportSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/import" => {
+                    #[allow(non_camel_case_types)]
+                    struct importSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::ImportRequest> for importSvc<T> {
+                        type Response = super::ImportResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ImportRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).import(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = importSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/create_feed" => {
+                    #[allow(non_camel_case_types)]
+                    struct create_feedSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::CreateFeedRequest>
+                        for create_feedSvc<T>
+                    {
+                        type Response = super::CreateFeedResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateFeedRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).create_feed(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = create_feedSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/revoke_feed" => {
+                    #[allow(non_camel_case_types)]
+                    struct revoke_feedSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::RevokeFeedRequest>
+                        for revoke_feedSvc<T>
+                    {
+                        type Response = super::RevokeFeedResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RevokeFeedRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).revoke_feed(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = revoke_feedSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/list_feeds" => {
+                    #[allow(non_camel_case_types)]
+                    struct list_feedsSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::ListFeedsRequest>
+                        for list_feedsSvc<T>
+                    {
+                        type Response = super::ListFeedsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListFeedsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list_feeds(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = list_feedsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/set_time_zone" => {
+                    #[allow(non_camel_case_types)]
+                    struct set_time_zoneSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::SetTimeZoneRequest>
+                        for set_time_zoneSvc<T>
+                    {
+                        type Response = super::SetTimeZoneResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetTimeZoneRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).set_time_zone(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = set_time_zoneSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec).apply_compression_config(
+                            accept_compression_encodings,
+                            send_compression_encodings,
+                        );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/reservation.ReservationService/listen" => {
                     #[allow(non_camel_case_types)]
                     struct listenSvc<T: ReservationService>(pub Arc<T>);
@@ -822,7 +2843,7 @@ pub mod reservation_service_server {
                         tonic::server::ServerStreamingService<super::ListenRequest>
                         for listenSvc<T>
                     {
-                        type Response = super::Reservation;
+                        type Response = super::ListenResponse;
                         type ResponseStream = T::listenStream;
                         type Future =
                             BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
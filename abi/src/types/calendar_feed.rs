@@ -0,0 +1,119 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    convert_to_timestamp, CalendarFeed, CalendarFeedKind, CreateFeedRequest, ReservationError,
+    ReservationQuery, ReservationQueryBuilder, RsvpFeedKind,
+};
+
+/// a new unguessable feed token, 32 random bytes in url safe base64
+pub fn new_feed_token() -> String {
+    let mut token = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut token);
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+/// only the hash of a token is stored, so the tokens can't be read back from the database
+pub fn feed_token_hash(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
+
+/// path of the feed of a token on the http server
+pub fn feed_path(token: &str) -> String {
+    format!("/feeds/{}.ics", token)
+}
+
+impl From<RsvpFeedKind> for CalendarFeedKind {
+    fn from(value: RsvpFeedKind) -> Self {
+        match value {
+            RsvpFeedKind::Resource => CalendarFeedKind::Resource,
+            RsvpFeedKind::User => CalendarFeedKind::User,
+        }
+    }
+}
+
+impl From<CalendarFeedKind> for RsvpFeedKind {
+    fn from(value: CalendarFeedKind) -> Self {
+        match value {
+            CalendarFeedKind::Resource => RsvpFeedKind::Resource,
+            CalendarFeedKind::User => RsvpFeedKind::User,
+        }
+    }
+}
+
+impl CreateFeedRequest {
+    pub fn get_kind(&self) -> Result<CalendarFeedKind, ReservationError> {
+        CalendarFeedKind::from_i32(self.kind).ok_or(ReservationError::InvalidFeedKind(self.kind))
+    }
+}
+
+impl CalendarFeed {
+    pub fn get_kind(&self) -> Result<CalendarFeedKind, ReservationError> {
+        CalendarFeedKind::from_i32(self.kind).ok_or(ReservationError::InvalidFeedKind(self.kind))
+    }
+
+    /// the reservations shown by the feed, cancelled ones are left out
+    pub fn query(&self) -> Result<ReservationQuery, ReservationError> {
+        let builder = match self.get_kind()? {
+            CalendarFeedKind::Resource => ReservationQueryBuilder::default()
+                .resource_id(self.subject.clone())
+                .build(),
+            CalendarFeedKind::User => ReservationQueryBuilder::default()
+                .user_id(self.subject.clone())
+                .build(),
+        };
+        Ok(builder.expect("all fields of a query have defaults"))
+    }
+}
+
+impl FromRow<'_, PgRow> for CalendarFeed {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let kind: RsvpFeedKind = row.try_get("kind")?;
+        let created_at: DateTime<Utc> = row.try_get("created_at")?;
+        let revoked_at: Option<DateTime<Utc>> = row.try_get("revoked_at")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            kind: CalendarFeedKind::from(kind) as i32,
+            subject: row.try_get("subject")?,
+            created_at: Some(convert_to_timestamp(created_at)),
+            revoked_at: revoked_at.map(convert_to_timestamp),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_token_should_be_random_and_url_safe() {
+        let token = new_feed_token();
+        assert_eq!(token.len(), 43);
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_ne!(token, new_feed_token());
+        assert_eq!(feed_token_hash(&token), feed_token_hash(&token));
+        assert_eq!(feed_token_hash(&token).len(), 32);
+        assert_eq!(feed_path("abc"), "/feeds/abc.ics");
+    }
+
+    #[test]
+    fn feed_query_should_follow_kind() {
+        let mut feed = CalendarFeed {
+            kind: CalendarFeedKind::User as i32,
+            subject: "alice".into(),
+            ..Default::default()
+        };
+        let query = feed.query().unwrap();
+        assert_eq!(query.user_id, "alice");
+        assert!(query.resource_id.is_empty());
+        assert!(!query.include_cancelled);
+
+        feed.kind = 7;
+        assert_eq!(feed.query(), Err(ReservationError::InvalidFeedKind(7)));
+    }
+}
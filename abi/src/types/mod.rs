@@ -1,30 +1,50 @@
+mod calendar_feed;
+mod page_token;
 mod reservation;
+mod reservation_analytics;
+mod reservation_attribute;
+mod reservation_change;
+mod reservation_group;
+mod reservation_import;
 mod reservation_query;
+mod reservation_search;
+mod reservation_sort;
 mod reservation_status;
+mod reservation_update;
 
 use std::ops::Bound;
 
+pub use calendar_feed::{feed_path, feed_token_hash, new_feed_token};
 use chrono::{DateTime, Utc};
+pub use page_token::{PageToken, PageTokenKey};
 use prost_types::Timestamp;
-pub use reservation::*;
-pub use reservation_status::*;
+pub use reservation_analytics::MAX_ANALYTICS_WINDOW_DAYS;
+pub use reservation_attribute::{attributes_from_json, attributes_to_json, normalize_tags};
+pub use reservation_change::changed_fields;
+pub use reservation_import::{DEFAULT_IMPORT_BATCH_SIZE, MAX_IMPORT_BATCH_SIZE};
+pub use reservation_search::{highlight, search_snippet};
+pub use reservation_sort::SortValue;
+pub use reservation_update::*;
 use sqlx::postgres::types::PgRange;
 
-use crate::{convert_to_utc_time, ReservationError};
+use crate::{convert_to_utc_time, ReservationError, ReservationStatus};
+
+/// an empty or 0 timestamp means the range is unbounded on that side
+pub fn to_bound_time(ts: Option<&Timestamp>) -> Option<DateTime<Utc>> {
+    match ts {
+        Some(ts) if ts.seconds != 0 || ts.nanos != 0 => Some(convert_to_utc_time(ts.clone())),
+        _ => None,
+    }
+}
 
 pub fn validate_range(
     start: Option<&Timestamp>,
     end: Option<&Timestamp>,
 ) -> Result<(), ReservationError> {
-    if start.is_none() || end.is_none() {
-        return Err(ReservationError::InvalidTimespan);
-    }
-
-    let start = start.unwrap();
-    let end = end.unwrap();
-
-    if start.seconds > end.seconds {
-        return Err(ReservationError::InvalidTimespan);
+    if let (Some(start), Some(end)) = (to_bound_time(start), to_bound_time(end)) {
+        if start > end {
+            return Err(ReservationError::InvalidTimespan);
+        }
     }
 
     Ok(())
@@ -36,11 +56,52 @@ pub fn get_timespan(
 ) -> Result<PgRange<DateTime<Utc>>, ReservationError> {
     validate_range(start, end)?;
 
-    let start = convert_to_utc_time(start.unwrap().clone());
-    let end = convert_to_utc_time(end.unwrap().clone());
-
     Ok(PgRange {
-        start: Bound::Included(start),
-        end: Bound::Excluded(end),
+        start: to_bound_time(start).map_or(Bound::Unbounded, Bound::Included),
+        end: to_bound_time(end).map_or(Bound::Unbounded, Bound::Excluded),
     })
 }
+
+/// page size used when a query or filter leaves it at 0
+pub const DEFAULT_PAGE_SIZE: i64 = 10;
+/// larger page sizes are clamped to this
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// the page size to use for a requested one: 0 means the default, sizes above the maximum are
+/// clamped, negative sizes are rejected
+pub fn normalize_page_size(page_size: i64) -> Result<i64, ReservationError> {
+    match page_size {
+        0 => Ok(DEFAULT_PAGE_SIZE),
+        v if v < 0 => Err(ReservationError::InvalidPageSize(v)),
+        v => Ok(v.min(MAX_PAGE_SIZE)),
+    }
+}
+
+/// values of a filter field, merged from its single and repeated form, empty means all
+pub fn merge_filter_values(value: &str, values: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = vec![];
+    for v in std::iter::once(value).chain(values.iter().map(String::as_str)) {
+        if !v.is_empty() && !merged.iter().any(|m| m == v) {
+            merged.push(v.to_string());
+        }
+    }
+    merged
+}
+
+/// statuses of a filter, merged from its single and repeated form. UNKNOWN is skipped, so an
+/// empty result means all statuses
+pub fn merge_filter_statuses(
+    status: i32,
+    statuses: &[i32],
+) -> Result<Vec<ReservationStatus>, ReservationError> {
+    let mut merged = vec![];
+    for &v in std::iter::once(&status).chain(statuses) {
+        match ReservationStatus::from_i32(v) {
+            None => return Err(ReservationError::InvalidStatus(v)),
+            Some(ReservationStatus::Unknown) => {}
+            Some(s) if !merged.contains(&s) => merged.push(s),
+            Some(_) => {}
+        }
+    }
+    Ok(merged)
+}
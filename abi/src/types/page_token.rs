@@ -0,0 +1,171 @@
+use std::{fmt, sync::Arc};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{Reservation, ReservationError, ReservationSort, SortValue};
+
+const TOKEN_VERSION: u8 = 2;
+const SIGNATURE_LEN: usize = 32;
+
+/// sort key values of the last reservation of a query page, the next page starts after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageToken {
+    /// sort keys of the query the token was made for
+    pub sort: Vec<ReservationSort>,
+    /// values of the sort keys, in the same order
+    pub values: Vec<SortValue>,
+    /// the id breaks ties between equal sort keys
+    pub id: i64,
+}
+
+/// secret which signs page tokens, so a client can't forge or alter them
+#[derive(Clone)]
+pub struct PageTokenKey(Arc<[u8]>);
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    version: u8,
+    sort: Vec<(i32, bool)>,
+    values: Vec<SortValue>,
+    id: i64,
+}
+
+impl PageTokenKey {
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self(secret.as_ref().into())
+    }
+
+    /// a random key, the tokens it signs are only accepted by the process that made them
+    pub fn random() -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::new(secret)
+    }
+
+    fn mac(&self, payload: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.0).expect("hmac takes keys of any size");
+        mac.update(payload);
+        mac
+    }
+}
+
+impl fmt::Debug for PageTokenKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PageTokenKey(..)")
+    }
+}
+
+impl PageToken {
+    /// the token to continue after the reservation in the given sort
+    pub fn after(rsvp: &Reservation, sort: Vec<ReservationSort>) -> Self {
+        let values = sort
+            .iter()
+            .map(|key| match key.get_field() {
+                Ok(field) => field.value_of(rsvp),
+                Err(_) => SortValue::Null,
+            })
+            .collect();
+        Self {
+            sort,
+            values,
+            id: rsvp.id,
+        }
+    }
+
+    /// the signed token, url safe base64
+    pub fn encode(&self, key: &PageTokenKey) -> String {
+        let payload = Payload {
+            version: TOKEN_VERSION,
+            sort: self.sort.iter().map(|k| (k.field, k.desc)).collect(),
+            values: self.values.clone(),
+            id: self.id,
+        };
+        let mut data = serde_json::to_vec(&payload).expect("payload is always serializable");
+        let signature = key.mac(&data).finalize().into_bytes();
+        data.extend_from_slice(&signature);
+
+        URL_SAFE_NO_PAD.encode(data)
+    }
+
+    /// decode a token made by encode, a token which isn't signed by the key is rejected
+    pub fn decode(token: &str, key: &PageTokenKey) -> Result<Self, ReservationError> {
+        let data = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| ReservationError::InvalidPageToken)?;
+        if data.len() <= SIGNATURE_LEN {
+            return Err(ReservationError::InvalidPageToken);
+        }
+
+        let (payload, signature) = data.split_at(data.len() - SIGNATURE_LEN);
+        key.mac(payload)
+            .verify_slice(signature)
+            .map_err(|_| ReservationError::InvalidPageToken)?;
+
+        let payload: Payload =
+            serde_json::from_slice(payload).map_err(|_| ReservationError::InvalidPageToken)?;
+        if payload.version != TOKEN_VERSION || payload.values.len() != payload.sort.len() {
+            return Err(ReservationError::InvalidPageToken);
+        }
+
+        Ok(Self {
+            sort: payload
+                .sort
+                .into_iter()
+                .map(|(field, desc)| ReservationSort { field, desc })
+                .collect(),
+            values: payload.values,
+            id: payload.id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ReservationSortField;
+
+    use super::*;
+
+    fn token() -> PageToken {
+        PageToken {
+            sort: vec![
+                ReservationSort::new(ReservationSortField::End, true),
+                ReservationSort::new(ReservationSortField::UserId, false),
+            ],
+            values: vec![SortValue::Null, SortValue::Text("hyx".into())],
+            id: 42,
+        }
+    }
+
+    #[test]
+    fn page_token_should_round_trip() {
+        let key = PageTokenKey::new("secret");
+        let encoded = token().encode(&key);
+        assert_eq!(PageToken::decode(&encoded, &key).unwrap(), token());
+    }
+
+    #[test]
+    fn altered_page_token_should_reject() {
+        let key = PageTokenKey::new("secret");
+        let mut data = URL_SAFE_NO_PAD.decode(token().encode(&key)).unwrap();
+        let pos = data.iter().position(|&b| b == b'h').unwrap();
+        data[pos] = b't';
+        let altered = URL_SAFE_NO_PAD.encode(data);
+
+        assert_eq!(
+            PageToken::decode(&altered, &key),
+            Err(ReservationError::InvalidPageToken)
+        );
+        assert_eq!(
+            PageToken::decode(&token().encode(&PageTokenKey::random()), &key),
+            Err(ReservationError::InvalidPageToken)
+        );
+        assert_eq!(
+            PageToken::decode("not a token", &key),
+            Err(ReservationError::InvalidPageToken)
+        );
+    }
+}
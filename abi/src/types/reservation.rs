@@ -1,14 +1,16 @@
 use std::ops::Bound;
 
 use chrono::{DateTime, FixedOffset, Utc};
+use serde_json::Value;
 use sqlx::{
     postgres::{types::PgRange, PgRow},
+    types::{Json, Uuid},
     FromRow, Row,
 };
 
 use crate::{
-    convert_to_timestamp, convert_to_utc_time, get_timespan, Reservation, ReservationError,
-    ReservationStatus, RsvpStatus, Validator,
+    attributes_from_json, attributes_to_json, convert_to_timestamp, get_timespan, normalize_tags,
+    to_bound_time, Reservation, ReservationError, ReservationStatus, RsvpStatus, Validator,
 };
 
 impl Reservation {
@@ -27,12 +29,25 @@ impl Reservation {
             start: Some(convert_to_timestamp(start.with_timezone(&Utc))),
             end: Some(convert_to_timestamp(end.with_timezone(&Utc))),
             note: note.into(),
+            ..Default::default()
         }
     }
 
     pub fn get_timestamp(&self) -> PgRange<DateTime<Utc>> {
         get_timespan(self.start.as_ref(), self.end.as_ref()).unwrap()
     }
+
+    /// check the reservation is at the expected version, None means any version
+    pub fn check_version(&self, expected: Option<i64>) -> Result<(), ReservationError> {
+        match expected {
+            Some(expected) if expected != self.version => Err(ReservationError::VersionMismatch {
+                id: self.id,
+                expected,
+                actual: self.version,
+            }),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Validator for Reservation {
@@ -47,16 +62,16 @@ impl Validator for Reservation {
             ));
         }
 
-        if self.start.is_none() || self.end.is_none() {
-            return Err(ReservationError::InvalidTimespan);
+        // a reservation must have a start, but could be open ended
+        let start = to_bound_time(self.start.as_ref()).ok_or(ReservationError::InvalidTimespan)?;
+        if let Some(end) = to_bound_time(self.end.as_ref()) {
+            if start >= end {
+                return Err(ReservationError::InvalidTimespan);
+            }
         }
 
-        let start = convert_to_utc_time(self.start.clone().unwrap());
-        let end = convert_to_utc_time(self.end.clone().unwrap());
-
-        if start > end {
-            return Err(ReservationError::InvalidTimespan);
-        }
+        normalize_tags(&self.tags)?;
+        attributes_to_json(&self.attributes)?;
 
         Ok(())
     }
@@ -67,20 +82,33 @@ impl FromRow<'_, PgRow> for Reservation {
         let id = row.try_get("id")?;
         let range: PgRange<DateTime<Utc>> = row.get("timespan");
         let range: NavieRange<DateTime<Utc>> = range.into();
-        assert!(range.start.is_some());
-        assert!(range.end.is_some());
-        let start = range.start.unwrap();
-        let end = range.end.unwrap();
 
         let status: RsvpStatus = row.get("status");
+        let group_id: Option<Uuid> = row.try_get("group_id")?;
+        let cancelled_at: Option<DateTime<Utc>> = row.try_get("cancelled_at")?;
+        let created_at: DateTime<Utc> = row.try_get("created_at")?;
+        let tags: Json<Vec<String>> = row.try_get("tags")?;
+        let attributes: Json<Value> = row.try_get("attributes")?;
         Ok(Self {
             id,
             user_id: row.try_get("user_id")?,
             status: ReservationStatus::from(status) as i32,
             resource_id: row.try_get("resource_id")?,
-            start: Some(convert_to_timestamp(start)),
-            end: Some(convert_to_timestamp(end)),
+            start: range.start.map(convert_to_timestamp),
+            end: range.end.map(convert_to_timestamp),
             note: row.try_get("note")?,
+            group_id: group_id.map(|id| id.to_string()).unwrap_or_default(),
+            cancel_reason: row
+                .try_get::<Option<String>, _>("cancel_reason")?
+                .unwrap_or_default(),
+            cancelled_by: row
+                .try_get::<Option<String>, _>("cancelled_by")?
+                .unwrap_or_default(),
+            cancelled_at: cancelled_at.map(convert_to_timestamp),
+            version: row.try_get("version")?,
+            created_at: Some(convert_to_timestamp(created_at)),
+            tags: tags.0,
+            attributes: attributes_from_json(&attributes.0),
         })
     }
 }
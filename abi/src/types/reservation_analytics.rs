@@ -0,0 +1,112 @@
+use core::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    merge_filter_values, to_bound_time, AnalyticsBucketSize, AnalyticsQuery, ReservationError,
+    Validator,
+};
+
+/// longest window of an analytics query, an hourly report of it has less than 10000 buckets
+pub const MAX_ANALYTICS_WINDOW_DAYS: i64 = 400;
+
+impl fmt::Display for AnalyticsBucketSize {
+    /// the date_trunc field of the bucket size
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyticsBucketSize::Day => write!(f, "day"),
+            AnalyticsBucketSize::Hour => write!(f, "hour"),
+            AnalyticsBucketSize::Week => write!(f, "week"),
+        }
+    }
+}
+
+impl AnalyticsQuery {
+    pub fn get_bucket_size(&self) -> AnalyticsBucketSize {
+        AnalyticsBucketSize::from_i32(self.bucket_size).unwrap_or(AnalyticsBucketSize::Day)
+    }
+
+    /// start and end of the window, both are required
+    pub fn get_window(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), ReservationError> {
+        let (Some(start), Some(end)) = (
+            to_bound_time(self.start.as_ref()),
+            to_bound_time(self.end.as_ref()),
+        ) else {
+            return Err(ReservationError::InvalidAnalyticsWindow(
+                "start and end are required".into(),
+            ));
+        };
+        if start >= end {
+            return Err(ReservationError::InvalidTimespan);
+        }
+        if end - start > Duration::days(MAX_ANALYTICS_WINDOW_DAYS) {
+            return Err(ReservationError::InvalidAnalyticsWindow(format!(
+                "longer than {} days",
+                MAX_ANALYTICS_WINDOW_DAYS
+            )));
+        }
+        Ok((start, end))
+    }
+
+    /// resources to report on, empty for every resource with a reservation in the window
+    pub fn get_resource_ids(&self) -> Vec<String> {
+        merge_filter_values("", &self.resource_ids)
+    }
+
+    pub fn get_time_zone(&self) -> &str {
+        match self.time_zone.as_str() {
+            "" => "UTC",
+            tz => tz,
+        }
+    }
+}
+
+impl Validator for AnalyticsQuery {
+    fn validate(&self) -> Result<(), ReservationError> {
+        if AnalyticsBucketSize::from_i32(self.bucket_size).is_none() {
+            return Err(ReservationError::InvalidBucketSize(self.bucket_size));
+        }
+        self.get_window()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::Timestamp;
+
+    use super::*;
+
+    #[test]
+    fn analytics_window_should_be_bounded() {
+        let query = |start: &str, end: &str| AnalyticsQuery {
+            start: Some(start.parse::<Timestamp>().unwrap()),
+            end: Some(end.parse::<Timestamp>().unwrap()),
+            ..Default::default()
+        };
+        assert!(query("2023-01-01T00:00:00Z", "2023-04-01T00:00:00Z")
+            .validate()
+            .is_ok());
+        assert_eq!(
+            query("2023-04-01T00:00:00Z", "2023-01-01T00:00:00Z").validate(),
+            Err(ReservationError::InvalidTimespan)
+        );
+        assert!(matches!(
+            query("2021-01-01T00:00:00Z", "2023-01-01T00:00:00Z").validate(),
+            Err(ReservationError::InvalidAnalyticsWindow(_))
+        ));
+        assert!(matches!(
+            AnalyticsQuery::default().validate(),
+            Err(ReservationError::InvalidAnalyticsWindow(_))
+        ));
+
+        let query = AnalyticsQuery {
+            bucket_size: 42,
+            ..query("2023-01-01T00:00:00Z", "2023-04-01T00:00:00Z")
+        };
+        assert_eq!(
+            query.validate(),
+            Err(ReservationError::InvalidBucketSize(42))
+        );
+    }
+}
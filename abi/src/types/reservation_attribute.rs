@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Number, Value};
+
+use crate::{attribute_value, AttributeFilter, AttributeValue, ReservationError, Validator};
+
+const MAX_TAG_LEN: usize = 64;
+
+impl AttributeValue {
+    /// the json form of the value, as stored in rsvp.reservations.attributes
+    pub fn to_json(&self) -> Result<Value, ReservationError> {
+        match &self.value {
+            Some(attribute_value::Value::StringValue(v)) => Ok(Value::String(v.clone())),
+            Some(attribute_value::Value::IntValue(v)) => Ok(Value::Number((*v).into())),
+            Some(attribute_value::Value::DoubleValue(v)) => Number::from_f64(*v)
+                .map(Value::Number)
+                .ok_or_else(|| ReservationError::InvalidAttribute(v.to_string())),
+            Some(attribute_value::Value::BoolValue(v)) => Ok(Value::Bool(*v)),
+            None => Err(ReservationError::InvalidAttribute("empty value".into())),
+        }
+    }
+
+    /// the value of a json scalar, numbers without fraction become int values
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let value = match value {
+            Value::String(v) => attribute_value::Value::StringValue(v.clone()),
+            Value::Number(v) => match v.as_i64() {
+                Some(v) => attribute_value::Value::IntValue(v),
+                None => attribute_value::Value::DoubleValue(v.as_f64()?),
+            },
+            Value::Bool(v) => attribute_value::Value::BoolValue(*v),
+            _ => return None,
+        };
+        Some(Self { value: Some(value) })
+    }
+}
+
+/// the json object of the attributes
+pub fn attributes_to_json(
+    attributes: &HashMap<String, AttributeValue>,
+) -> Result<Value, ReservationError> {
+    let mut map = Map::with_capacity(attributes.len());
+    for (key, value) in attributes {
+        if key.is_empty() {
+            return Err(ReservationError::InvalidAttribute("empty key".into()));
+        }
+        map.insert(key.clone(), value.to_json()?);
+    }
+    Ok(Value::Object(map))
+}
+
+/// the attributes of a json object, values which aren't scalars are skipped
+pub fn attributes_from_json(value: &Value) -> HashMap<String, AttributeValue> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .filter_map(|(k, v)| AttributeValue::from_json(v).map(|v| (k.clone(), v)))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// tags sorted and without duplicates, empty or too long tags are rejected
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, ReservationError> {
+    let mut normalized = Vec::with_capacity(tags.len());
+    for tag in tags {
+        if tag.is_empty() || tag.len() > MAX_TAG_LEN {
+            return Err(ReservationError::InvalidTag(tag.clone()));
+        }
+        normalized.push(tag.clone());
+    }
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+impl Validator for AttributeFilter {
+    fn validate(&self) -> Result<(), ReservationError> {
+        if self.key.is_empty() {
+            return Err(ReservationError::InvalidAttribute("empty key".into()));
+        }
+        let values = [&self.eq, &self.gte, &self.lt];
+        if values.iter().all(|v| v.is_none()) {
+            return Err(ReservationError::InvalidAttribute(format!(
+                "no condition on {}",
+                self.key
+            )));
+        }
+        for value in values.into_iter().flatten() {
+            value.to_json()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(v: attribute_value::Value) -> AttributeValue {
+        AttributeValue { value: Some(v) }
+    }
+
+    #[test]
+    fn attributes_should_round_trip_json() {
+        let attributes = HashMap::from([
+            (
+                "cost_center".to_string(),
+                value(attribute_value::Value::StringValue("cc-42".into())),
+            ),
+            (
+                "attendees".to_string(),
+                value(attribute_value::Value::IntValue(12)),
+            ),
+            (
+                "budget".to_string(),
+                value(attribute_value::Value::DoubleValue(99.5)),
+            ),
+            (
+                "catering".to_string(),
+                value(attribute_value::Value::BoolValue(true)),
+            ),
+        ]);
+        let json = attributes_to_json(&attributes).unwrap();
+        assert_eq!(json["attendees"], 12);
+        assert_eq!(attributes_from_json(&json), attributes);
+
+        let invalid = HashMap::from([(
+            "budget".to_string(),
+            value(attribute_value::Value::DoubleValue(f64::NAN)),
+        )]);
+        assert!(attributes_to_json(&invalid).is_err());
+    }
+
+    #[test]
+    fn normalize_tags_should_sort_and_dedup() {
+        let tags = vec!["vip".to_string(), "board".to_string(), "vip".to_string()];
+        assert_eq!(normalize_tags(&tags).unwrap(), vec!["board", "vip"]);
+        assert_eq!(
+            normalize_tags(&["".to_string()]),
+            Err(ReservationError::InvalidTag("".into()))
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::ops::Bound;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{
+    postgres::{types::PgRange, PgRow},
+    types::Json,
+    FromRow, Row,
+};
+
+use crate::{
+    attributes_from_json, convert_to_timestamp, parse_datetime, Reservation, ReservationChange,
+    ReservationError, ReservationStatus, ReservationUpdateType, RsvpStatus, RsvpUpdateType,
+};
+
+/// a reservation row as recorded by to_jsonb() in rsvp.reservation_changes.
+/// columns added by later migrations are missing in old records
+#[derive(Debug, Deserialize)]
+struct ReservationRecord {
+    id: i64,
+    user_id: String,
+    status: RsvpStatus,
+    resource_id: String,
+    timespan: String,
+    note: Option<String>,
+    group_id: Option<String>,
+    cancel_reason: Option<String>,
+    cancelled_by: Option<String>,
+    cancelled_at: Option<DateTime<Utc>>,
+    version: Option<i64>,
+    created_at: Option<DateTime<Utc>>,
+    tags: Option<Vec<String>>,
+    attributes: Option<serde_json::Value>,
+}
+
+impl TryFrom<ReservationRecord> for Reservation {
+    type Error = ReservationError;
+
+    fn try_from(record: ReservationRecord) -> Result<Self, Self::Error> {
+        let range = parse_timespan(&record.timespan)?;
+        let bound = |b: Bound<DateTime<Utc>>| match b {
+            Bound::Included(v) | Bound::Excluded(v) => Some(convert_to_timestamp(v)),
+            Bound::Unbounded => None,
+        };
+
+        Ok(Self {
+            id: record.id,
+            user_id: record.user_id,
+            status: ReservationStatus::from(record.status) as i32,
+            resource_id: record.resource_id,
+            start: bound(range.start),
+            end: bound(range.end),
+            note: record.note.unwrap_or_default(),
+            group_id: record.group_id.unwrap_or_default(),
+            cancel_reason: record.cancel_reason.unwrap_or_default(),
+            cancelled_by: record.cancelled_by.unwrap_or_default(),
+            cancelled_at: record.cancelled_at.map(convert_to_timestamp),
+            version: record.version.unwrap_or_default(),
+            created_at: record.created_at.map(convert_to_timestamp),
+            tags: record.tags.unwrap_or_default(),
+            attributes: record
+                .attributes
+                .as_ref()
+                .map(attributes_from_json)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl From<RsvpUpdateType> for ReservationUpdateType {
+    fn from(value: RsvpUpdateType) -> Self {
+        match value {
+            RsvpUpdateType::Create => ReservationUpdateType::Create,
+            RsvpUpdateType::Update => ReservationUpdateType::Update,
+            RsvpUpdateType::Delete => ReservationUpdateType::Delete,
+            RsvpUpdateType::Unknown => ReservationUpdateType::Unknown,
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for ReservationChange {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let decode = |column: &str| -> Result<Option<Reservation>, sqlx::Error> {
+            let record: Option<Json<ReservationRecord>> = row.try_get(column)?;
+            record
+                .map(|Json(record)| record.try_into())
+                .transpose()
+                .map_err(|e: ReservationError| sqlx::Error::Decode(Box::new(e)))
+        };
+
+        let op: RsvpUpdateType = row.try_get("op")?;
+        let before = decode("old")?;
+        let after = decode("new")?;
+        let changed_fields = match (&before, &after) {
+            (Some(before), Some(after)) => changed_fields(before, after),
+            _ => vec![],
+        };
+        let actor: Option<String> = row.try_get("actor")?;
+        let reason: Option<String> = row.try_get("reason")?;
+        let at: DateTime<Utc> = row.try_get("changed_at")?;
+
+        Ok(Self {
+            op: ReservationUpdateType::from(op) as i32,
+            before,
+            after,
+            changed_fields,
+            actor: actor.unwrap_or_default(),
+            at: Some(convert_to_timestamp(at)),
+            reason: reason.unwrap_or_default(),
+        })
+    }
+}
+
+/// names of the fields that differ between two versions of a reservation, the version itself
+/// is not listed
+pub fn changed_fields(before: &Reservation, after: &Reservation) -> Vec<String> {
+    let fields = [
+        ("user_id", before.user_id != after.user_id),
+        ("status", before.status != after.status),
+        ("resource_id", before.resource_id != after.resource_id),
+        ("start", before.start != after.start),
+        ("end", before.end != after.end),
+        ("note", before.note != after.note),
+        ("group_id", before.group_id != after.group_id),
+        ("cancel_reason", before.cancel_reason != after.cancel_reason),
+        ("cancelled_by", before.cancelled_by != after.cancelled_by),
+        ("cancelled_at", before.cancelled_at != after.cancelled_at),
+        ("tags", before.tags != after.tags),
+        ("attributes", before.attributes != after.attributes),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// parse the text form of a tstzrange, e.g. ["2022-12-24 19:00:00+00","2022-12-28 19:00:00+00")
+fn parse_timespan(s: &str) -> Result<PgRange<DateTime<Utc>>, ReservationError> {
+    let s = s.trim();
+    if s.len() < 2 || !s.is_char_boundary(1) || !s.is_char_boundary(s.len() - 1) {
+        return Err(ReservationError::InvalidTimespan);
+    }
+
+    let (lower, upper) = s[1..s.len() - 1]
+        .split_once(',')
+        .ok_or(ReservationError::InvalidTimespan)?;
+    let parse = |v: &str, inclusive: bool| -> Result<Bound<DateTime<Utc>>, ReservationError> {
+        let v = v.trim_matches('"');
+        if v.is_empty() || v.ends_with("infinity") {
+            return Ok(Bound::Unbounded);
+        }
+        let dt = parse_datetime(v).map_err(|_| ReservationError::InvalidTimespan)?;
+        Ok(if inclusive {
+            Bound::Included(dt)
+        } else {
+            Bound::Excluded(dt)
+        })
+    };
+
+    let start = match &s[..1] {
+        "[" => parse(lower, true)?,
+        "(" => parse(lower, false)?,
+        _ => return Err(ReservationError::InvalidTimespan),
+    };
+    let end = match &s[s.len() - 1..] {
+        "]" => parse(upper, true)?,
+        ")" => parse(upper, false)?,
+        _ => return Err(ReservationError::InvalidTimespan),
+    };
+
+    Ok(PgRange { start, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timespan_should_work() {
+        let range =
+            parse_timespan(r#"["2022-12-24 19:00:00+00","2022-12-28 19:00:00.5+00")"#).unwrap();
+        assert_eq!(
+            range.start,
+            Bound::Included("2022-12-24T19:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            range.end,
+            Bound::Excluded("2022-12-28T19:00:00.5Z".parse().unwrap())
+        );
+
+        let range = parse_timespan(r#"(,"2022-12-28 19:00:00+00")"#).unwrap();
+        assert_eq!(range.start, Bound::Unbounded);
+
+        assert!(parse_timespan("empty").is_err());
+        assert!(parse_timespan("").is_err());
+    }
+
+    #[test]
+    fn reservation_record_should_decode() {
+        let json = r#"{"id": 1, "note": "hello", "status": "checked_in", "user_id": "hyx", "group_id": null, "timespan": "[\"2022-12-24 19:00:00+00\",\"2022-12-28 19:00:00+00\")", "resource_id": "room-421"}"#;
+        let record: ReservationRecord = serde_json::from_str(json).unwrap();
+        let rsvp: Reservation = record.try_into().unwrap();
+
+        let expected = Reservation {
+            id: 1,
+            status: ReservationStatus::CheckedIn as i32,
+            ..Reservation::new_pending(
+                "hyx",
+                "room-421",
+                "2022-12-24T12:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "hello",
+            )
+        };
+        assert_eq!(rsvp, expected);
+    }
+
+    #[test]
+    fn changed_fields_should_work() {
+        let before = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+        let after = Reservation {
+            status: ReservationStatus::Confirmed as i32,
+            note: "world".into(),
+            ..before.clone()
+        };
+        assert_eq!(changed_fields(&before, &after), vec!["status", "note"]);
+        assert!(changed_fields(&before, &before).is_empty());
+    }
+}
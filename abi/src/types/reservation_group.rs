@@ -0,0 +1,23 @@
+use crate::{
+    convert_to_timestamp, ReservationConflictInfo, ReservationError, ReservationFailure,
+    ReservationItemError,
+};
+
+impl From<ReservationItemError> for ReservationFailure {
+    fn from(item: ReservationItemError) -> Self {
+        let (conflict_start, conflict_end) = match &item.error {
+            ReservationError::ConflictReservation(ReservationConflictInfo::Parsed(info)) => (
+                Some(convert_to_timestamp(info.old.start)),
+                info.old.end.map(convert_to_timestamp),
+            ),
+            _ => (None, None),
+        };
+
+        Self {
+            index: item.index as i32,
+            reason: item.error.to_string(),
+            conflict_start,
+            conflict_end,
+        }
+    }
+}
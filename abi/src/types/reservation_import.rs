@@ -0,0 +1,60 @@
+use crate::{ImportFormat, ImportMode, ImportRequest, ReservationError};
+
+/// rows inserted per transaction when the request leaves the batch size at 0
+pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 1000;
+/// larger batch sizes are rejected
+pub const MAX_IMPORT_BATCH_SIZE: usize = 10000;
+
+impl ImportRequest {
+    pub fn get_format(&self) -> Result<ImportFormat, ReservationError> {
+        ImportFormat::from_i32(self.format)
+            .ok_or(ReservationError::InvalidImportFormat(self.format))
+    }
+
+    pub fn get_mode(&self) -> Result<ImportMode, ReservationError> {
+        ImportMode::from_i32(self.mode).ok_or(ReservationError::InvalidImportMode(self.mode))
+    }
+
+    /// 0 means the default, negative or too large sizes are rejected
+    pub fn get_batch_size(&self) -> Result<usize, ReservationError> {
+        match self.batch_size {
+            0 => Ok(DEFAULT_IMPORT_BATCH_SIZE),
+            v if v < 0 || v as usize > MAX_IMPORT_BATCH_SIZE => {
+                Err(ReservationError::InvalidImportBatchSize(v))
+            }
+            v => Ok(v as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_request_should_check_options() {
+        let request = ImportRequest::default();
+        assert_eq!(request.get_format().unwrap(), ImportFormat::Csv);
+        assert_eq!(request.get_mode().unwrap(), ImportMode::AllOrNothing);
+        assert_eq!(request.get_batch_size().unwrap(), DEFAULT_IMPORT_BATCH_SIZE);
+
+        let request = ImportRequest {
+            format: 7,
+            mode: 7,
+            batch_size: 10001,
+            ..Default::default()
+        };
+        assert_eq!(
+            request.get_format(),
+            Err(ReservationError::InvalidImportFormat(7))
+        );
+        assert_eq!(
+            request.get_mode(),
+            Err(ReservationError::InvalidImportMode(7))
+        );
+        assert_eq!(
+            request.get_batch_size(),
+            Err(ReservationError::InvalidImportBatchSize(10001))
+        );
+    }
+}
@@ -1,12 +1,63 @@
+use core::fmt;
+
 use chrono::{DateTime, Utc};
 use sqlx::postgres::types::PgRange;
 
-use crate::{get_timespan, validate_range, ReservationError, ReservationQuery, Validator};
+use crate::{
+    get_timespan, merge_filter_statuses, merge_filter_values, normalize_page_size, normalize_tags,
+    validate_range, ReservationError, ReservationFilter, ReservationMatchMode, ReservationQuery,
+    ReservationStatus, Validator,
+};
+
+impl fmt::Display for ReservationMatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReservationMatchMode::Within => write!(f, "within"),
+            ReservationMatchMode::Overlaps => write!(f, "overlaps"),
+            ReservationMatchMode::Contains => write!(f, "contains"),
+            ReservationMatchMode::StartsIn => write!(f, "starts_in"),
+        }
+    }
+}
 
 impl ReservationQuery {
     pub fn get_timespan(&self) -> PgRange<DateTime<Utc>> {
         get_timespan(self.start.as_ref(), self.end.as_ref()).unwrap()
     }
+
+    pub fn get_match_mode(&self) -> ReservationMatchMode {
+        ReservationMatchMode::from_i32(self.match_mode).unwrap_or(ReservationMatchMode::Within)
+    }
+
+    /// user ids to match, empty for all users
+    pub fn get_user_ids(&self) -> Vec<String> {
+        merge_filter_values(&self.user_id, &self.user_ids)
+    }
+
+    /// resource ids to match, empty for all resources
+    pub fn get_resource_ids(&self) -> Vec<String> {
+        merge_filter_values(&self.resource_id, &self.resource_ids)
+    }
+
+    /// statuses to match, empty for all statuses
+    pub fn get_statuses(&self) -> Result<Vec<ReservationStatus>, ReservationError> {
+        merge_filter_statuses(self.status, &self.statuses)
+    }
+
+    /// the page to return, starting at 1
+    pub fn get_page(&self) -> i64 {
+        self.page.max(1) as i64
+    }
+
+    /// the page size to use, see normalize_page_size
+    pub fn get_page_size(&self) -> Result<i64, ReservationError> {
+        normalize_page_size(self.page_size as i64)
+    }
+
+    /// tags the reservations must all have
+    pub fn get_tags(&self) -> Result<Vec<String>, ReservationError> {
+        normalize_tags(&self.tags)
+    }
     // pub fn new(
     //     uid: impl Into<String>,
     //     rid: impl Into<String>,
@@ -33,7 +84,126 @@ impl ReservationQuery {
 impl Validator for ReservationQuery {
     fn validate(&self) -> Result<(), ReservationError> {
         validate_range(self.start.as_ref(), self.end.as_ref())?;
+        if ReservationMatchMode::from_i32(self.match_mode).is_none() {
+            return Err(ReservationError::InvalidMatchMode(self.match_mode));
+        }
+        self.get_statuses()?;
+        self.get_page_size()?;
+        self.get_sort()?;
+        self.get_tags()?;
+        for filter in &self.attributes {
+            filter.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ReservationFilter {
+    /// user ids to match, empty for all users
+    pub fn get_user_ids(&self) -> Vec<String> {
+        merge_filter_values(&self.user_id, &self.user_ids)
+    }
+
+    /// resource ids to match, empty for all resources
+    pub fn get_resource_ids(&self) -> Vec<String> {
+        merge_filter_values(&self.resource_id, &self.resource_ids)
+    }
+
+    /// statuses to match, empty for all statuses
+    pub fn get_statuses(&self) -> Result<Vec<ReservationStatus>, ReservationError> {
+        merge_filter_statuses(self.status, &self.statuses)
+    }
+
+    /// the page size to use, see normalize_page_size
+    pub fn get_page_size(&self) -> Result<i64, ReservationError> {
+        normalize_page_size(self.page_size)
+    }
+
+    /// tags the reservations must all have
+    pub fn get_tags(&self) -> Result<Vec<String>, ReservationError> {
+        normalize_tags(&self.tags)
+    }
+}
+
+impl Validator for ReservationFilter {
+    fn validate(&self) -> Result<(), ReservationError> {
+        self.get_statuses()?;
+        self.get_page_size()?;
+        self.get_tags()?;
+        for filter in &self.attributes {
+            filter.validate()?;
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_values_should_merge_single_and_repeated() {
+        let query = ReservationQuery {
+            user_id: "hyx".into(),
+            user_ids: vec!["tyr".into(), "hyx".into(), "".into()],
+            ..Default::default()
+        };
+        assert_eq!(query.get_user_ids(), vec!["hyx", "tyr"]);
+        assert!(query.get_resource_ids().is_empty());
+    }
+
+    #[test]
+    fn unknown_status_should_match_all() {
+        let filter = ReservationFilter::default();
+        assert!(filter.get_statuses().unwrap().is_empty());
+
+        let filter = ReservationFilter {
+            status: ReservationStatus::Unknown as i32,
+            statuses: vec![
+                ReservationStatus::Confirmed as i32,
+                ReservationStatus::Pending as i32,
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.get_statuses().unwrap(),
+            vec![ReservationStatus::Confirmed, ReservationStatus::Pending]
+        );
+
+        let query = ReservationQuery {
+            statuses: vec![42],
+            ..Default::default()
+        };
+        assert_eq!(query.validate(), Err(ReservationError::InvalidStatus(42)));
+    }
+
+    #[test]
+    fn page_size_should_default_and_clamp() {
+        let query = ReservationQuery::default();
+        assert_eq!(query.get_page_size().unwrap(), 10);
+        assert_eq!(query.get_page(), 1);
+
+        let query = ReservationQuery {
+            page_size: 5,
+            ..Default::default()
+        };
+        assert_eq!(query.get_page_size().unwrap(), 5);
+
+        let filter = ReservationFilter {
+            page_size: 1000,
+            ..Default::default()
+        };
+        assert_eq!(filter.get_page_size().unwrap(), 100);
+
+        let filter = ReservationFilter {
+            page_size: -1,
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.validate(),
+            Err(ReservationError::InvalidPageSize(-1))
+        );
+    }
+}
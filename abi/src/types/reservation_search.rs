@@ -0,0 +1,246 @@
+use crate::{
+    attribute_value, normalize_page_size, Reservation, ReservationError, ReservationSearch,
+    Validator,
+};
+
+/// length of a snippet in characters, without the ellipses
+const SNIPPET_LEN: usize = 160;
+
+impl ReservationSearch {
+    /// the page to return, starting at 1
+    pub fn get_page(&self) -> i64 {
+        self.page.max(1) as i64
+    }
+
+    /// the page size to use, see normalize_page_size
+    pub fn get_page_size(&self) -> Result<i64, ReservationError> {
+        normalize_page_size(self.page_size as i64)
+    }
+
+    /// lowercase words of the query to highlight, excluded words and OR are left out.
+    /// a run of chinese, japanese or korean characters is a word of its own
+    pub fn get_terms(&self) -> Vec<String> {
+        let mut terms: Vec<String> = vec![];
+        for word in self.query.split(|c: char| c.is_whitespace() || c == '"') {
+            if word.starts_with('-') || word == "OR" || word == "or" {
+                continue;
+            }
+            let mut term = String::new();
+            for c in word.chars().flat_map(char::to_lowercase) {
+                let boundary = term.chars().last().is_some_and(|l| is_cjk(l) != is_cjk(c));
+                if !c.is_alphanumeric() || boundary {
+                    push_term(&mut terms, std::mem::take(&mut term));
+                }
+                if c.is_alphanumeric() {
+                    term.push(c);
+                }
+            }
+            push_term(&mut terms, term);
+        }
+        terms
+    }
+}
+
+impl Validator for ReservationSearch {
+    fn validate(&self) -> Result<(), ReservationError> {
+        if self.query.trim().is_empty() {
+            return Err(ReservationError::InvalidSearchQuery("empty query".into()));
+        }
+        self.get_page_size()?;
+        Ok(())
+    }
+}
+
+/// the html escaped part of the text around the first match of the terms, matches are wrapped
+/// in <b></b>. A latin term matches the words it starts, so a stemmed match is highlighted too,
+/// a chinese, japanese or korean term matches anywhere
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let merged = find_matches(&chars, terms);
+
+    let (start, end) = if chars.len() <= SNIPPET_LEN {
+        (0, chars.len())
+    } else {
+        let first = merged.first().map_or(0, |m| m.0);
+        let start = first
+            .saturating_sub(SNIPPET_LEN / 4)
+            .min(chars.len() - SNIPPET_LEN);
+        (start, start + SNIPPET_LEN)
+    };
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut pos = start;
+    for (s, e) in merged {
+        let (s, e) = (s.max(start), e.min(end));
+        if s >= e {
+            continue;
+        }
+        escape_into(&mut snippet, &chars[pos..s]);
+        snippet.push_str("<b>");
+        escape_into(&mut snippet, &chars[s..e]);
+        snippet.push_str("</b>");
+        pos = e;
+    }
+    escape_into(&mut snippet, &chars[pos..end]);
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// the sorted, non overlapping ranges of chars matching the terms
+fn find_matches(chars: &[char], terms: &[String]) -> Vec<(usize, usize)> {
+    let lower: Vec<char> = chars.iter().map(|&c| to_lower(c)).collect();
+
+    let mut matches: Vec<(usize, usize)> = vec![];
+    for term in terms {
+        let term: Vec<char> = term.chars().map(to_lower).collect();
+        let Some(&first) = term.first() else { continue };
+        let mut i = 0;
+        while i + term.len() <= lower.len() {
+            let word_start = i == 0 || !lower[i - 1].is_alphanumeric() || is_cjk(lower[i - 1]);
+            if lower[i..i + term.len()] != term[..] || !(is_cjk(first) || word_start) {
+                i += 1;
+                continue;
+            }
+            let mut end = i + term.len();
+            if !is_cjk(first) {
+                while end < lower.len() && lower[end].is_alphanumeric() && !is_cjk(lower[end]) {
+                    end += 1;
+                }
+            }
+            matches.push((i, end));
+            i = end;
+        }
+    }
+    matches.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = vec![];
+    for (start, end) in matches {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// the snippet of the first field of the reservation matching the terms: the note, the tags,
+/// then the string attributes by name. The note if none of them does, as for a stemmed match
+/// highlight doesn't find
+pub fn search_snippet(rsvp: &Reservation, terms: &[String]) -> String {
+    let mut attributes: Vec<_> = rsvp.attributes.iter().collect();
+    attributes.sort_unstable_by_key(|(name, _)| *name);
+    let strings = attributes.into_iter().filter_map(|(_, v)| match &v.value {
+        Some(attribute_value::Value::StringValue(s)) => Some(s.clone()),
+        _ => None,
+    });
+    let field = [rsvp.note.clone(), rsvp.tags.join(", ")]
+        .into_iter()
+        .chain(strings)
+        .find(|field| !find_matches(&field.chars().collect::<Vec<_>>(), terms).is_empty());
+    highlight(field.as_deref().unwrap_or(&rsvp.note), terms)
+}
+
+/// same ranges as rsvp.cjk_lexemes: kana, cjk ideographs and hangul syllables
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{ac00}'..='\u{d7af}')
+}
+
+/// one char for one char, so positions in the lowercase text are positions in the text
+fn to_lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn push_term(terms: &mut Vec<String>, term: String) {
+    if !term.is_empty() && !terms.contains(&term) {
+        terms.push(term);
+    }
+}
+
+fn escape_into(out: &mut String, chars: &[char]) {
+    for &c in chars {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(query: &str) -> ReservationSearch {
+        ReservationSearch {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn get_terms_should_skip_excluded_words() {
+        assert_eq!(
+            search(r#""Acme demo" OR 会议室演示 -vip acme"#).get_terms(),
+            vec!["acme", "demo", "会议室演示"]
+        );
+        assert_eq!(
+            search("room-421 下午Demo").get_terms(),
+            vec!["room", "421", "下午", "demo"]
+        );
+        assert!(search("  ").validate().is_err());
+    }
+
+    #[test]
+    fn highlight_should_wrap_matches() {
+        let terms = search("acme 演示").get_terms();
+        assert_eq!(
+            highlight("Demos for <ACME> & 和Acme的演示", &terms),
+            "Demos for &lt;<b>ACME</b>&gt; &amp; 和<b>Acme</b>的<b>演示</b>"
+        );
+        assert_eq!(highlight("academy", &terms), "academy");
+        assert_eq!(
+            highlight("the acme demonstration", &search("demo").get_terms()),
+            "the acme <b>demonstration</b>"
+        );
+    }
+
+    #[test]
+    fn highlight_should_cut_long_text_around_first_match() {
+        let text = format!("{} acme {}", "a".repeat(200), "b".repeat(200));
+        let snippet = highlight(&text, &search("acme").get_terms());
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains(" <b>acme</b> "));
+        assert_eq!(snippet.chars().count(), SNIPPET_LEN + 2 + "<b></b>".len());
+
+        let snippet = highlight(&text, &search("zzz").get_terms());
+        assert!(snippet.starts_with("aaa") && snippet.ends_with('…'));
+    }
+
+    #[test]
+    fn search_snippet_should_use_matching_field() {
+        let rsvp = Reservation {
+            note: "weekly sync".into(),
+            tags: vec!["board".into(), "demo".into()],
+            attributes: [("customer", "Acme Inc"), ("contact", "acme sales")]
+                .into_iter()
+                .map(|(k, v)| {
+                    let value = attribute_value::Value::StringValue(v.into());
+                    (k.to_string(), crate::AttributeValue { value: Some(value) })
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let snippet = |query: &str| search_snippet(&rsvp, &search(query).get_terms());
+        assert_eq!(snippet("sync demo"), "weekly <b>sync</b>");
+        assert_eq!(snippet("demo"), "board, <b>demo</b>");
+        assert_eq!(snippet("acme"), "<b>acme</b> sales");
+        assert_eq!(snippet("syncing"), "weekly sync");
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    convert_to_utc_time, Reservation, ReservationError, ReservationQuery, ReservationSort,
+    ReservationSortField, ReservationStatus,
+};
+
+/// value of a sort key for a reservation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortValue {
+    Time(DateTime<Utc>),
+    Text(String),
+    /// the end of an open ended reservation
+    Null,
+}
+
+impl ReservationSortField {
+    /// the value of the field for the reservation
+    pub fn value_of(&self, rsvp: &Reservation) -> SortValue {
+        let time = |ts: &Option<prost_types::Timestamp>| match ts {
+            Some(ts) => SortValue::Time(convert_to_utc_time(ts.clone())),
+            None => SortValue::Null,
+        };
+        match self {
+            ReservationSortField::Start => time(&rsvp.start),
+            ReservationSortField::End => time(&rsvp.end),
+            ReservationSortField::CreatedAt => time(&rsvp.created_at),
+            ReservationSortField::UserId => SortValue::Text(rsvp.user_id.clone()),
+            ReservationSortField::ResourceId => SortValue::Text(rsvp.resource_id.clone()),
+            ReservationSortField::Status => SortValue::Text(
+                ReservationStatus::from_i32(rsvp.status)
+                    .unwrap_or(ReservationStatus::Unknown)
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl ReservationSort {
+    pub fn new(field: ReservationSortField, desc: bool) -> Self {
+        Self {
+            field: field as i32,
+            desc,
+        }
+    }
+
+    pub fn get_field(&self) -> Result<ReservationSortField, ReservationError> {
+        ReservationSortField::from_i32(self.field)
+            .ok_or(ReservationError::InvalidSortField(self.field))
+    }
+}
+
+impl ReservationQuery {
+    /// sort keys in order of precedence, a field listed twice only counts the first time.
+    /// without keys the query is sorted by start in the direction of desc
+    pub fn get_sort(&self) -> Result<Vec<ReservationSort>, ReservationError> {
+        if self.sort.is_empty() {
+            return Ok(vec![ReservationSort::new(
+                ReservationSortField::Start,
+                self.desc,
+            )]);
+        }
+
+        let mut sort: Vec<ReservationSort> = Vec::with_capacity(self.sort.len());
+        for key in &self.sort {
+            key.get_field()?;
+            if !sort.iter().any(|k| k.field == key.field) {
+                sort.push(key.clone());
+            }
+        }
+        Ok(sort)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_sort_should_default_to_start() {
+        let query = ReservationQuery {
+            desc: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            query.get_sort().unwrap(),
+            vec![ReservationSort::new(ReservationSortField::Start, true)]
+        );
+
+        let query = ReservationQuery {
+            sort: vec![
+                ReservationSort::new(ReservationSortField::Status, false),
+                ReservationSort::new(ReservationSortField::End, true),
+                ReservationSort::new(ReservationSortField::Status, true),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            query.get_sort().unwrap(),
+            vec![
+                ReservationSort::new(ReservationSortField::Status, false),
+                ReservationSort::new(ReservationSortField::End, true),
+            ]
+        );
+
+        let query = ReservationQuery {
+            sort: vec![ReservationSort {
+                field: 42,
+                desc: false,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            query.get_sort(),
+            Err(ReservationError::InvalidSortField(42))
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use core::fmt;
 
-use crate::{ReservationStatus, RsvpStatus};
+use crate::{ReservationError, ReservationStatus, RsvpStatus};
 
 impl From<RsvpStatus> for ReservationStatus {
     fn from(value: RsvpStatus) -> Self {
@@ -8,6 +8,11 @@ impl From<RsvpStatus> for ReservationStatus {
             RsvpStatus::Pending => ReservationStatus::Pending,
             RsvpStatus::Blocked => ReservationStatus::Blocked,
             RsvpStatus::Confirmed => ReservationStatus::Confirmed,
+            RsvpStatus::CheckedIn => ReservationStatus::CheckedIn,
+            RsvpStatus::Completed => ReservationStatus::Completed,
+            RsvpStatus::Cancelled => ReservationStatus::Cancelled,
+            RsvpStatus::Expired => ReservationStatus::Expired,
+            RsvpStatus::NoShow => ReservationStatus::NoShow,
             RsvpStatus::Unknown => ReservationStatus::Unknown,
         }
     }
@@ -19,7 +24,107 @@ impl fmt::Display for ReservationStatus {
             ReservationStatus::Pending => write!(f, "pending"),
             ReservationStatus::Blocked => write!(f, "blocked"),
             ReservationStatus::Confirmed => write!(f, "confirmed"),
+            ReservationStatus::CheckedIn => write!(f, "checked_in"),
+            ReservationStatus::Completed => write!(f, "completed"),
+            ReservationStatus::Cancelled => write!(f, "cancelled"),
+            ReservationStatus::Expired => write!(f, "expired"),
+            ReservationStatus::NoShow => write!(f, "no_show"),
             ReservationStatus::Unknown => write!(f, "unknown"),
         }
     }
 }
+
+impl ReservationStatus {
+    /// a new reservation could only start as pending, confirmed or blocked
+    pub fn is_initial(&self) -> bool {
+        matches!(
+            self,
+            ReservationStatus::Pending | ReservationStatus::Confirmed | ReservationStatus::Blocked
+        )
+    }
+
+    /// no transition is allowed out of a final status
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ReservationStatus::Completed
+                | ReservationStatus::Cancelled
+                | ReservationStatus::Expired
+                | ReservationStatus::NoShow
+        )
+    }
+
+    pub fn can_transition_to(&self, to: ReservationStatus) -> bool {
+        use ReservationStatus::*;
+
+        matches!(
+            (self, to),
+            (Pending, Confirmed | Cancelled | Expired)
+                | (Confirmed, CheckedIn | Cancelled | NoShow)
+                | (CheckedIn, Completed)
+                | (Blocked, Cancelled)
+        )
+    }
+
+    /// validate a status change to the given status. Cancelling is left to cancel, which records
+    /// who cancelled and why
+    pub fn change_to(&self, to: ReservationStatus) -> Result<(), ReservationError> {
+        if to == ReservationStatus::Cancelled && *self != to {
+            return Err(ReservationError::CancelWithStatus);
+        }
+        self.transition_to(to)
+    }
+
+    /// validate the transition to the given status. Staying in the same status is a no-op
+    pub fn transition_to(&self, to: ReservationStatus) -> Result<(), ReservationError> {
+        if *self == to || self.can_transition_to(to) {
+            Ok(())
+        } else {
+            Err(ReservationError::InvalidTransition(*self, to))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifecycle_transitions_should_work() {
+        let path = [
+            ReservationStatus::Pending,
+            ReservationStatus::Confirmed,
+            ReservationStatus::CheckedIn,
+            ReservationStatus::Completed,
+        ];
+        for pair in path.windows(2) {
+            assert!(pair[0].transition_to(pair[1]).is_ok());
+        }
+        assert!(ReservationStatus::Pending
+            .transition_to(ReservationStatus::Pending)
+            .is_ok());
+        assert!(ReservationStatus::Blocked
+            .transition_to(ReservationStatus::Cancelled)
+            .is_ok());
+    }
+
+    #[test]
+    fn illegal_transitions_should_be_rejected() {
+        let err = ReservationStatus::Blocked
+            .transition_to(ReservationStatus::Confirmed)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ReservationError::InvalidTransition(
+                ReservationStatus::Blocked,
+                ReservationStatus::Confirmed
+            )
+        );
+        assert!(ReservationStatus::Pending
+            .transition_to(ReservationStatus::CheckedIn)
+            .is_err());
+        assert!(ReservationStatus::Cancelled
+            .transition_to(ReservationStatus::Pending)
+            .is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::str::FromStr;
+
+use prost_types::FieldMask;
+
+use crate::{normalize_tags, Reservation, ReservationError, ReservationStatus, Validator};
+
+/// fields of a reservation that could be referenced by an update mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservationField {
+    UserId,
+    Status,
+    ResourceId,
+    Start,
+    End,
+    Note,
+    Tags,
+    Attributes,
+}
+
+impl FromStr for ReservationField {
+    type Err = ReservationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user_id" => Ok(Self::UserId),
+            "status" => Ok(Self::Status),
+            "resource_id" => Ok(Self::ResourceId),
+            "start" => Ok(Self::Start),
+            "end" => Ok(Self::End),
+            "note" => Ok(Self::Note),
+            "tags" => Ok(Self::Tags),
+            "attributes" => Ok(Self::Attributes),
+            "id" | "version" | "created_at" => Err(ReservationError::ImmutableField(s.to_string())),
+            _ => Err(ReservationError::InvalidUpdateMask(s.to_string())),
+        }
+    }
+}
+
+impl ReservationField {
+    /// parse all the paths in the mask, an empty mask is rejected
+    pub fn parse_mask(mask: &FieldMask) -> Result<Vec<Self>, ReservationError> {
+        if mask.paths.is_empty() {
+            return Err(ReservationError::InvalidUpdateMask("empty mask".into()));
+        }
+
+        let mut fields = Vec::with_capacity(mask.paths.len());
+        for path in &mask.paths {
+            let field = path.parse()?;
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+        Ok(fields)
+    }
+}
+
+impl Reservation {
+    /// copy the masked fields from `partial` into self, then validate the result
+    pub fn apply_update(
+        &mut self,
+        partial: &Reservation,
+        fields: &[ReservationField],
+    ) -> Result<(), ReservationError> {
+        for field in fields {
+            match field {
+                ReservationField::UserId => self.user_id = partial.user_id.clone(),
+                ReservationField::Status => match ReservationStatus::from_i32(partial.status) {
+                    Some(ReservationStatus::Unknown) | None => {
+                        return Err(ReservationError::InvalidStatus(partial.status))
+                    }
+                    Some(to) => {
+                        let from = ReservationStatus::from_i32(self.status)
+                            .unwrap_or(ReservationStatus::Unknown);
+                        from.change_to(to)?;
+                        self.status = partial.status;
+                    }
+                },
+                ReservationField::ResourceId => self.resource_id = partial.resource_id.clone(),
+                ReservationField::Start => {
+                    if partial.start.is_none() {
+                        return Err(ReservationError::InvalidTimespan);
+                    }
+                    self.start = partial.start.clone();
+                }
+                // an empty end makes the reservation open ended
+                ReservationField::End => self.end = partial.end.clone(),
+                ReservationField::Note => self.note = partial.note.clone(),
+                ReservationField::Tags => self.tags = normalize_tags(&partial.tags)?,
+                ReservationField::Attributes => self.attributes = partial.attributes.clone(),
+            }
+        }
+
+        self.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(paths: &[&str]) -> FieldMask {
+        FieldMask {
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn parse_mask_should_work() {
+        let fields = ReservationField::parse_mask(&mask(&["note", "start", "note"])).unwrap();
+        assert_eq!(
+            fields,
+            vec![ReservationField::Note, ReservationField::Start]
+        );
+    }
+
+    #[test]
+    fn parse_mask_should_reject_immutable_and_unknown_paths() {
+        let err = ReservationField::parse_mask(&mask(&["note", "id"])).unwrap_err();
+        assert_eq!(err, ReservationError::ImmutableField("id".into()));
+
+        let err = ReservationField::parse_mask(&mask(&["color"])).unwrap_err();
+        assert_eq!(err, ReservationError::InvalidUpdateMask("color".into()));
+
+        assert!(ReservationField::parse_mask(&mask(&[])).is_err());
+    }
+
+    #[test]
+    fn apply_update_should_only_touch_masked_fields() {
+        let mut rsvp = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-11-20T12:00:00-0700".parse().unwrap(),
+            "2022-11-22T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+        let partial = Reservation {
+            user_id: "tyr".into(),
+            note: "world".into(),
+            ..Default::default()
+        };
+
+        rsvp.apply_update(&partial, &[ReservationField::Note])
+            .unwrap();
+        assert_eq!(rsvp.note, "world");
+        assert_eq!(rsvp.user_id, "hyx");
+
+        let err = rsvp
+            .apply_update(&partial, &[ReservationField::Start])
+            .unwrap_err();
+        assert_eq!(err, ReservationError::InvalidTimespan);
+
+        let err = rsvp
+            .apply_update(&partial, &[ReservationField::Status])
+            .unwrap_err();
+        assert_eq!(err, ReservationError::InvalidStatus(0));
+
+        let partial = Reservation {
+            status: ReservationStatus::Completed as i32,
+            ..Default::default()
+        };
+        let err = rsvp
+            .apply_update(&partial, &[ReservationField::Status])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ReservationError::InvalidTransition(
+                ReservationStatus::Pending,
+                ReservationStatus::Completed
+            )
+        );
+
+        // cancel records who cancelled and why, an update can't skip it
+        let partial = Reservation {
+            status: ReservationStatus::Cancelled as i32,
+            ..Default::default()
+        };
+        let err = rsvp
+            .apply_update(&partial, &[ReservationField::Status])
+            .unwrap_err();
+        assert_eq!(err, ReservationError::CancelWithStatus);
+    }
+}
@@ -0,0 +1,2 @@
+DROP INDEX rsvp.reservations_group_id_idx;
+ALTER TABLE rsvp.reservations DROP COLUMN group_id;
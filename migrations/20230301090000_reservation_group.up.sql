@@ -0,0 +1,4 @@
+
+-- reservations made together in one batch share a group id
+ALTER TABLE rsvp.reservations ADD COLUMN group_id uuid;
+CREATE INDEX reservations_group_id_idx ON rsvp.reservations (group_id);
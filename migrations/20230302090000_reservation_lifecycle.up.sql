@@ -0,0 +1,35 @@
+
+-- reservation lifecycle: pending -> confirmed -> checked_in -> completed, plus cancelled/expired/no_show
+ALTER TYPE rsvp.reservation_status ADD VALUE 'checked_in';
+ALTER TYPE rsvp.reservation_status ADD VALUE 'completed';
+ALTER TYPE rsvp.reservation_status ADD VALUE 'cancelled';
+ALTER TYPE rsvp.reservation_status ADD VALUE 'expired';
+ALTER TYPE rsvp.reservation_status ADD VALUE 'no_show';
+
+-- record the status transition of each change
+ALTER TABLE rsvp.reservation_changes
+    ADD COLUMN from_status rsvp.reservation_status,
+    ADD COLUMN to_status rsvp.reservation_status;
+
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status)
+            VALUES (NEW.id, null, to_jsonb(NEW), 'create', null, NEW.status);
+    ELSIF TG_OP = 'UPDATE' THEN
+        -- if status changed, update reservation_changes
+        IF OLD.status <> NEW.status THEN
+            INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status)
+                VALUES (NEW.id, to_jsonb(OLD), to_jsonb(NEW), 'update', OLD.status, NEW.status);
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status)
+            VALUES (OLD.id, to_jsonb(OLD), null, 'delete', OLD.status, null);
+    END IF;
+    -- notify a channel called reservation_update
+    NOTIFY reservation_update;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
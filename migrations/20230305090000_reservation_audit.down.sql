@@ -0,0 +1,28 @@
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+DECLARE
+    _actor text := NULLIF(current_setting('rsvp.actor', true), '');
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor)
+            VALUES (NEW.id, null, to_jsonb(NEW), 'create', null, NEW.status, _actor);
+    ELSIF TG_OP = 'UPDATE' THEN
+        -- if status changed, update reservation_changes
+        IF OLD.status <> NEW.status THEN
+            INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor)
+                VALUES (NEW.id, to_jsonb(OLD), to_jsonb(NEW), 'update', OLD.status, NEW.status, _actor);
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor)
+            VALUES (OLD.id, to_jsonb(OLD), null, 'delete', OLD.status, null, _actor);
+    END IF;
+    -- notify a channel called reservation_update
+    NOTIFY reservation_update;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE rsvp.reservation_changes
+    DROP COLUMN reason,
+    DROP COLUMN changed_fields;
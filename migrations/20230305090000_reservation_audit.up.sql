@@ -0,0 +1,38 @@
+
+-- why the change was made, and which columns it changed
+ALTER TABLE rsvp.reservation_changes
+    ADD COLUMN reason TEXT,
+    ADD COLUMN changed_fields TEXT[];
+
+-- record every update that changes at least one column, the actor and reason are read from
+-- the transaction-local settings rsvp.actor and rsvp.reason
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+DECLARE
+    _actor text := NULLIF(current_setting('rsvp.actor', true), '');
+    _reason text := NULLIF(current_setting('rsvp.reason', true), '');
+    _changed text[];
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor, reason)
+            VALUES (NEW.id, null, to_jsonb(NEW), 'create', null, NEW.status, _actor, _reason);
+    ELSIF TG_OP = 'UPDATE' THEN
+        -- compare the old and new row column by column
+        SELECT array_agg(n.key ORDER BY n.key) INTO _changed
+            FROM jsonb_each(to_jsonb(NEW)) n
+            LEFT JOIN jsonb_each(to_jsonb(OLD)) o ON n.key = o.key
+            WHERE n.value IS DISTINCT FROM o.value;
+        IF _changed IS NOT NULL THEN
+            INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor, reason, changed_fields)
+                VALUES (NEW.id, to_jsonb(OLD), to_jsonb(NEW), 'update', OLD.status, NEW.status, _actor, _reason, _changed);
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor, reason)
+            VALUES (OLD.id, to_jsonb(OLD), null, 'delete', OLD.status, null, _actor, _reason);
+    END IF;
+    -- notify a channel called reservation_update
+    NOTIFY reservation_update;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
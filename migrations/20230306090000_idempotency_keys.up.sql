@@ -0,0 +1,10 @@
+-- results of mutating calls, keyed by the client supplied idempotency key
+-- a NULL response means the call is still in progress
+CREATE TABLE rsvp.idempotency_keys (
+    key VARCHAR(128) PRIMARY KEY,
+    method VARCHAR(64) NOT NULL,
+    response BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX idempotency_keys_expires_at_idx ON rsvp.idempotency_keys (expires_at);
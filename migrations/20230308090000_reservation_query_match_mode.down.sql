@@ -0,0 +1,58 @@
+DROP FUNCTION rsvp.query(text, text, TSTZRANGE, rsvp.reservation_status, integer, bool, integer, bool, text);
+
+CREATE OR REPLACE FUNCTION rsvp.query(
+    uid text,
+    rid text,
+    during TSTZRANGE,
+    status rsvp.reservation_status,
+    page integer DEFAULT 1,
+    is_desc bool DEFAULT true,
+    page_size integer DEFAULT 10,
+    include_cancelled bool DEFAULT false
+) RETURNS TABLE(LIKE rsvp.reservations )AS $$
+DECLARE
+    _sql text;
+BEGIN
+    -- if page_size not between 10 and 100, set it to 10
+    IF page_size < 10 OR page_size > 100 THEN
+        page_size := 10;
+    END IF;
+
+    IF page < 1 THEN
+        page := 1;
+    END IF;
+    -- format the query based on parameters
+    _sql := format(
+        'SELECT * FROM rsvp.reservations WHERE %L @> timespan AND %s AND %s AND %s ORDER BY lower(timespan) %s LIMIT %L::Integer OFFSET %L::Integer',
+        during,
+        CASE
+            WHEN uid IS NULL AND rid IS NULL THEN 'TRUE'
+            WHEN uid IS NULL THEN 'resource_id = ' || quote_literal(rid)
+            WHEN rid IS NULL THEN 'user_id = ' || quote_literal(uid)
+            ELSE 'user_id =' || quote_literal(uid) ||'AND resource_id =' || quote_literal(rid)
+        END,
+        CASE
+            WHEN status IS NULL THEN 'TRUE'
+            ELSE 'status = ' || quote_literal(status)
+        END,
+        CASE
+            WHEN include_cancelled OR status = 'cancelled' THEN 'TRUE'
+            ELSE 'status <> ''cancelled'''
+        END,
+        CASE
+            WHEN is_desc THEN 'DESC'
+            ELSE 'ASC'
+        END,
+        page_size,
+        (page - 1) * page_size
+
+    );
+
+    -- execute the query
+    RETURN QUERY EXECUTE _sql;
+
+    -- log the sql
+    RAISE NOTICE '%', _sql;
+
+END;
+$$ LANGUAGE plpgsql;
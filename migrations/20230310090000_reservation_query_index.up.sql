@@ -0,0 +1,6 @@
+-- queries are built with bound parameters by the reservation crate now
+DROP FUNCTION rsvp.query(text[], text[], TSTZRANGE, rsvp.reservation_status[], integer, bool, integer, bool, text);
+DROP FUNCTION rsvp.filter(text[], text[], rsvp.reservation_status[], bigint, bool, integer, bool);
+DROP FUNCTION rsvp.any_of(text, text[]);
+
+CREATE INDEX reservations_timespan_idx ON rsvp.reservations USING gist (timespan);
@@ -0,0 +1,2 @@
+-- query pages are sorted and continued by (lower(timespan), id)
+CREATE INDEX reservations_start_id_idx ON rsvp.reservations (lower(timespan), id);
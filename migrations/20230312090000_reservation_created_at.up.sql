@@ -0,0 +1,9 @@
+-- when the reservation was made, queries could be sorted by it
+ALTER TABLE rsvp.reservations ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+-- existing reservations take the time of their create record, the backfill is not a change
+ALTER TABLE rsvp.reservations DISABLE TRIGGER USER;
+UPDATE rsvp.reservations r SET created_at = c.changed_at
+    FROM rsvp.reservation_changes c
+    WHERE c.reservation_id = r.id::text AND c.op = 'create';
+ALTER TABLE rsvp.reservations ENABLE TRIGGER USER;
@@ -0,0 +1,8 @@
+-- free form tags and typed attributes of a reservation
+ALTER TABLE rsvp.reservations
+    ADD COLUMN tags JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+
+-- containment of tags and attribute values
+CREATE INDEX reservations_tags_idx ON rsvp.reservations USING gin (tags jsonb_path_ops);
+CREATE INDEX reservations_attributes_idx ON rsvp.reservations USING gin (attributes jsonb_path_ops);
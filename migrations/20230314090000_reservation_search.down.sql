@@ -0,0 +1,6 @@
+DROP INDEX rsvp.reservations_search_idx;
+DROP FUNCTION rsvp.search_query(text);
+DROP FUNCTION rsvp.search_document(text, jsonb, jsonb);
+DROP FUNCTION rsvp.text_vector(text);
+DROP FUNCTION rsvp.without_cjk(text);
+DROP FUNCTION rsvp.cjk_lexemes(text, bool);
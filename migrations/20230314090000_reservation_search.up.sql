@@ -0,0 +1,58 @@
+-- the text search parser keeps a run of chinese, japanese or korean characters as one word,
+-- so a search for part of the run wouldn't match. The runs are split into overlapping bigrams
+-- instead, with the single characters for documents so a one character search matches too
+CREATE OR REPLACE FUNCTION rsvp.cjk_lexemes(doc text, with_unigrams bool) RETURNS text[] AS $$
+DECLARE
+    run text;
+    lexemes text[] := '{}';
+BEGIN
+    FOR run IN SELECT m[1] FROM regexp_matches(coalesce(doc, ''), '([぀-ヿ㐀-䶿一-鿿가-힯]+)', 'g') AS m LOOP
+        IF with_unigrams OR length(run) = 1 THEN
+            FOR i IN 1..length(run) LOOP
+                lexemes := lexemes || substr(run, i, 1);
+            END LOOP;
+        END IF;
+        FOR i IN 1..length(run) - 1 LOOP
+            lexemes := lexemes || substr(run, i, 2);
+        END LOOP;
+    END LOOP;
+    RETURN lexemes;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- the text left for the parser, without the runs split by rsvp.cjk_lexemes
+CREATE OR REPLACE FUNCTION rsvp.without_cjk(doc text) RETURNS text AS $$
+    SELECT regexp_replace(coalesce(doc, ''), '[぀-ヿ㐀-䶿一-鿿가-힯]+', ' ', 'g');
+$$ LANGUAGE sql IMMUTABLE;
+
+-- words of a text, stemmed in english and as written for other languages. The bigrams get
+-- positions too, setweight only weights lexemes with positions
+CREATE OR REPLACE FUNCTION rsvp.text_vector(doc text) RETURNS tsvector AS $$
+    SELECT to_tsvector('english', rsvp.without_cjk(doc))
+        || to_tsvector('simple', rsvp.without_cjk(doc))
+        || (SELECT coalesce(string_agg(quote_literal(lexeme) || ':' || pos, ' '), '')::tsvector
+            FROM unnest(rsvp.cjk_lexemes(doc, true)) WITH ORDINALITY AS t(lexeme, pos));
+$$ LANGUAGE sql IMMUTABLE;
+
+-- the searchable words of a reservation: the note ranks above tags, tags above string attributes
+CREATE OR REPLACE FUNCTION rsvp.search_document(note text, tags jsonb, attributes jsonb) RETURNS tsvector AS $$
+    SELECT setweight(rsvp.text_vector(note), 'A')
+        || setweight(rsvp.text_vector(
+            (SELECT string_agg(tag, ' ') FROM jsonb_array_elements_text(tags) AS tag)), 'B')
+        || setweight(rsvp.text_vector(
+            (SELECT string_agg(value #>> '{}', ' ') FROM jsonb_each(attributes)
+                WHERE jsonb_typeof(value) = 'string')), 'C');
+$$ LANGUAGE sql IMMUTABLE;
+
+-- a websearch style query, with the chinese, japanese and korean words as bigrams which must all match
+CREATE OR REPLACE FUNCTION rsvp.search_query(q text) RETURNS tsquery AS $$
+    SELECT (websearch_to_tsquery('english', rsvp.without_cjk(q))
+            || websearch_to_tsquery('simple', rsvp.without_cjk(q)))
+        && coalesce(
+            (SELECT string_agg(quote_literal(lexeme), ' & ')
+                FROM unnest(rsvp.cjk_lexemes(q, false)) AS lexeme),
+            '')::tsquery;
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE INDEX reservations_search_idx ON rsvp.reservations
+    USING gin (rsvp.search_document(note, tags, attributes));
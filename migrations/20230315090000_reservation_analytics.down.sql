@@ -0,0 +1 @@
+DROP INDEX rsvp.reservation_changes_cancelled_idx;
@@ -0,0 +1,3 @@
+-- cancellations are counted from the change log by analytics, cancelled reservations may be purged
+CREATE INDEX reservation_changes_cancelled_idx ON rsvp.reservation_changes (reservation_id)
+    WHERE op = 'update' AND to_status = 'cancelled';
@@ -0,0 +1,9 @@
+-- the time zone of a resource, exports show reservation times in it. A resource without a row is in UTC
+CREATE TABLE rsvp.resources (
+    id VARCHAR(64) NOT NULL,
+    time_zone TEXT NOT NULL DEFAULT 'UTC',
+
+    CONSTRAINT resources_pkey PRIMARY KEY (id),
+    -- postgres rejects a time zone it doesn't know
+    CONSTRAINT resources_time_zone CHECK (now() AT TIME ZONE time_zone IS NOT NULL)
+);
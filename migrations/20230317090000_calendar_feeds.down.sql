@@ -0,0 +1,6 @@
+DROP INDEX rsvp.reservation_changes_old_user_id_idx;
+DROP INDEX rsvp.reservation_changes_new_user_id_idx;
+DROP INDEX rsvp.reservation_changes_old_resource_id_idx;
+DROP INDEX rsvp.reservation_changes_new_resource_id_idx;
+DROP TABLE rsvp.calendar_feeds;
+DROP TYPE rsvp.feed_kind;
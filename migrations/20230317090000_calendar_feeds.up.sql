@@ -0,0 +1,23 @@
+-- whose reservations a calendar feed shows
+CREATE TYPE rsvp.feed_kind AS ENUM ('resource', 'user');
+
+-- live icalendar feeds, a feed is read with its token and only the hash of the token is kept
+CREATE TABLE rsvp.calendar_feeds (
+    id BIGSERIAL NOT NULL,
+    kind rsvp.feed_kind NOT NULL,
+    subject VARCHAR(64) NOT NULL,
+    token_hash BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at TIMESTAMPTZ,
+
+    CONSTRAINT calendar_feeds_pkey PRIMARY KEY (id),
+    CONSTRAINT calendar_feeds_token_hash_key UNIQUE (token_hash)
+);
+CREATE INDEX calendar_feeds_subject_idx ON rsvp.calendar_feeds (subject);
+
+-- the version of a feed is read from the changes of the reservations of its resource or user, a
+-- reservation could be moved to another resource or user so both sides of a change are indexed
+CREATE INDEX reservation_changes_new_resource_id_idx ON rsvp.reservation_changes ((new->>'resource_id'), changed_at);
+CREATE INDEX reservation_changes_old_resource_id_idx ON rsvp.reservation_changes ((old->>'resource_id'), changed_at);
+CREATE INDEX reservation_changes_new_user_id_idx ON rsvp.reservation_changes ((new->>'user_id'), changed_at);
+CREATE INDEX reservation_changes_old_user_id_idx ON rsvp.reservation_changes ((old->>'user_id'), changed_at);
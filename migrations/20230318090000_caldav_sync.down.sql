@@ -0,0 +1,2 @@
+DROP TABLE rsvp.caldav_events;
+DROP TABLE rsvp.caldav_collections;
@@ -0,0 +1,33 @@
+-- a resource mirrored into a caldav collection, with the state of the last sync
+CREATE TABLE rsvp.caldav_collections (
+    id BIGSERIAL NOT NULL,
+    resource_id VARCHAR(64) NOT NULL,
+    url TEXT NOT NULL,
+    -- sync-token of the collection after the last pull, empty before the first one
+    sync_token TEXT NOT NULL DEFAULT '',
+    -- the changes of the resource up to this time are pushed, null before the first push
+    pushed_at TIMESTAMPTZ,
+
+    CONSTRAINT caldav_collections_pkey PRIMARY KEY (id),
+    CONSTRAINT caldav_collections_resource_id_url_key UNIQUE (resource_id, url)
+);
+
+-- events of a collection known to the sync, with the etag they had when last seen or written.
+-- An event is either a reservation pushed by the sync, or made on the caldav side. A declined
+-- event of the caldav side has no reservation
+CREATE TABLE rsvp.caldav_events (
+    collection_id BIGINT NOT NULL,
+    href TEXT NOT NULL,
+    uid TEXT NOT NULL,
+    etag TEXT NOT NULL DEFAULT '',
+    reservation_id BIGINT,
+    -- version of the reservation when it was last written to the event
+    version BIGINT NOT NULL DEFAULT 0,
+    -- the event was made on the caldav side, the sync only changes its PARTSTAT
+    external BOOLEAN NOT NULL,
+
+    CONSTRAINT caldav_events_pkey PRIMARY KEY (collection_id, href),
+    CONSTRAINT caldav_events_collection_id_fkey FOREIGN KEY (collection_id)
+        REFERENCES rsvp.caldav_collections (id) ON DELETE CASCADE
+);
+CREATE INDEX caldav_events_reservation_id_idx ON rsvp.caldav_events (collection_id, reservation_id);
@@ -0,0 +1,3 @@
+DROP INDEX rsvp.reservation_changes_seq_idx;
+
+ALTER TABLE rsvp.reservation_changes DROP COLUMN seq;
@@ -0,0 +1,5 @@
+-- position of a change in the change log, the listeners of the change stream resume after it.
+-- A position is taken when the change is made, so a change may become visible after a later one
+ALTER TABLE rsvp.reservation_changes ADD COLUMN seq BIGSERIAL NOT NULL;
+
+CREATE UNIQUE INDEX reservation_changes_seq_idx ON rsvp.reservation_changes (seq);
@@ -0,0 +1,30 @@
+-- the changed fields of a change are worked out from its old and new rows when it is read, with
+-- the names of the reservation fields, so the column names stored by the trigger are dropped
+ALTER TABLE rsvp.reservation_changes DROP COLUMN changed_fields;
+
+-- record every update that changes at least one column, the actor and reason are read from
+-- the transaction-local settings rsvp.actor and rsvp.reason
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+DECLARE
+    _actor text := NULLIF(current_setting('rsvp.actor', true), '');
+    _reason text := NULLIF(current_setting('rsvp.reason', true), '');
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor, reason)
+            VALUES (NEW.id, null, to_jsonb(NEW), 'create', null, NEW.status, _actor, _reason);
+    ELSIF TG_OP = 'UPDATE' THEN
+        IF to_jsonb(NEW) IS DISTINCT FROM to_jsonb(OLD) THEN
+            INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor, reason)
+                VALUES (NEW.id, to_jsonb(OLD), to_jsonb(NEW), 'update', OLD.status, NEW.status, _actor, _reason);
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        -- update reservation_changes
+        INSERT INTO rsvp.reservation_changes (reservation_id, old, new, op, from_status, to_status, actor, reason)
+            VALUES (OLD.id, to_jsonb(OLD), null, 'delete', OLD.status, null, _actor, _reason);
+    END IF;
+    -- notify a channel called reservation_update
+    NOTIFY reservation_update;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,6 @@
+-- hash of the request a key was used for, a call with the key and another request is refused.
+-- A call in progress holds its key with the transaction of its changes, the key is committed
+-- with the response, so a key without response is left over from an interrupted call
+DELETE FROM rsvp.idempotency_keys WHERE response IS NULL;
+
+ALTER TABLE rsvp.idempotency_keys ADD COLUMN request_hash BYTEA NOT NULL DEFAULT '';
@@ -0,0 +1,882 @@
+use std::collections::HashSet;
+
+use abi::{
+    Reservation, ReservationConflictInfo, ReservationError, ReservationId, ReservationStatus,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use prost_types::FieldMask;
+
+use crate::{
+    calendar::{
+        event_uid, parse_event, set_answer, uid_reservation_id, CalendarWriter, ResourceAnswer,
+    },
+    export::ExportRow,
+    CalDav, CalDavCollection, ChangeContext, ReservationManager, Rsvp, SyncReport,
+};
+
+/// changes committed late could be older than the ones pushed before, the push looks back this
+/// many seconds. Reservations already pushed at their version are skipped
+const PUSH_OVERLAP_SECS: f64 = 60.0;
+/// actor of the changes made by the sync
+const ACTOR: &str = "caldav";
+
+/// an event of a collection known to the sync
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+struct CalDavEvent {
+    href: String,
+    uid: String,
+    etag: String,
+    reservation_id: Option<ReservationId>,
+    version: i64,
+    external: bool,
+}
+
+impl ReservationManager {
+    /// two way sync of a resource with a caldav collection. The events made on the caldav side
+    /// since the last sync are reserved, or declined when they can't be, then the reservations of
+    /// the resource changed since the last sync are written to the collection
+    pub async fn sync_caldav(
+        &self,
+        client: &(impl CalDav + Sync),
+        resource_id: &str,
+        url: &str,
+        user_id: &str,
+    ) -> Result<SyncReport, ReservationError> {
+        let collection = sqlx::query_as(
+            "INSERT INTO rsvp.caldav_collections (resource_id, url) VALUES ($1, $2) ON CONFLICT (resource_id, url) DO UPDATE SET url = EXCLUDED.url RETURNING *",
+        )
+        .bind(resource_id)
+        .bind(url)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let mut sync = CollectionSync {
+            manager: self.with_context(ChangeContext {
+                actor: ACTOR.to_string(),
+                reason: "caldav sync".to_string(),
+            }),
+            client,
+            collection,
+            user_id,
+            report: SyncReport::default(),
+        };
+        sync.pull().await?;
+        sync.push().await?;
+        Ok(sync.report)
+    }
+}
+
+struct CollectionSync<'a, C> {
+    manager: ReservationManager,
+    client: &'a C,
+    collection: CalDavCollection,
+    /// user of the events without one
+    user_id: &'a str,
+    report: SyncReport,
+}
+
+impl<'a, C: CalDav + Sync> CollectionSync<'a, C> {
+    /// apply the changes of the collection since the stored sync token
+    async fn pull(&mut self) -> Result<(), ReservationError> {
+        let url = self.collection.url.clone();
+        let token = self.collection.sync_token.clone();
+        let (delta, full) = match self.client.sync_collection(&url, &token).await {
+            // the server forgot the token, start over from every event
+            Err(ReservationError::InvalidSyncToken) if !token.is_empty() => {
+                (self.client.sync_collection(&url, "").await?, true)
+            }
+            delta => (delta?, token.is_empty()),
+        };
+
+        let mut removed = delta.removed;
+        if full {
+            // a listing of every event has no removals, the known events missing from it are gone
+            let listed: HashSet<&str> = delta.changed.iter().map(|(h, _)| h.as_str()).collect();
+            removed.extend(
+                self.events()
+                    .await?
+                    .into_iter()
+                    .map(|event| event.href)
+                    .filter(|href| !listed.contains(href.as_str())),
+            );
+        }
+        for href in removed {
+            self.event_removed(&href).await?;
+        }
+        for (href, etag) in &delta.changed {
+            self.event_changed(href, etag).await?;
+        }
+
+        sqlx::query("UPDATE rsvp.caldav_collections SET sync_token = $1 WHERE id = $2")
+            .bind(&delta.token)
+            .bind(self.collection.id)
+            .execute(&self.manager.pool)
+            .await?;
+        self.collection.sync_token = delta.token;
+        Ok(())
+    }
+
+    /// write the reservations of the resource changed since the last push
+    async fn push(&mut self) -> Result<(), ReservationError> {
+        let changed: Vec<(ReservationId, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT reservation_id::bigint, max(changed_at) FROM rsvp.reservation_changes WHERE (new->>'resource_id' = $1 OR old->>'resource_id' = $1) AND ($2::timestamptz IS NULL OR changed_at > $2 - $3 * interval '1 second') GROUP BY reservation_id ORDER BY max(changed_at)",
+        )
+        .bind(&self.collection.resource_id)
+        .bind(self.collection.pushed_at)
+        .bind(PUSH_OVERLAP_SECS)
+        .fetch_all(&self.manager.pool)
+        .await?;
+
+        let mut pushed_at = self.collection.pushed_at;
+        for (id, changed_at) in changed {
+            self.push_reservation(id, false).await?;
+            pushed_at = pushed_at.max(Some(changed_at));
+        }
+
+        sqlx::query("UPDATE rsvp.caldav_collections SET pushed_at = $1 WHERE id = $2")
+            .bind(pushed_at)
+            .bind(self.collection.id)
+            .execute(&self.manager.pool)
+            .await?;
+        self.collection.pushed_at = pushed_at;
+        Ok(())
+    }
+
+    async fn event_changed(&mut self, href: &str, etag: &str) -> Result<(), ReservationError> {
+        let known = self.event(href).await?;
+        if let Some(mut event) = known.clone() {
+            // our own write. A server which didn't return the etag of a write gets it here
+            if event.etag == etag || event.etag.is_empty() {
+                if event.etag.is_empty() {
+                    event.etag = etag.to_string();
+                    self.save_event(&event).await?;
+                }
+                return Ok(());
+            }
+        }
+
+        let Some((data, etag)) = self.client.get_event(href).await? else {
+            return Ok(());
+        };
+        match known {
+            // the reservation wins over the edits of its event on the caldav side
+            Some(mut event) if !event.external => {
+                event.etag = etag;
+                self.save_event(&event).await?;
+                match event.reservation_id {
+                    Some(id) => self.push_reservation(id, true).await,
+                    None => Ok(()),
+                }
+            }
+            known => self.answer_event(href, known, &data, etag).await,
+        }
+    }
+
+    /// reserve a new or changed event of the caldav side, and write the answer of the resource
+    /// back to it
+    async fn answer_event(
+        &mut self,
+        href: &str,
+        known: Option<CalDavEvent>,
+        data: &[u8],
+        etag: String,
+    ) -> Result<(), ReservationError> {
+        let parsed = parse_event(data);
+        if let (None, Ok((uid, _))) = (&known, &parsed) {
+            // an event pushed before the state of the sync was lost, the reservation wins. Only
+            // where the push writes it, anyone could copy the uid into an event of their own
+            let pushed = uid_reservation_id(uid).filter(|&id| href == self.pushed_href(id));
+            if let Some(id) = pushed {
+                let event = CalDavEvent {
+                    href: href.to_string(),
+                    uid: uid.clone(),
+                    etag,
+                    reservation_id: Some(id),
+                    version: 0,
+                    external: false,
+                };
+                self.save_event(&event).await?;
+                return self.push_reservation(id, true).await;
+            }
+        }
+
+        let uid = match (&parsed, &known) {
+            (Ok((uid, _)), _) => uid.clone(),
+            (Err(_), Some(event)) => event.uid.clone(),
+            (Err(_), None) => String::new(),
+        };
+        let booked = known.as_ref().and_then(|event| event.reservation_id);
+        let result = match parsed {
+            Ok((_, rsvp)) if rsvp.status == ReservationStatus::Cancelled as i32 => {
+                // cancelled by the organizer, there is nothing to answer
+                if let Some(id) = booked {
+                    self.cancel(id, "the caldav event is cancelled").await?;
+                }
+                let event = CalDavEvent {
+                    href: href.to_string(),
+                    uid,
+                    etag,
+                    reservation_id: None,
+                    version: 0,
+                    external: true,
+                };
+                return self.save_event(&event).await;
+            }
+            Ok((_, rsvp)) => match booked {
+                Some(id) => {
+                    let mask = FieldMask {
+                        paths: vec!["start".into(), "end".into(), "note".into()],
+                    };
+                    self.manager.update(id, rsvp, mask, None).await
+                }
+                None => self.reserve(rsvp).await,
+            },
+            Err(e) => Err(e),
+        };
+
+        let (answer, reservation_id, version) = match result {
+            Ok(rsvp) => (
+                ResourceAnswer::Accepted(rsvp.id),
+                Some(rsvp.id),
+                rsvp.version,
+            ),
+            Err(ReservationError::DbError(e)) => return Err(ReservationError::DbError(e)),
+            Err(e) => {
+                if let Some(id) = booked {
+                    self.cancel(id, "the caldav event moved into a conflict")
+                        .await?;
+                }
+                self.report.declined += 1;
+                (ResourceAnswer::Declined(decline_reason(&e)), None, 0)
+            }
+        };
+        let etag = self.answer(href, data, etag, &answer).await?;
+        let event = CalDavEvent {
+            href: href.to_string(),
+            uid,
+            etag,
+            reservation_id,
+            version,
+            external: true,
+        };
+        self.save_event(&event).await
+    }
+
+    async fn event_removed(&mut self, href: &str) -> Result<(), ReservationError> {
+        let Some(event) = self.event(href).await? else {
+            return Ok(());
+        };
+        sqlx::query("DELETE FROM rsvp.caldav_events WHERE collection_id = $1 AND href = $2")
+            .bind(self.collection.id)
+            .bind(href)
+            .execute(&self.manager.pool)
+            .await?;
+        match (event.external, event.reservation_id) {
+            (true, Some(id)) => self.cancel(id, "the caldav event is deleted").await,
+            // the reservation wins, its event is written again if it is still active
+            (false, Some(id)) => self.push_reservation(id, true).await,
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// bring the event of a reservation up to date. A reservation which is cancelled, purged or
+    /// moved to another resource loses its event, or declines it if it was made on the caldav
+    /// side. Without force, a reservation already written at its version is skipped
+    async fn push_reservation(
+        &mut self,
+        id: ReservationId,
+        force: bool,
+    ) -> Result<(), ReservationError> {
+        let rsvp = match self.manager.get(id).await {
+            Ok(rsvp) => Some(rsvp),
+            Err(ReservationError::DbError(e)) => return Err(ReservationError::DbError(e)),
+            Err(_) => None,
+        };
+        let rsvp = rsvp.filter(|rsvp| {
+            rsvp.resource_id == self.collection.resource_id
+                && rsvp.status != ReservationStatus::Cancelled as i32
+        });
+
+        match (rsvp, self.event_of(id).await?) {
+            (Some(rsvp), Some(event)) if !force && event.version == rsvp.version => Ok(()),
+            (Some(rsvp), Some(event)) if event.external => {
+                let version = rsvp.version;
+                self.answer_again(event, ResourceAnswer::Accepted(id), version)
+                    .await
+            }
+            (Some(rsvp), event) => {
+                let href = match event {
+                    Some(event) => event.href,
+                    None => self.pushed_href(id),
+                };
+                let uid = event_uid(id);
+                let version = rsvp.version;
+                let row = ExportRow {
+                    rsvp,
+                    tz: self.time_zone().await?,
+                };
+                let data = CalendarWriter::default().write_event(&row, &uid);
+                let etag = self.client.put_event(&href, data).await?;
+                self.report.pushed += 1;
+                let event = CalDavEvent {
+                    href,
+                    uid,
+                    etag: etag.unwrap_or_default(),
+                    reservation_id: Some(id),
+                    version,
+                    external: false,
+                };
+                self.save_event(&event).await
+            }
+            (None, Some(event)) if event.external => {
+                let answer = ResourceAnswer::Declined("the reservation is cancelled".to_string());
+                self.report.declined += 1;
+                self.answer_again(event, answer, 0).await
+            }
+            (None, Some(event)) => {
+                self.client.delete_event(&event.href).await?;
+                self.report.removed += 1;
+                sqlx::query(
+                    "DELETE FROM rsvp.caldav_events WHERE collection_id = $1 AND href = $2",
+                )
+                .bind(self.collection.id)
+                .bind(&event.href)
+                .execute(&self.manager.pool)
+                .await?;
+                Ok(())
+            }
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// answer an event of the caldav side again after its reservation changed
+    async fn answer_again(
+        &mut self,
+        event: CalDavEvent,
+        answer: ResourceAnswer,
+        version: i64,
+    ) -> Result<(), ReservationError> {
+        let Some((data, etag)) = self.client.get_event(&event.href).await? else {
+            return Ok(());
+        };
+        // edited since the last pull, the next pull answers it
+        if !event.etag.is_empty() && etag != event.etag {
+            return Ok(());
+        }
+        let reservation_id = match answer {
+            ResourceAnswer::Accepted(id) => Some(id),
+            ResourceAnswer::Declined(_) => None,
+        };
+        let etag = self.answer(&event.href, &data, etag, &answer).await?;
+        let event = CalDavEvent {
+            etag,
+            reservation_id,
+            version,
+            ..event
+        };
+        self.save_event(&event).await
+    }
+
+    /// write the answer to the event unless it has it already, returns the etag of the event
+    async fn answer(
+        &self,
+        href: &str,
+        data: &[u8],
+        etag: String,
+        answer: &ResourceAnswer,
+    ) -> Result<String, ReservationError> {
+        let answered = set_answer(data, &self.collection.resource_id, answer);
+        if answered == data {
+            return Ok(etag);
+        }
+        Ok(self
+            .client
+            .put_event(href, answered)
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn reserve(&mut self, mut rsvp: Reservation) -> Result<Reservation, ReservationError> {
+        rsvp.resource_id = self.collection.resource_id.clone();
+        if rsvp.user_id.is_empty() {
+            rsvp.user_id = self.user_id.to_string();
+        }
+        let rsvp = self.manager.reserve(rsvp).await?;
+        self.report.reserved += 1;
+        Ok(rsvp)
+    }
+
+    async fn cancel(&mut self, id: ReservationId, reason: &str) -> Result<(), ReservationError> {
+        match self
+            .manager
+            .cancel(id, reason.to_string(), ACTOR.to_string(), None)
+            .await
+        {
+            Ok(_) => {
+                self.report.cancelled += 1;
+                Ok(())
+            }
+            Err(ReservationError::DbError(e)) => Err(ReservationError::DbError(e)),
+            // purged or over already, there is nothing to cancel
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// href of the event the push writes for a reservation
+    fn pushed_href(&self, id: ReservationId) -> String {
+        format!(
+            "{}reservation-{}.ics",
+            collection_path(&self.collection.url),
+            id
+        )
+    }
+
+    /// time zone of the resource, UTC if it has none
+    async fn time_zone(&self) -> Result<Tz, ReservationError> {
+        let name: Option<String> =
+            sqlx::query_scalar("SELECT time_zone FROM rsvp.resources WHERE id = $1")
+                .bind(&self.collection.resource_id)
+                .fetch_optional(&self.manager.pool)
+                .await?;
+        match name {
+            Some(name) => name
+                .parse()
+                .map_err(|_| ReservationError::InvalidTimeZone(name)),
+            None => Ok(Tz::UTC),
+        }
+    }
+
+    async fn events(&self) -> Result<Vec<CalDavEvent>, ReservationError> {
+        let events = sqlx::query_as("SELECT * FROM rsvp.caldav_events WHERE collection_id = $1")
+            .bind(self.collection.id)
+            .fetch_all(&self.manager.pool)
+            .await?;
+        Ok(events)
+    }
+
+    async fn event(&self, href: &str) -> Result<Option<CalDavEvent>, ReservationError> {
+        let event = sqlx::query_as(
+            "SELECT * FROM rsvp.caldav_events WHERE collection_id = $1 AND href = $2",
+        )
+        .bind(self.collection.id)
+        .bind(href)
+        .fetch_optional(&self.manager.pool)
+        .await?;
+        Ok(event)
+    }
+
+    async fn event_of(&self, id: ReservationId) -> Result<Option<CalDavEvent>, ReservationError> {
+        let event = sqlx::query_as(
+            "SELECT * FROM rsvp.caldav_events WHERE collection_id = $1 AND reservation_id = $2 LIMIT 1",
+        )
+        .bind(self.collection.id)
+        .bind(id)
+        .fetch_optional(&self.manager.pool)
+        .await?;
+        Ok(event)
+    }
+
+    async fn save_event(&self, event: &CalDavEvent) -> Result<(), ReservationError> {
+        sqlx::query(
+            "INSERT INTO rsvp.caldav_events (collection_id, href, uid, etag, reservation_id, version, external) VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (collection_id, href) DO UPDATE SET uid = EXCLUDED.uid, etag = EXCLUDED.etag, reservation_id = EXCLUDED.reservation_id, version = EXCLUDED.version, external = EXCLUDED.external",
+        )
+        .bind(self.collection.id)
+        .bind(&event.href)
+        .bind(&event.uid)
+        .bind(&event.etag)
+        .bind(event.reservation_id)
+        .bind(event.version)
+        .bind(event.external)
+        .execute(&self.manager.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// why an event is declined, written to the event for its organizer
+fn decline_reason(e: &ReservationError) -> String {
+    match e {
+        ReservationError::ConflictReservation(ReservationConflictInfo::Parsed(conflict)) => {
+            match conflict.old.end {
+                Some(end) => format!(
+                    "conflicts with a reservation from {} to {}",
+                    conflict.old.start, end
+                ),
+                None => format!("conflicts with a reservation from {}", conflict.old.start),
+            }
+        }
+        e => e.to_string(),
+    }
+}
+
+/// path of the collection, with a trailing slash. The events pushed are made under it
+fn collection_path(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = rest.find('/').map_or("/", |i| &rest[i..]);
+    match path.ends_with('/') {
+        true => path.to_string(),
+        false => format!("{}/", path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Mutex};
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::SyncDelta;
+
+    const URL: &str = "http://localhost:5232/alice/room-1/";
+
+    /// a collection in memory, its sync token is the length of its change log
+    #[derive(Default)]
+    struct FakeCalDav {
+        inner: Mutex<FakeCollection>,
+    }
+
+    #[derive(Default)]
+    struct FakeCollection {
+        events: BTreeMap<String, (Vec<u8>, String)>,
+        log: Vec<String>,
+        writes: usize,
+    }
+
+    impl FakeCalDav {
+        /// a write of another caldav client
+        fn write(&self, href: &str, data: Vec<u8>) {
+            let mut inner = self.inner.lock().unwrap();
+            let etag = format!("\"{}\"", inner.log.len());
+            inner.events.insert(href.to_string(), (data, etag));
+            inner.log.push(href.to_string());
+        }
+
+        fn remove(&self, href: &str) {
+            let mut inner = self.inner.lock().unwrap();
+            inner.events.remove(href);
+            inner.log.push(href.to_string());
+        }
+
+        fn read(&self, href: &str) -> Option<String> {
+            let inner = self.inner.lock().unwrap();
+            let (data, _) = inner.events.get(href)?;
+            Some(String::from_utf8(data.clone()).unwrap())
+        }
+
+        /// writes and removals made by the sync
+        fn writes(&self) -> usize {
+            self.inner.lock().unwrap().writes
+        }
+    }
+
+    #[async_trait]
+    impl CalDav for FakeCalDav {
+        async fn sync_collection(
+            &self,
+            url: &str,
+            token: &str,
+        ) -> Result<SyncDelta, ReservationError> {
+            assert_eq!(url, URL);
+            let inner = self.inner.lock().unwrap();
+            let since = match token {
+                "" => None,
+                token => match token.parse::<usize>() {
+                    Ok(n) if n <= inner.log.len() => Some(n),
+                    _ => return Err(ReservationError::InvalidSyncToken),
+                },
+            };
+            let hrefs: Vec<&String> = match since {
+                None => inner.events.keys().collect(),
+                Some(n) => inner.log[n..].iter().collect(),
+            };
+            let mut delta = SyncDelta {
+                token: inner.log.len().to_string(),
+                ..Default::default()
+            };
+            for href in hrefs {
+                if delta.changed.iter().any(|(h, _)| h == href) || delta.removed.contains(href) {
+                    continue;
+                }
+                match inner.events.get(href) {
+                    Some((_, etag)) => delta.changed.push((href.clone(), etag.clone())),
+                    None => delta.removed.push(href.clone()),
+                }
+            }
+            Ok(delta)
+        }
+
+        async fn get_event(
+            &self,
+            href: &str,
+        ) -> Result<Option<(Vec<u8>, String)>, ReservationError> {
+            Ok(self.inner.lock().unwrap().events.get(href).cloned())
+        }
+
+        async fn put_event(
+            &self,
+            href: &str,
+            data: Vec<u8>,
+        ) -> Result<Option<String>, ReservationError> {
+            self.write(href, data);
+            let mut inner = self.inner.lock().unwrap();
+            inner.writes += 1;
+            Ok(inner.events.get(href).map(|(_, etag)| etag.clone()))
+        }
+
+        async fn delete_event(&self, href: &str) -> Result<(), ReservationError> {
+            self.remove(href);
+            self.inner.lock().unwrap().writes += 1;
+            Ok(())
+        }
+    }
+
+    fn event(uid: &str, start: &str, end: &str, extra: &str) -> Vec<u8> {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//test//EN\r\nBEGIN:VEVENT\r\nUID:{}\r\nDTSTAMP:20230101T000000Z\r\nDTSTART:{}\r\nDTEND:{}\r\nSUMMARY:team meeting\r\n{}END:VEVENT\r\nEND:VCALENDAR\r\n",
+            uid, start, end, extra
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn collection_path_should_work() {
+        assert_eq!(collection_path(URL), "/alice/room-1/");
+        assert_eq!(collection_path("https://dav.example.com/cal"), "/cal/");
+        assert_eq!(collection_path("https://dav.example.com"), "/");
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn sync_caldav_should_mirror_both_ways() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let caldav = FakeCalDav::default();
+        let rsvp = manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-1",
+                "2023-03-01T10:00:00+0000".parse().unwrap(),
+                "2023-03-01T12:00:00+0000".parse().unwrap(),
+                "standup",
+            ))
+            .await
+            .unwrap();
+        caldav.write(
+            "/alice/room-1/ext-1.ics",
+            event(
+                "ext-1@example",
+                "20230301T130000Z",
+                "20230301T140000Z",
+                "ORGANIZER:mailto:bob@example.com\r\n",
+            ),
+        );
+        caldav.write(
+            "/alice/room-1/ext-2.ics",
+            event("ext-2@example", "20230301T110000Z", "20230301T123000Z", ""),
+        );
+
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                pushed: 1,
+                reserved: 1,
+                declined: 1,
+                ..Default::default()
+            }
+        );
+        let pushed_href = format!("/alice/room-1/reservation-{}.ics", rsvp.id);
+        let pushed = caldav.read(&pushed_href).unwrap();
+        assert!(pushed.contains(&format!("UID:{}@reservation\r\n", rsvp.id)));
+        assert!(pushed.contains("DESCRIPTION:standup\r\n"));
+
+        let accepted = caldav.read("/alice/room-1/ext-1.ics").unwrap();
+        assert!(accepted.contains("PARTSTAT=ACCEPTED"));
+        assert!(accepted.contains("SUMMARY:team meeting\r\n"));
+        let id: i64 = accepted
+            .split("X-RESERVATION-ID:")
+            .nth(1)
+            .and_then(|rest| rest.split("\r\n").next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let booked = manager.get(id).await.unwrap();
+        assert_eq!(booked.user_id, "bob@example.com");
+        assert_eq!(booked.resource_id, "room-1");
+        let history = manager.get_history(id).await.unwrap();
+        assert_eq!(history[0].actor, "caldav");
+
+        let declined = caldav.read("/alice/room-1/ext-2.ics").unwrap();
+        assert!(declined.contains("PARTSTAT=DECLINED"));
+        assert!(declined.contains("X-RESERVATION-ERROR:conflicts with a reservation"));
+
+        // only the changes move, nothing changed since the last sync
+        let writes = caldav.writes();
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(report, SyncReport::default());
+        assert_eq!(caldav.writes(), writes);
+
+        // an edit of a pushed event is overwritten by the reservation
+        caldav.write(
+            &pushed_href,
+            event("other", "20230301T100000Z", "20230301T110000Z", ""),
+        );
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(report.pushed, 1);
+        assert!(caldav
+            .read(&pushed_href)
+            .unwrap()
+            .contains("DESCRIPTION:standup\r\n"));
+
+        // a cancelled reservation loses its event
+        manager
+            .cancel(rsvp.id, "".into(), "".into(), None)
+            .await
+            .unwrap();
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(report.removed, 1);
+        assert!(caldav.read(&pushed_href).is_none());
+
+        // a deleted event of the caldav side cancels its reservation
+        caldav.remove("/alice/room-1/ext-1.ics");
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(report.cancelled, 1);
+        assert_eq!(
+            manager.get(id).await.unwrap().status,
+            ReservationStatus::Cancelled as i32
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn sync_caldav_should_not_trust_copied_uids() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let caldav = FakeCalDav::default();
+        let other = manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-2",
+                "2023-03-01T10:00:00+0000".parse().unwrap(),
+                "2023-03-01T12:00:00+0000".parse().unwrap(),
+                "",
+            ))
+            .await
+            .unwrap();
+        // the uid of a reservation in an event the sync didn't write
+        let href = "/alice/room-1/copy.ics";
+        caldav.write(
+            href,
+            event(
+                &format!("{}@reservation", other.id),
+                "20230301T130000Z",
+                "20230301T140000Z",
+                "",
+            ),
+        );
+
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                reserved: 1,
+                ..Default::default()
+            }
+        );
+        let accepted = caldav.read(href).unwrap();
+        assert!(accepted.contains("PARTSTAT=ACCEPTED"));
+        assert!(!accepted.contains(&format!("X-RESERVATION-ID:{}\r\n", other.id)));
+        assert_eq!(manager.get(other.id).await.unwrap(), other);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn sync_caldav_should_follow_moved_events() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let caldav = FakeCalDav::default();
+        let href = "/alice/room-1/ext-1.ics";
+        caldav.write(
+            href,
+            event("ext-1@example", "20230301T130000Z", "20230301T140000Z", ""),
+        );
+        manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        let blocker = manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-1",
+                "2023-03-02T10:00:00+0000".parse().unwrap(),
+                "2023-03-02T12:00:00+0000".parse().unwrap(),
+                "",
+            ))
+            .await
+            .unwrap();
+
+        // the organizer moves the event, the reservation follows
+        caldav.write(
+            href,
+            event("ext-1@example", "20230301T150000Z", "20230301T160000Z", ""),
+        );
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        // only the new reservation is pushed
+        assert_eq!(
+            report,
+            SyncReport {
+                pushed: 1,
+                ..Default::default()
+            }
+        );
+        let filter = abi::ReservationQuery {
+            user_id: "caldav".into(),
+            ..Default::default()
+        };
+        let (rsvps, _) = manager.query(filter.clone()).await.unwrap();
+        assert_eq!(rsvps.len(), 1);
+        assert_eq!(
+            rsvps[0].start,
+            Some(abi::convert_to_timestamp(
+                "2023-03-01T15:00:00Z".parse().unwrap()
+            ))
+        );
+
+        // into a conflict, the reservation is cancelled and the event declined
+        caldav.write(
+            href,
+            event("ext-1@example", "20230302T110000Z", "20230302T130000Z", ""),
+        );
+        let report = manager
+            .sync_caldav(&caldav, "room-1", URL, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(report.declined, 1);
+        assert_eq!(report.cancelled, 1);
+        assert!(caldav.read(href).unwrap().contains("PARTSTAT=DECLINED"));
+        let (rsvps, _) = manager.query(filter).await.unwrap();
+        assert!(rsvps.is_empty());
+        assert_eq!(
+            manager.get(blocker.id).await.unwrap().status,
+            ReservationStatus::Pending as i32
+        );
+    }
+}
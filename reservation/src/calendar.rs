@@ -0,0 +1,786 @@
+use std::collections::BTreeMap;
+
+use abi::{
+    convert_to_timestamp, convert_to_utc_time, Reservation, ReservationError, ReservationId,
+    ReservationStatus,
+};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use ical::{parser::ical::component::IcalEvent, property::Property, IcalParser};
+
+use crate::export::ExportRow;
+
+/// domain part of the UID of an event, the local part is the reservation id
+const UID_DOMAIN: &str = "reservation";
+const PRODID: &str = "-//reservation//reservation service//EN";
+/// content lines are folded at 75 octets
+const LINE_WIDTH: usize = 75;
+
+type TzOffset = <Tz as TimeZone>::Offset;
+
+/// writes reservations as the VEVENTs of a VCALENDAR. The VTIMEZONEs come last, when the time
+/// span of the events of each zone is known
+#[derive(Default)]
+pub(crate) struct CalendarWriter {
+    started: bool,
+    /// earliest and latest time of the events per time zone, UTC doesn't need one
+    zones: BTreeMap<&'static str, (Tz, DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl CalendarWriter {
+    pub(crate) fn write(&mut self, rows: &[ExportRow]) -> Vec<u8> {
+        let mut lines = ContentLines::default();
+        self.start(&mut lines);
+        for row in rows {
+            self.event(&mut lines, row, &event_uid(row.rsvp.id));
+        }
+        lines.into_bytes()
+    }
+
+    /// a whole VCALENDAR of a single event with the UID, as stored in a caldav collection
+    pub(crate) fn write_event(mut self, row: &ExportRow, uid: &str) -> Vec<u8> {
+        let mut lines = ContentLines::default();
+        self.start(&mut lines);
+        self.event(&mut lines, row, uid);
+        let mut data = lines.into_bytes();
+        data.extend(self.finish());
+        data
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let mut lines = ContentLines::default();
+        self.start(&mut lines);
+        for (tz, from, to) in self.zones.values() {
+            time_zone(&mut lines, *tz, *from, *to);
+        }
+        lines.line("END:VCALENDAR");
+        lines.into_bytes()
+    }
+
+    fn start(&mut self, lines: &mut ContentLines) {
+        if !self.started {
+            lines.line("BEGIN:VCALENDAR");
+            lines.property("VERSION", "2.0");
+            lines.property("PRODID", PRODID);
+            lines.property("CALSCALE", "GREGORIAN");
+            self.started = true;
+        }
+    }
+
+    fn event(&mut self, lines: &mut ContentLines, row: &ExportRow, uid: &str) {
+        let rsvp = &row.rsvp;
+        let Some(start) = row.start() else {
+            return;
+        };
+        let end = row.end();
+        if row.tz != Tz::UTC {
+            let last = end.unwrap_or(start);
+            let span = self
+                .zones
+                .entry(row.tz.name())
+                .or_insert((row.tz, start, last));
+            span.1 = span.1.min(start);
+            span.2 = span.2.max(last);
+        }
+
+        lines.line("BEGIN:VEVENT");
+        lines.property("UID", uid);
+        let stamp = rsvp
+            .created_at
+            .clone()
+            .map(convert_to_utc_time)
+            .unwrap_or_else(Utc::now);
+        lines.property("DTSTAMP", &format_utc(stamp));
+        lines.property("SEQUENCE", &rsvp.version.to_string());
+        lines.time("DTSTART", start, row.tz);
+        if let Some(end) = end {
+            lines.time("DTEND", end, row.tz);
+        }
+        lines.property("SUMMARY", &escape(&rsvp.resource_id));
+        lines.property("LOCATION", &escape(&rsvp.resource_id));
+        if !rsvp.note.is_empty() {
+            lines.property("DESCRIPTION", &escape(&rsvp.note));
+        }
+        lines.property("STATUS", event_status(rsvp));
+        if !rsvp.tags.is_empty() {
+            let tags: Vec<String> = rsvp.tags.iter().map(|t| escape(t)).collect();
+            lines.property("CATEGORIES", &tags.join(","));
+        }
+        lines.property("X-RESERVATION-USER-ID", &escape(&rsvp.user_id));
+        lines.line("END:VEVENT");
+    }
+}
+
+/// UID of the event of a reservation
+pub(crate) fn event_uid(id: ReservationId) -> String {
+    format!("{}@{}", id, UID_DOMAIN)
+}
+
+/// the reservation of an event UID made by event_uid
+pub(crate) fn uid_reservation_id(uid: &str) -> Option<ReservationId> {
+    uid.strip_suffix(UID_DOMAIN)?
+        .strip_suffix('@')?
+        .parse()
+        .ok()
+}
+
+/// answer of a resource to an event made on the caldav side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ResourceAnswer {
+    /// the event is booked as the reservation
+    Accepted(ReservationId),
+    /// the event couldn't be booked, for the reason
+    Declined(String),
+}
+
+/// the caldav object with the resource as an ATTENDEE of its events, with the answer as PARTSTAT.
+/// The id of the reservation or the reason of the decline is added as an X- property, the other
+/// lines are kept as they are, so answering again replaces the previous answer
+pub(crate) fn set_answer(data: &[u8], resource_id: &str, answer: &ResourceAnswer) -> Vec<u8> {
+    let uri = format!("urn:reservation:{}", resource_id);
+    let mut lines = ContentLines::default();
+    for line in unfold(&String::from_utf8_lossy(data)) {
+        let name = line
+            .split([';', ':'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let ours = match name.as_str() {
+            "ATTENDEE" => line.ends_with(&format!(":{}", uri)),
+            "X-RESERVATION-ID" | "X-RESERVATION-ERROR" => true,
+            _ => false,
+        };
+        if ours {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("END:VEVENT") {
+            let partstat = match answer {
+                ResourceAnswer::Accepted(_) => "ACCEPTED",
+                ResourceAnswer::Declined(_) => "DECLINED",
+            };
+            lines.line(&format!(
+                "ATTENDEE;CUTYPE=RESOURCE;ROLE=NON-PARTICIPANT;PARTSTAT={};CN=\"{}\":{}",
+                partstat,
+                resource_id.replace('"', ""),
+                uri
+            ));
+            match answer {
+                ResourceAnswer::Accepted(id) => lines.property("X-RESERVATION-ID", &id.to_string()),
+                ResourceAnswer::Declined(reason) => {
+                    lines.property("X-RESERVATION-ERROR", &escape(reason))
+                }
+            }
+        }
+        lines.line(&line);
+    }
+    lines.into_bytes()
+}
+
+/// content lines joined back from their folds, empty lines are dropped
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)) {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(fold), Some(last)) => last.push_str(fold),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+#[derive(Default)]
+struct ContentLines(String);
+
+impl ContentLines {
+    /// a content line, folded without splitting a character
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for ch in line.chars() {
+            if width + ch.len_utf8() > LINE_WIDTH {
+                self.0.push_str("\r\n ");
+                width = 1;
+            }
+            self.0.push(ch);
+            width += ch.len_utf8();
+        }
+        self.0.push_str("\r\n");
+    }
+
+    fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+
+    /// a time in UTC, or local to the zone with its TZID
+    fn time(&mut self, name: &str, time: DateTime<Utc>, tz: Tz) {
+        if tz == Tz::UTC {
+            self.property(name, &format_utc(time));
+        } else {
+            let local = time.with_timezone(&tz).naive_local();
+            self.property(
+                &format!("{};TZID={}", name, tz.name()),
+                &format_local(local),
+            );
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.0.into_bytes()
+    }
+}
+
+/// a VTIMEZONE with the offset at `from` and every transition until `to`
+fn time_zone(lines: &mut ContentLines, tz: Tz, from: DateTime<Utc>, to: DateTime<Utc>) {
+    let offset_at = |t: DateTime<Utc>| tz.offset_from_utc_datetime(&t.naive_utc());
+
+    lines.line("BEGIN:VTIMEZONE");
+    lines.property("TZID", tz.name());
+    let mut offset = offset_at(from);
+    observance(lines, offset, offset, from);
+
+    let mut t = from;
+    while t < to {
+        let next = (t + Duration::days(1)).min(to);
+        if !same_offset(offset_at(next), offset) {
+            // the transition is in (t, next], transitions are on whole seconds
+            let (mut lo, mut hi) = (t, next);
+            while hi - lo > Duration::seconds(1) {
+                let mid = lo + (hi - lo) / 2;
+                if same_offset(offset_at(mid), offset) {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            let to_offset = offset_at(hi);
+            observance(lines, offset, to_offset, hi);
+            offset = to_offset;
+        }
+        t = next;
+    }
+    lines.line("END:VTIMEZONE");
+}
+
+fn observance(lines: &mut ContentLines, from: TzOffset, to: TzOffset, at: DateTime<Utc>) {
+    let kind = if to.dst_offset().num_seconds() == 0 {
+        "STANDARD"
+    } else {
+        "DAYLIGHT"
+    };
+    lines.line(&format!("BEGIN:{}", kind));
+    // the onset is local time in the offset before it
+    lines.property(
+        "DTSTART",
+        &format_local(at.with_timezone(&from.fix()).naive_local()),
+    );
+    lines.property("TZOFFSETFROM", &format_offset(from.fix()));
+    lines.property("TZOFFSETTO", &format_offset(to.fix()));
+    lines.property("TZNAME", &escape(to.abbreviation()));
+    lines.line(&format!("END:{}", kind));
+}
+
+fn same_offset(a: TzOffset, b: TzOffset) -> bool {
+    a.fix() == b.fix() && a.dst_offset() == b.dst_offset()
+}
+
+fn event_status(rsvp: &Reservation) -> &'static str {
+    match ReservationStatus::from_i32(rsvp.status).unwrap_or(ReservationStatus::Unknown) {
+        ReservationStatus::Pending | ReservationStatus::Blocked | ReservationStatus::Unknown => {
+            "TENTATIVE"
+        }
+        ReservationStatus::Cancelled => "CANCELLED",
+        _ => "CONFIRMED",
+    }
+}
+
+fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_local(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn format_offset(offset: FixedOffset) -> String {
+    let secs = offset.local_minus_utc();
+    let sign = if secs < 0 { '-' } else { '+' };
+    let secs = secs.abs();
+    match secs % 60 {
+        0 => format!("{}{:02}{:02}", sign, secs / 3600, secs / 60 % 60),
+        s => format!("{}{:02}{:02}{:02}", sign, secs / 3600, secs / 60 % 60, s),
+    }
+}
+
+/// escape a TEXT value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// the VEVENTs of the calendars of the file as reservations, they are validated by the caller.
+/// LOCATION is the resource, X-RESERVATION-USER-ID the user, DESCRIPTION or SUMMARY the note and
+/// CATEGORIES the tags
+pub(crate) fn parse_calendar(
+    data: &[u8],
+) -> Result<Vec<Result<Reservation, ReservationError>>, ReservationError> {
+    let mut rows = vec![];
+    for calendar in IcalParser::new(data) {
+        let calendar = calendar.map_err(|e| ReservationError::InvalidImportData(e.to_string()))?;
+        rows.extend(calendar.events.iter().map(event_to_reservation));
+    }
+    Ok(rows)
+}
+
+/// the first VEVENT of a caldav object as a reservation, with its UID. Without
+/// X-RESERVATION-USER-ID the user is the ORGANIZER, if there is one
+pub(crate) fn parse_event(data: &[u8]) -> Result<(String, Reservation), ReservationError> {
+    let calendar = IcalParser::new(data)
+        .next()
+        .ok_or_else(|| ReservationError::InvalidImportData("no VCALENDAR".into()))?
+        .map_err(|e| ReservationError::InvalidImportData(e.to_string()))?;
+    let event = calendar
+        .events
+        .first()
+        .ok_or_else(|| ReservationError::InvalidImportData("no VEVENT".into()))?;
+    let value = |name: &str| {
+        event
+            .properties
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.value.clone())
+            .unwrap_or_default()
+    };
+
+    let mut rsvp = event_to_reservation(event)?;
+    if rsvp.user_id.is_empty() {
+        let organizer = value("ORGANIZER");
+        rsvp.user_id = match organizer.get(..7) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => organizer[7..].to_string(),
+            _ => organizer,
+        };
+    }
+    Ok((value("UID"), rsvp))
+}
+
+fn event_to_reservation(event: &IcalEvent) -> Result<Reservation, ReservationError> {
+    let property = |name: &str| event.properties.iter().find(|p| p.name == name);
+    let text = |name: &str| {
+        property(name)
+            .and_then(|p| p.value.as_deref())
+            .map(unescape)
+            .unwrap_or_default()
+    };
+    if property("RRULE").is_some() || property("RDATE").is_some() {
+        return Err(ReservationError::InvalidImportData(
+            "recurring events are not supported".into(),
+        ));
+    }
+
+    let (start, all_day) = property("DTSTART")
+        .map(parse_time)
+        .ok_or_else(|| ReservationError::InvalidImportData("DTSTART is required".into()))??;
+    let end = match (property("DTEND"), property("DURATION")) {
+        (Some(p), _) => parse_time(p)?.0,
+        (None, Some(p)) => {
+            let value = p.value.as_deref().unwrap_or_default();
+            start
+                + parse_duration(value).ok_or_else(|| {
+                    ReservationError::InvalidImportData(format!("DURATION: {}", value))
+                })?
+        }
+        // an all day event without an end lasts a day
+        (None, None) if all_day => start + Duration::days(1),
+        (None, None) => {
+            return Err(ReservationError::InvalidImportData(
+                "DTEND or DURATION is required".into(),
+            ))
+        }
+    };
+    let status = match text("STATUS").as_str() {
+        "" | "TENTATIVE" => ReservationStatus::Pending,
+        "CONFIRMED" => ReservationStatus::Confirmed,
+        "CANCELLED" => ReservationStatus::Cancelled,
+        v => {
+            return Err(ReservationError::InvalidImportData(format!(
+                "STATUS: {}",
+                v
+            )))
+        }
+    };
+    // the summary of an exported event is its resource, only a foreign event has its note there
+    let note = match text("DESCRIPTION") {
+        v if v.is_empty() && property("X-RESERVATION-USER-ID").is_none() => text("SUMMARY"),
+        v => v,
+    };
+    let tags = event
+        .properties
+        .iter()
+        .filter(|p| p.name == "CATEGORIES")
+        .filter_map(|p| p.value.as_deref())
+        .flat_map(split_list)
+        .collect();
+
+    Ok(Reservation {
+        user_id: text("X-RESERVATION-USER-ID"),
+        resource_id: text("LOCATION"),
+        status: status as i32,
+        start: Some(convert_to_timestamp(start)),
+        end: Some(convert_to_timestamp(end)),
+        note,
+        tags,
+        ..Default::default()
+    })
+}
+
+/// a DATE or DATE-TIME property, in UTC, local to its TZID or floating. Floating times are taken
+/// as UTC. The flag is set for a DATE
+fn parse_time(property: &Property) -> Result<(DateTime<Utc>, bool), ReservationError> {
+    let value = property.value.as_deref().unwrap_or_default();
+    let invalid = || ReservationError::InvalidImportData(format!("{}: {}", property.name, value));
+    let tz = match property
+        .params
+        .iter()
+        .flatten()
+        .find(|(name, _)| name == "TZID")
+        .and_then(|(_, values)| values.first())
+    {
+        Some(id) => id
+            .trim_start_matches('/')
+            .parse::<Tz>()
+            .map_err(|_| ReservationError::InvalidTimeZone(id.clone()))?,
+        None => Tz::UTC,
+    };
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        let local = date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?;
+        let time = tz
+            .from_local_datetime(&local)
+            .earliest()
+            .ok_or_else(invalid)?;
+        return Ok((time.with_timezone(&Utc), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok((time.and_utc(), false));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+    // a local time skipped by a transition doesn't exist
+    let time = tz
+        .from_local_datetime(&local)
+        .earliest()
+        .ok_or_else(invalid)?;
+    Ok((time.with_timezone(&Utc), false))
+}
+
+/// a positive duration such as P1D, PT1H30M or P1W
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.strip_prefix('+').unwrap_or(s).strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut time = false;
+    for ch in s.chars() {
+        match ch {
+            '0'..='9' => number.push(ch),
+            'T' if number.is_empty() && !time => time = true,
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match (ch, time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    (number.is_empty() && total > Duration::zero()).then_some(total)
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, ch == '\\') {
+            (_, true) => match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(next) => unescaped.push(next),
+                None => {}
+            },
+            (ch, false) => unescaped.push(ch),
+        }
+    }
+    unescaped
+}
+
+/// the values of a list, split at the commas which aren't escaped
+fn split_list(text: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, ch) in text.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                values.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(&text[start..]);
+    values
+        .into_iter()
+        .map(unescape)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{ImportFormat, ImportRequest, ImportRowStatus, ReservationQueryBuilder};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{ReservationManager, Rsvp};
+
+    const ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//example//EN\r
+BEGIN:VEVENT\r
+UID:a@example\r
+DTSTART;TZID=America/New_York:20230312T013000\r
+DTEND;TZID=America/New_York:20230312T033000\r
+SUMMARY:Board meeting\r
+LOCATION:room-1\r
+CATEGORIES:vip,board\\,2023\r
+STATUS:CONFIRMED\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:b@example\r
+DTSTART:20230313T090000Z\r
+DURATION:PT1H30M\r
+DESCRIPTION:line 1\\nline 2\\; 你好\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:c@example\r
+DTSTART;VALUE=DATE:20230314\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:d@example\r
+DTSTART:20230315T090000Z\r
+DTEND:20230315T100000Z\r
+RRULE:FREQ=WEEKLY\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn text_should_escape_and_fold() {
+        let text = "a,b;c\\d\ne";
+        assert_eq!(escape(text), "a\\,b\\;c\\\\d\\ne");
+        assert_eq!(unescape(&escape(text)), text);
+        assert_eq!(split_list("vip,board\\,2023,"), vec!["vip", "board,2023"]);
+
+        let mut lines = ContentLines::default();
+        lines.property("DESCRIPTION", &"你好".repeat(40));
+        let content = String::from_utf8(lines.into_bytes()).unwrap();
+        let folded: Vec<&str> = content.trim_end().split("\r\n").collect();
+        assert!(folded.len() > 1);
+        assert!(folded.iter().all(|l| l.len() <= LINE_WIDTH));
+        assert_eq!(
+            folded.concat().replace(" ", ""),
+            format!("DESCRIPTION:{}", "你好".repeat(40))
+        );
+    }
+
+    #[test]
+    fn set_answer_should_replace_previous_answer() {
+        let data = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:a@example\r\nDESCRIPTION:a line\r\n  folded\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let accepted = set_answer(data.as_bytes(), "room-1", &ResourceAnswer::Accepted(7));
+        let lines = unfold(&String::from_utf8(accepted.clone()).unwrap());
+        assert_eq!(
+            lines,
+            [
+                "BEGIN:VCALENDAR",
+                "BEGIN:VEVENT",
+                "UID:a@example",
+                "DESCRIPTION:a line folded",
+                "ATTENDEE;CUTYPE=RESOURCE;ROLE=NON-PARTICIPANT;PARTSTAT=ACCEPTED;CN=\"room-1\":urn:reservation:room-1",
+                "X-RESERVATION-ID:7",
+                "END:VEVENT",
+                "END:VCALENDAR",
+            ]
+        );
+        assert_eq!(
+            set_answer(&accepted, "room-1", &ResourceAnswer::Accepted(7)),
+            accepted
+        );
+
+        let answer = ResourceAnswer::Declined("busy, sorry".into());
+        let lines = unfold(&String::from_utf8(set_answer(&accepted, "room-1", &answer)).unwrap());
+        assert_eq!(lines.len(), 8);
+        assert!(lines[4].contains(";PARTSTAT=DECLINED;"));
+        assert_eq!(lines[5], "X-RESERVATION-ERROR:busy\\, sorry");
+    }
+
+    #[test]
+    fn parse_event_should_take_organizer_as_user() {
+        let (uid, rsvp) = parse_event(ICS.as_bytes()).unwrap();
+        assert_eq!(uid, "a@example");
+        assert_eq!(rsvp.note, "Board meeting");
+        assert_eq!(rsvp.user_id, "");
+
+        let data = ICS.replace(
+            "UID:a@example\r\n",
+            "UID:a@example\r\nORGANIZER;CN=Bob:MAILTO:bob@example.com\r\n",
+        );
+        let (_, rsvp) = parse_event(data.as_bytes()).unwrap();
+        assert_eq!(rsvp.user_id, "bob@example.com");
+        assert_eq!(uid_reservation_id(&event_uid(42)), Some(42));
+        assert_eq!(uid_reservation_id("a@example"), None);
+    }
+
+    #[test]
+    fn parse_duration_should_work() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1W2D"), Some(Duration::days(9)));
+        assert_eq!(parse_duration("-PT1H"), None);
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(parse_duration("PT"), None);
+    }
+
+    #[test]
+    fn parse_calendar_should_work() {
+        let rows = parse_calendar(ICS.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 4);
+
+        let rsvp = rows[0].as_ref().unwrap();
+        // 01:30 is before the DST transition of the day, 03:30 after it
+        assert_eq!(
+            convert_to_utc_time(rsvp.start.clone().unwrap()),
+            utc("2023-03-12T06:30:00Z")
+        );
+        assert_eq!(
+            convert_to_utc_time(rsvp.end.clone().unwrap()),
+            utc("2023-03-12T07:30:00Z")
+        );
+        assert_eq!(rsvp.resource_id, "room-1");
+        assert_eq!(rsvp.note, "Board meeting");
+        assert_eq!(rsvp.tags, vec!["vip", "board,2023"]);
+        assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
+
+        let rsvp = rows[1].as_ref().unwrap();
+        assert_eq!(
+            convert_to_utc_time(rsvp.end.clone().unwrap()),
+            utc("2023-03-13T10:30:00Z")
+        );
+        assert_eq!(rsvp.note, "line 1\nline 2; 你好");
+        assert_eq!(rsvp.status, ReservationStatus::Pending as i32);
+
+        let rsvp = rows[2].as_ref().unwrap();
+        assert_eq!(
+            convert_to_utc_time(rsvp.end.clone().unwrap()),
+            utc("2023-03-15T00:00:00Z")
+        );
+        assert_eq!(
+            rows[3].as_ref().unwrap_err(),
+            &ReservationError::InvalidImportData("recurring events are not supported".into())
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn calendar_export_should_round_trip() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        sqlx::query(
+            "INSERT INTO rsvp.resources (id, time_zone) VALUES ('room-1', 'America/New_York')",
+        )
+        .execute(&migrated_pool)
+        .await
+        .unwrap();
+        let resp = manager
+            .import(ImportRequest {
+                data: ICS.as_bytes().to_vec(),
+                format: ImportFormat::Icalendar as i32,
+                user_id: "hyx".into(),
+                resource_id: "room-2".into(),
+                ..Default::default()
+            })
+            .await;
+        // the recurring event fails the import
+        assert_eq!(resp.unwrap().imported, 0);
+
+        let mut rsvp = parse_calendar(ICS.as_bytes()).unwrap().remove(0).unwrap();
+        rsvp.user_id = "hyx".into();
+        // a reservation after the transition back to standard time
+        let later = Reservation {
+            start: Some(convert_to_timestamp(utc("2023-11-06T14:00:00Z"))),
+            end: Some(convert_to_timestamp(utc("2023-11-06T15:00:00Z"))),
+            tags: vec![],
+            note: "".into(),
+            ..rsvp.clone()
+        };
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        manager.reserve(later).await.unwrap();
+
+        let query = ReservationQueryBuilder::default()
+            .resource_id("room-1")
+            .build()
+            .unwrap();
+        let chunks: Vec<Vec<u8>> = manager
+            .export(query, abi::ExportFormat::Icalendar)
+            .try_collect()
+            .await
+            .unwrap();
+        let calendar = String::from_utf8(chunks.concat()).unwrap();
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains(&format!("UID:{}@reservation\r\n", rsvp.id)));
+        assert!(calendar.contains("DTSTART;TZID=America/New_York:20230312T013000\r\n"));
+        assert!(calendar.contains("DTSTART;TZID=America/New_York:20231106T090000\r\n"));
+        assert!(calendar.contains("CATEGORIES:board\\,2023,vip\r\n"));
+        // the offsets of the span of the events: EST, then EDT from march, EST from november
+        let tz = &calendar[calendar.find("BEGIN:VTIMEZONE").unwrap()..];
+        assert!(tz.contains("BEGIN:DAYLIGHT\r\nDTSTART:20230312T020000\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nTZNAME:EDT\r\n"));
+        assert!(tz.contains("BEGIN:STANDARD\r\nDTSTART:20231105T020000\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nTZNAME:EST\r\n"));
+
+        // the exported events are read back as the same slots and notes
+        let notes: Vec<String> = parse_calendar(calendar.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.unwrap().note)
+            .collect();
+        assert_eq!(notes, vec![rsvp.note.clone(), "".to_string()]);
+        let resp = manager
+            .import(ImportRequest {
+                data: calendar.into_bytes(),
+                format: ImportFormat::Icalendar as i32,
+                dry_run: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(resp.rows.len(), 2);
+        assert_eq!(resp.rows[0].status, ImportRowStatus::Conflict as i32);
+        assert_eq!(resp.rows[0].conflict_id, rsvp.id);
+        assert_eq!(resp.rows[1].status, ImportRowStatus::Conflict as i32);
+    }
+}
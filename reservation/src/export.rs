@@ -0,0 +1,505 @@
+use std::{collections::HashMap, sync::Arc};
+
+use abi::{
+    attributes_to_json, convert_to_utc_time, ExportFormat, Reservation, ReservationError,
+    ReservationQuery, ReservationStatus, MAX_PAGE_SIZE,
+};
+use arrow_array::{
+    builder::{ListBuilder, StringBuilder},
+    ArrayRef, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use futures::stream::{self, BoxStream, StreamExt};
+use parquet::arrow::ArrowWriter;
+
+use crate::{calendar::CalendarWriter, ReservationManager, Rsvp};
+
+/// columns of an export in order. The schema is stable, new columns are only appended
+pub const EXPORT_COLUMNS: [&str; 18] = [
+    "id",
+    "user_id",
+    "resource_id",
+    "status",
+    "start_utc",
+    "end_utc",
+    "time_zone",
+    "start_local",
+    "end_local",
+    "note",
+    "group_id",
+    "cancel_reason",
+    "cancelled_by",
+    "cancelled_at",
+    "version",
+    "created_at",
+    "tags",
+    "attributes",
+];
+
+/// rows encoded together, a chunk of a parquet export is a row group
+const CHUNK_ROWS: usize = 5000;
+
+/// the reservations matching the query as a file of the format. They are read a page at a time
+/// in the order of the query, and the file is streamed in chunks, so only a chunk is in memory
+pub(crate) fn export(
+    manager: ReservationManager,
+    query: ReservationQuery,
+    format: ExportFormat,
+) -> BoxStream<'static, Result<Vec<u8>, ReservationError>> {
+    export_in_chunks(manager, query, format, CHUNK_ROWS)
+}
+
+fn export_in_chunks(
+    manager: ReservationManager,
+    query: ReservationQuery,
+    format: ExportFormat,
+    chunk_rows: usize,
+) -> BoxStream<'static, Result<Vec<u8>, ReservationError>> {
+    let export = Export {
+        manager,
+        query: ReservationQuery {
+            page: 0,
+            page_size: MAX_PAGE_SIZE as i32,
+            page_token: String::new(),
+            ..query
+        },
+        encoder: None,
+        format,
+        chunk_rows,
+        time_zones: HashMap::new(),
+        done: false,
+    };
+    stream::try_unfold(export, |mut export| async move {
+        Ok(export.next_chunk().await?.map(|data| (data, export)))
+    })
+    .boxed()
+}
+
+struct Export {
+    manager: ReservationManager,
+    query: ReservationQuery,
+    /// made with the first chunk, so an invalid query fails before anything is encoded
+    encoder: Option<Encoder>,
+    format: ExportFormat,
+    /// whole pages are read until a chunk has at least this many rows
+    chunk_rows: usize,
+    /// time zones of the resources seen so far
+    time_zones: HashMap<String, Tz>,
+    done: bool,
+}
+
+impl Export {
+    /// the next chunk of the file, None after the last one
+    async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, ReservationError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut rows = Vec::new();
+        let mut last = false;
+        while rows.len() < self.chunk_rows && !last {
+            let (rsvps, next_page_token) = self.manager.query(self.query.clone()).await?;
+            self.load_time_zones(&rsvps).await?;
+            rows.extend(rsvps.into_iter().map(|rsvp| {
+                ExportRow {
+                    tz: self
+                        .time_zones
+                        .get(&rsvp.resource_id)
+                        .copied()
+                        .unwrap_or(Tz::UTC),
+                    rsvp,
+                }
+            }));
+            match next_page_token {
+                Some(token) => self.query.page_token = token,
+                None => last = true,
+            }
+        }
+
+        let mut encoder = match self.encoder.take() {
+            Some(encoder) => encoder,
+            None => Encoder::new(self.format)?,
+        };
+        let mut data = encoder.write(&rows)?;
+        if last {
+            data.extend(encoder.finish()?);
+            self.done = true;
+        } else {
+            self.encoder = Some(encoder);
+        }
+        Ok(Some(data))
+    }
+
+    /// time zones of the resources of the reservations, a resource without one is in UTC
+    async fn load_time_zones(&mut self, rsvps: &[Reservation]) -> Result<(), ReservationError> {
+        let mut ids: Vec<String> = rsvps
+            .iter()
+            .filter(|rsvp| !self.time_zones.contains_key(&rsvp.resource_id))
+            .map(|rsvp| rsvp.resource_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let zones: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, time_zone FROM rsvp.resources WHERE id = ANY($1)")
+                .bind(&ids)
+                .fetch_all(&self.manager.pool)
+                .await?;
+        for id in ids {
+            self.time_zones.insert(id, Tz::UTC);
+        }
+        for (id, name) in zones {
+            let tz = name
+                .parse::<Tz>()
+                .map_err(|_| ReservationError::InvalidTimeZone(name))?;
+            self.time_zones.insert(id, tz);
+        }
+        Ok(())
+    }
+}
+
+/// a reservation with the time zone of its resource
+pub(crate) struct ExportRow {
+    pub(crate) rsvp: Reservation,
+    pub(crate) tz: Tz,
+}
+
+impl ExportRow {
+    pub(crate) fn start(&self) -> Option<DateTime<Utc>> {
+        self.rsvp.start.clone().map(convert_to_utc_time)
+    }
+
+    pub(crate) fn end(&self) -> Option<DateTime<Utc>> {
+        self.rsvp.end.clone().map(convert_to_utc_time)
+    }
+
+    fn local(&self, time: Option<DateTime<Utc>>) -> Option<NaiveDateTime> {
+        time.map(|t| t.with_timezone(&self.tz).naive_local())
+    }
+
+    fn status(&self) -> String {
+        ReservationStatus::from_i32(self.rsvp.status)
+            .unwrap_or(ReservationStatus::Unknown)
+            .to_string()
+    }
+
+    fn attributes(&self) -> Result<String, ReservationError> {
+        Ok(attributes_to_json(&self.rsvp.attributes)?.to_string())
+    }
+}
+
+enum Encoder {
+    Csv { header: bool },
+    Parquet(Box<ArrowWriter<Vec<u8>>>),
+    Calendar(CalendarWriter),
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Result<Self, ReservationError> {
+        Ok(match format {
+            ExportFormat::Csv => Self::Csv { header: true },
+            ExportFormat::Parquet => Self::Parquet(Box::new(
+                ArrowWriter::try_new(Vec::new(), export_schema(), None).map_err(export_error)?,
+            )),
+            ExportFormat::Icalendar => Self::Calendar(CalendarWriter::default()),
+        })
+    }
+
+    /// encode the rows, returns the bytes of the file which are ready
+    fn write(&mut self, rows: &[ExportRow]) -> Result<Vec<u8>, ReservationError> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self {
+            Self::Csv { header } => {
+                let data = encode_csv(rows, *header)?;
+                *header = false;
+                Ok(data)
+            }
+            Self::Parquet(writer) => {
+                writer.write(&record_batch(rows)?).map_err(export_error)?;
+                writer.flush().map_err(export_error)?;
+                // the writer keeps track of the bytes it wrote, taking them out is safe
+                Ok(std::mem::take(writer.inner_mut()))
+            }
+            Self::Calendar(writer) => Ok(writer.write(rows)),
+        }
+    }
+
+    /// the rest of the file, a csv file without rows still has the header and a calendar its
+    /// time zones
+    fn finish(self) -> Result<Vec<u8>, ReservationError> {
+        match self {
+            Self::Csv { header: true } => encode_csv(&[], true),
+            Self::Csv { header: false } => Ok(Vec::new()),
+            Self::Parquet(writer) => writer.into_inner().map_err(export_error),
+            Self::Calendar(writer) => Ok(writer.finish()),
+        }
+    }
+}
+
+fn encode_csv(rows: &[ExportRow], header: bool) -> Result<Vec<u8>, ReservationError> {
+    let utc = |time: Option<DateTime<Utc>>| {
+        time.map(|t| t.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            .unwrap_or_default()
+    };
+    let local = |time: Option<NaiveDateTime>| {
+        time.map(|t| t.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            .unwrap_or_default()
+    };
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if header {
+        writer.write_record(EXPORT_COLUMNS).map_err(export_error)?;
+    }
+    for row in rows {
+        let rsvp = &row.rsvp;
+        writer
+            .write_record([
+                rsvp.id.to_string(),
+                rsvp.user_id.clone(),
+                rsvp.resource_id.clone(),
+                row.status(),
+                utc(row.start()),
+                utc(row.end()),
+                row.tz.name().to_string(),
+                local(row.local(row.start())),
+                local(row.local(row.end())),
+                rsvp.note.clone(),
+                rsvp.group_id.clone(),
+                rsvp.cancel_reason.clone(),
+                rsvp.cancelled_by.clone(),
+                utc(rsvp.cancelled_at.clone().map(convert_to_utc_time)),
+                rsvp.version.to_string(),
+                utc(rsvp.created_at.clone().map(convert_to_utc_time)),
+                serde_json::to_string(&rsvp.tags).map_err(export_error)?,
+                row.attributes()?,
+            ])
+            .map_err(export_error)?;
+    }
+    writer.into_inner().map_err(export_error)
+}
+
+/// parquet types of the columns: times in UTC carry the time zone, local times don't, tags are a
+/// list and attributes a json object
+fn export_schema() -> SchemaRef {
+    let utc = || DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+    let local = || DataType::Timestamp(TimeUnit::Microsecond, None);
+    let tags = DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)));
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("user_id", DataType::Utf8, false),
+        Field::new("resource_id", DataType::Utf8, false),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("start_utc", utc(), false),
+        Field::new("end_utc", utc(), true),
+        Field::new("time_zone", DataType::Utf8, false),
+        Field::new("start_local", local(), false),
+        Field::new("end_local", local(), true),
+        Field::new("note", DataType::Utf8, false),
+        Field::new("group_id", DataType::Utf8, false),
+        Field::new("cancel_reason", DataType::Utf8, false),
+        Field::new("cancelled_by", DataType::Utf8, false),
+        Field::new("cancelled_at", utc(), true),
+        Field::new("version", DataType::Int64, false),
+        Field::new("created_at", utc(), true),
+        Field::new("tags", tags, false),
+        Field::new("attributes", DataType::Utf8, false),
+    ]))
+}
+
+fn record_batch(rows: &[ExportRow]) -> Result<RecordBatch, ReservationError> {
+    let text = |f: fn(&Reservation) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| f(&row.rsvp)),
+        ))
+    };
+    let utc = |f: &dyn Fn(&ExportRow) -> Option<DateTime<Utc>>| -> ArrayRef {
+        let values = rows.iter().map(|row| f(row).map(|t| t.timestamp_micros()));
+        Arc::new(TimestampMicrosecondArray::from_iter(values).with_timezone("UTC"))
+    };
+    let local = |f: &dyn Fn(&ExportRow) -> Option<DateTime<Utc>>| -> ArrayRef {
+        let values = rows
+            .iter()
+            .map(|row| row.local(f(row)).map(|t| t.and_utc().timestamp_micros()));
+        Arc::new(TimestampMicrosecondArray::from_iter(values))
+    };
+
+    let mut tags = ListBuilder::new(StringBuilder::new());
+    for row in rows {
+        for tag in &row.rsvp.tags {
+            tags.values().append_value(tag);
+        }
+        tags.append(true);
+    }
+    let attributes = rows
+        .iter()
+        .map(ExportRow::attributes)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(
+            rows.iter().map(|row| row.rsvp.id),
+        )),
+        text(|r| &r.user_id),
+        text(|r| &r.resource_id),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(ExportRow::status),
+        )),
+        utc(&ExportRow::start),
+        utc(&ExportRow::end),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.tz.name()),
+        )),
+        local(&ExportRow::start),
+        local(&ExportRow::end),
+        text(|r| &r.note),
+        text(|r| &r.group_id),
+        text(|r| &r.cancel_reason),
+        text(|r| &r.cancelled_by),
+        utc(&|row| row.rsvp.cancelled_at.clone().map(convert_to_utc_time)),
+        Arc::new(Int64Array::from_iter_values(
+            rows.iter().map(|row| row.rsvp.version),
+        )),
+        utc(&|row| row.rsvp.created_at.clone().map(convert_to_utc_time)),
+        Arc::new(tags.finish()),
+        Arc::new(StringArray::from_iter_values(attributes)),
+    ];
+    RecordBatch::try_new(export_schema(), columns).map_err(export_error)
+}
+
+fn export_error(e: impl ToString) -> ReservationError {
+    ReservationError::ExportFailed(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{ReservationQueryBuilder, ReservationSort, ReservationSortField};
+    use arrow_array::Array;
+    use bytes::Bytes;
+    use futures::TryStreamExt;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    async fn reserve_hours(manager: &ReservationManager, resource_id: &str, hours: i64) {
+        let start: DateTime<Utc> = "2023-01-02T00:00:00Z".parse().unwrap();
+        for i in 0..hours {
+            let rsvp = Reservation {
+                tags: vec!["vip".to_string()],
+                ..Reservation::new_pending(
+                    "hyx",
+                    resource_id,
+                    (start + chrono::Duration::hours(i)).into(),
+                    (start + chrono::Duration::hours(i + 1)).into(),
+                    "你好, \"acme\"",
+                )
+            };
+            manager.reserve(rsvp).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn export_schema_should_follow_columns() {
+        let schema = export_schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, EXPORT_COLUMNS);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn export_csv_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        sqlx::query(
+            "INSERT INTO rsvp.resources (id, time_zone) VALUES ('room-1', 'Asia/Shanghai')",
+        )
+        .execute(&migrated_pool)
+        .await
+        .unwrap();
+        reserve_hours(&manager, "room-1", 2).await;
+        reserve_hours(&manager, "room-2", 1).await;
+
+        let query = ReservationQueryBuilder::default()
+            .sort(vec![
+                ReservationSort::new(ReservationSortField::ResourceId, false),
+                ReservationSort::new(ReservationSortField::Start, false),
+            ])
+            .build()
+            .unwrap();
+        let chunks: Vec<Vec<u8>> = manager
+            .export(query, ExportFormat::Csv)
+            .try_collect()
+            .await
+            .unwrap();
+        let data = chunks.concat();
+        let mut reader = csv::Reader::from_reader(data.as_slice());
+        assert_eq!(reader.headers().unwrap(), EXPORT_COLUMNS.as_slice());
+
+        let records: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 3);
+        let row = &records[1];
+        assert_eq!(&row[2], "room-1");
+        assert_eq!(&row[3], "pending");
+        assert_eq!(&row[4], "2023-01-02T01:00:00Z");
+        assert_eq!(&row[6], "Asia/Shanghai");
+        assert_eq!(&row[7], "2023-01-02T09:00:00");
+        assert_eq!(&row[9], "你好, \"acme\"");
+        assert_eq!(&row[16], r#"["vip"]"#);
+        assert_eq!(&row[17], "{}");
+        assert_eq!(
+            (&records[2][6], &records[2][7]),
+            ("UTC", "2023-01-02T00:00:00")
+        );
+
+        // an empty export is only the header
+        let query = ReservationQueryBuilder::default()
+            .user_id("nobody")
+            .build()
+            .unwrap();
+        let chunks: Vec<Vec<u8>> = manager
+            .export(query, ExportFormat::Csv)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(chunks.concat()).unwrap(),
+            format!("{}\n", EXPORT_COLUMNS.join(","))
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn export_parquet_in_chunks_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        reserve_hours(&manager, "room-1", 250).await;
+
+        let query = ReservationQueryBuilder::default().build().unwrap();
+        let chunks: Vec<Vec<u8>> =
+            export_in_chunks(manager.clone(), query, ExportFormat::Parquet, 100)
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(chunks.len(), 3);
+
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(Bytes::from(chunks.concat())).unwrap();
+        // each chunk is a row group of its own
+        assert_eq!(builder.metadata().num_row_groups(), 3);
+        let batches: Vec<RecordBatch> = builder.build().unwrap().map(|b| b.unwrap()).collect();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 250);
+
+        let batch = &batches[0];
+        assert_eq!(batch.schema().fields(), export_schema().fields());
+        let start = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        let first: DateTime<Utc> = "2023-01-02T00:00:00Z".parse().unwrap();
+        assert_eq!(start.value(0), first.timestamp_micros());
+        assert!(batch.column(5).null_count() == 0);
+    }
+}
@@ -0,0 +1,221 @@
+use abi::{
+    feed_token_hash, new_feed_token, CalendarFeed, CalendarFeedKind, ReservationError, RsvpFeedKind,
+};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+use crate::{FeedVersion, ReservationManager};
+
+const MAX_SUBJECT_LEN: usize = 64;
+
+impl ReservationManager {
+    /// create a feed of the reservations of a resource or user, returns it with its token
+    pub async fn create_feed(
+        &self,
+        kind: CalendarFeedKind,
+        subject: String,
+    ) -> Result<(CalendarFeed, String), ReservationError> {
+        if subject.is_empty() || subject.len() > MAX_SUBJECT_LEN {
+            return Err(match kind {
+                CalendarFeedKind::Resource => ReservationError::InvalidResourceId(subject),
+                CalendarFeedKind::User => ReservationError::InvalidUserId(subject),
+            });
+        }
+
+        let token = new_feed_token();
+        let feed = sqlx::query_as(
+            "INSERT INTO rsvp.calendar_feeds (kind, subject, token_hash) VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(RsvpFeedKind::from(kind))
+        .bind(subject)
+        .bind(feed_token_hash(&token))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok((feed, token))
+    }
+
+    /// revoke a feed, its token is not accepted anymore. A revoked feed keeps its revoked_at
+    pub async fn revoke_feed(&self, id: i64) -> Result<CalendarFeed, ReservationError> {
+        sqlx::query_as(
+            "UPDATE rsvp.calendar_feeds SET revoked_at = coalesce(revoked_at, now()) WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(ReservationError::FeedNotFound(id))
+    }
+
+    /// feeds of a resource or user, or all feeds if subject is empty, in creation order
+    pub async fn list_feeds(
+        &self,
+        subject: &str,
+        include_revoked: bool,
+    ) -> Result<Vec<CalendarFeed>, ReservationError> {
+        let feeds = sqlx::query_as(
+            "SELECT * FROM rsvp.calendar_feeds WHERE ($1 = '' OR subject = $1) AND ($2 OR revoked_at IS NULL) ORDER BY id",
+        )
+        .bind(subject)
+        .bind(include_revoked)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(feeds)
+    }
+
+    /// the feed of a token, unknown tokens and tokens of revoked feeds are rejected alike
+    pub async fn get_feed(&self, token: &str) -> Result<CalendarFeed, ReservationError> {
+        sqlx::query_as(
+            "SELECT * FROM rsvp.calendar_feeds WHERE token_hash = $1 AND revoked_at IS NULL",
+        )
+        .bind(feed_token_hash(token))
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(ReservationError::InvalidFeedToken)
+    }
+
+    /// version of the content of a feed, read from the change log. It changes with every change
+    /// of a reservation which is or was of the resource or user of the feed
+    pub async fn feed_version(&self, feed: &CalendarFeed) -> Result<FeedVersion, ReservationError> {
+        // the column is picked by kind, never from the request
+        let column = match feed.get_kind()? {
+            CalendarFeedKind::Resource => "resource_id",
+            CalendarFeedKind::User => "user_id",
+        };
+        let sql = format!(
+            "SELECT count(*) AS changes, max(changed_at) AS last_changed_at FROM rsvp.reservation_changes WHERE new->>'{0}' = $1 \
+             UNION ALL \
+             SELECT count(*), max(changed_at) FROM rsvp.reservation_changes WHERE old->>'{0}' = $1",
+            column
+        );
+        let rows = sqlx::query(&sql)
+            .bind(&feed.subject)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut changes = 0i64;
+        let mut last_changed_at = None;
+        let mut last_modified = feed
+            .created_at
+            .clone()
+            .map(abi::convert_to_utc_time)
+            .unwrap_or_default();
+        for row in rows {
+            changes += row.get::<i64, _>("changes");
+            if let Some(at) = row.get::<Option<DateTime<Utc>>, _>("last_changed_at") {
+                last_changed_at = last_changed_at.max(Some(at));
+                last_modified = last_modified.max(at);
+            }
+        }
+
+        Ok(FeedVersion {
+            // a change committed late could be older than the last one, the count still moves
+            etag: format!(
+                "\"{}-{}-{}\"",
+                feed.id,
+                changes,
+                last_changed_at.map_or(0, |at| at.timestamp_micros())
+            ),
+            last_modified,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::Reservation;
+
+    use super::*;
+    use crate::Rsvp;
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn feed_token_should_work_until_revoked() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let (feed, token) = manager
+            .create_feed(CalendarFeedKind::Resource, "room-1".into())
+            .await
+            .unwrap();
+        assert_eq!(feed.kind, CalendarFeedKind::Resource as i32);
+        assert_eq!(feed.subject, "room-1");
+        assert!(feed.revoked_at.is_none());
+
+        assert_eq!(manager.get_feed(&token).await.unwrap(), feed);
+        assert_eq!(
+            manager.get_feed("unknown").await.unwrap_err(),
+            ReservationError::InvalidFeedToken
+        );
+        assert_eq!(
+            manager.list_feeds("room-1", false).await.unwrap(),
+            vec![feed.clone()]
+        );
+
+        let revoked = manager.revoke_feed(feed.id).await.unwrap();
+        assert!(revoked.revoked_at.is_some());
+        assert_eq!(manager.revoke_feed(feed.id).await.unwrap(), revoked);
+        assert_eq!(
+            manager.get_feed(&token).await.unwrap_err(),
+            ReservationError::InvalidFeedToken
+        );
+        assert!(manager.list_feeds("", false).await.unwrap().is_empty());
+        assert_eq!(manager.list_feeds("", true).await.unwrap(), [revoked]);
+        assert_eq!(
+            manager.revoke_feed(999).await.unwrap_err(),
+            ReservationError::FeedNotFound(999)
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn create_feed_should_reject_empty_subject() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let err = manager
+            .create_feed(CalendarFeedKind::User, "".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::InvalidUserId("".into()));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn feed_version_should_change_with_reservations_of_subject() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let (feed, _) = manager
+            .create_feed(CalendarFeedKind::Resource, "room-1".into())
+            .await
+            .unwrap();
+        let empty = manager.feed_version(&feed).await.unwrap();
+        assert_eq!(
+            Some(abi::convert_to_timestamp(empty.last_modified)),
+            feed.created_at
+        );
+
+        let rsvp = manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-1",
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "note",
+            ))
+            .await
+            .unwrap();
+        let reserved = manager.feed_version(&feed).await.unwrap();
+        assert_ne!(reserved.etag, empty.etag);
+        assert!(reserved.last_modified >= empty.last_modified);
+
+        // a reservation of another resource leaves the feed alone
+        manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-2",
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "note",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(manager.feed_version(&feed).await.unwrap(), reserved);
+
+        manager.purge(rsvp.id).await.unwrap();
+        assert_ne!(
+            manager.feed_version(&feed).await.unwrap().etag,
+            reserved.etag
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use abi::ReservationError;
+use sqlx::{Postgres, Row, Transaction};
+
+use crate::{IdempotencyClaim, IdempotentCall, ReservationManager};
+
+const MAX_KEY_LEN: usize = 128;
+/// how long a claim waits for another call with the same key to finish
+const CLAIM_WAIT: &str = "5s";
+
+impl ReservationManager {
+    /// claim the key for a call of method with the hash of its request, the key is kept for
+    /// window after the call. A key which was completed before returns the stored response,
+    /// if it was used for the same method and request
+    pub async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        method: &str,
+        request_hash: &[u8],
+        window: Duration,
+    ) -> Result<IdempotencyClaim, ReservationError> {
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(ReservationError::InvalidIdempotencyKey(key.to_string()));
+        }
+
+        sqlx::query("DELETE FROM rsvp.idempotency_keys WHERE expires_at < now()")
+            .execute(&self.pool)
+            .await?;
+
+        // the insert waits for the transaction of another call holding the key
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT set_config('lock_timeout', $1, true)")
+            .bind(CLAIM_WAIT)
+            .execute(&mut tx)
+            .await?;
+        let claimed = sqlx::query(
+            "INSERT INTO rsvp.idempotency_keys (key, method, request_hash, expires_at) VALUES ($1, $2, $3, now() + $4 * interval '1 second') ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(key)
+        .bind(method)
+        .bind(request_hash)
+        .bind(window.as_secs_f64())
+        .execute(&mut tx)
+        .await
+        .map_err(|e| claim_error(e, key))?
+        .rows_affected()
+            == 1;
+        if claimed {
+            sqlx::query("SET LOCAL lock_timeout TO DEFAULT")
+                .execute(&mut tx)
+                .await?;
+            return Ok(IdempotencyClaim::Claimed(IdempotentCall {
+                key: key.to_string(),
+                method: method.to_string(),
+                request_hash: request_hash.to_vec(),
+                window,
+                tx: Arc::new(Mutex::new(Some(tx))),
+            }));
+        }
+        tx.rollback().await?;
+
+        let row = sqlx::query(
+            "SELECT method, request_hash, response FROM rsvp.idempotency_keys WHERE key = $1",
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        // the key expired in between, let the client retry
+        let row = row.ok_or_else(|| ReservationError::IdempotencyKeyInProgress(key.to_string()))?;
+
+        let stored_method: String = row.get("method");
+        let stored_hash: Vec<u8> = row.get("request_hash");
+        if stored_method != method || stored_hash != request_hash {
+            return Err(ReservationError::IdempotencyKeyReused(key.to_string()));
+        }
+        match row.get::<Option<Vec<u8>>, _>("response") {
+            Some(response) => Ok(IdempotencyClaim::Completed(response)),
+            None => Err(ReservationError::IdempotencyKeyInProgress(key.to_string())),
+        }
+    }
+
+    /// a manager making its changes in the transaction of the claimed key, they are committed
+    /// by complete_idempotency_key, and rolled back if the claim is dropped
+    pub fn with_idempotent_call(&self, call: IdempotentCall) -> Self {
+        Self {
+            idempotent_call: Some(call),
+            ..self.clone()
+        }
+    }
+
+    /// store the encoded response of the call made with the key, and commit it with the changes
+    /// of the call
+    pub async fn complete_idempotency_key(
+        &self,
+        call: IdempotentCall,
+        response: Vec<u8>,
+    ) -> Result<(), ReservationError> {
+        // a call which changed nothing may have rolled back the transaction of the key
+        let mut tx = match call.take_tx() {
+            Some(tx) => tx,
+            None => self.pool.begin().await?,
+        };
+        sqlx::query(
+            "INSERT INTO rsvp.idempotency_keys (key, method, request_hash, response, expires_at) VALUES ($1, $2, $3, $4, now() + $5 * interval '1 second') ON CONFLICT (key) DO UPDATE SET response = excluded.response WHERE rsvp.idempotency_keys.response IS NULL",
+        )
+        .bind(&call.key)
+        .bind(&call.method)
+        .bind(&call.request_hash)
+        .bind(response)
+        .bind(call.window.as_secs_f64())
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+impl IdempotentCall {
+    /// the transaction of the key, for the next change of the call
+    pub(crate) fn take_tx(&self) -> Option<Transaction<'static, Postgres>> {
+        self.tx.lock().expect("idempotent call lock").take()
+    }
+
+    /// keep the transaction of a change until the response is stored
+    pub(crate) fn put_tx(&self, tx: Transaction<'static, Postgres>) {
+        *self.tx.lock().expect("idempotent call lock") = Some(tx);
+    }
+}
+
+/// a lock timeout means the key is held by a call in progress
+fn claim_error(e: sqlx::Error, key: &str) -> ReservationError {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("55P03") => {
+            ReservationError::IdempotencyKeyInProgress(key.to_string())
+        }
+        _ => e.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::Reservation;
+
+    use super::*;
+    use crate::Rsvp;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    async fn claim(
+        manager: &ReservationManager,
+        key: &str,
+        method: &str,
+        request: &[u8],
+    ) -> Result<IdempotencyClaim, ReservationError> {
+        manager
+            .claim_idempotency_key(key, method, request, WINDOW)
+            .await
+    }
+
+    fn claimed(claim: IdempotencyClaim) -> IdempotentCall {
+        match claim {
+            IdempotencyClaim::Claimed(call) => call,
+            claim => panic!("expected a claim, got {:?}", claim),
+        }
+    }
+
+    async fn count(manager: &ReservationManager) -> i64 {
+        sqlx::query_scalar("SELECT count(*) FROM rsvp.reservations")
+            .fetch_one(&manager.pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn claim_idempotency_key_should_return_stored_response() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+
+        let call = claimed(claim(&manager, "key-1", "reserve", b"a").await.unwrap());
+        manager
+            .complete_idempotency_key(call, vec![1, 2, 3])
+            .await
+            .unwrap();
+        let claim_again = claim(&manager, "key-1", "reserve", b"a").await.unwrap();
+        assert!(matches!(claim_again, IdempotencyClaim::Completed(r) if r == [1, 2, 3]));
+
+        // another call or another request with the same key
+        for (method, request) in [("cancel", b"a"), ("reserve", b"b")] {
+            let err = claim(&manager, "key-1", method, request).await.unwrap_err();
+            assert_eq!(err, ReservationError::IdempotencyKeyReused("key-1".into()));
+        }
+
+        let err = claim(&manager, "", "reserve", b"a").await.unwrap_err();
+        assert_eq!(err, ReservationError::InvalidIdempotencyKey("".into()));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn changes_should_be_committed_with_the_response() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "alice",
+            "room-1",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "note",
+        );
+
+        // a dropped claim rolls back the changes, and the key can be claimed again
+        let call = claimed(claim(&manager, "key-1", "reserve", b"a").await.unwrap());
+        manager
+            .with_idempotent_call(call.clone())
+            .reserve(rsvp.clone())
+            .await
+            .unwrap();
+        drop(call);
+        assert_eq!(count(&manager).await, 0);
+
+        let call = claimed(claim(&manager, "key-1", "reserve", b"a").await.unwrap());
+        manager
+            .with_idempotent_call(call.clone())
+            .reserve(rsvp)
+            .await
+            .unwrap();
+        assert_eq!(count(&manager).await, 0);
+
+        // a retry waits for the call holding the key, then gets its response
+        let retry = {
+            let manager = manager.clone();
+            tokio::spawn(async move { claim(&manager, "key-1", "reserve", b"a").await })
+        };
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!retry.is_finished());
+        manager
+            .complete_idempotency_key(call, vec![1])
+            .await
+            .unwrap();
+        assert_eq!(count(&manager).await, 1);
+        let claim_again = retry.await.unwrap().unwrap();
+        assert!(matches!(claim_again, IdempotencyClaim::Completed(r) if r == [1]));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn expired_idempotency_key_should_be_claimed_again() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+
+        let call = claimed(
+            manager
+                .claim_idempotency_key("key-2", "reserve", b"a", Duration::ZERO)
+                .await
+                .unwrap(),
+        );
+        manager
+            .complete_idempotency_key(call, vec![1])
+            .await
+            .unwrap();
+        claimed(claim(&manager, "key-2", "reserve", b"b").await.unwrap());
+    }
+}
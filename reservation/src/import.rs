@@ -0,0 +1,608 @@
+use std::collections::{BTreeMap, HashMap};
+
+use abi::{
+    attributes_from_json, convert_to_timestamp, convert_to_utc_time, ImportFormat, ImportMode,
+    ImportRequest, ImportResponse, ImportRowReport, ImportRowStatus, Reservation, ReservationError,
+    ReservationStatus, Validator,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::Acquire;
+
+use crate::{calendar::parse_calendar, manager::insert_row, ReservationManager};
+
+/// columns read from a csv import, named as the columns of an export
+pub const IMPORT_COLUMNS: [&str; 8] = [
+    "user_id",
+    "resource_id",
+    "status",
+    "start_utc",
+    "end_utc",
+    "note",
+    "tags",
+    "attributes",
+];
+
+const REQUIRED_COLUMNS: [&str; 3] = ["user_id", "resource_id", "start_utc"];
+
+/// check every row of the file, then import the rows unless it's a dry run. All the rows are
+/// imported in one transaction, or the invalid rows are skipped and the others are imported a
+/// batch per transaction
+pub(crate) async fn import(
+    manager: &ReservationManager,
+    request: ImportRequest,
+) -> Result<ImportResponse, ReservationError> {
+    let format = request.get_format()?;
+    let mode = request.get_mode()?;
+    let batch_size = request.get_batch_size()?;
+
+    let parsed = match format {
+        ImportFormat::Csv => parse_csv(&request.data)?,
+        ImportFormat::Json => parse_json(&request.data)?,
+        ImportFormat::Icalendar => parse_calendar(&request.data)?,
+    };
+    let mut import = Import::new(parsed, &request);
+    import.check_in_file();
+    import.check_database(manager, batch_size).await?;
+
+    if !request.dry_run {
+        match mode {
+            ImportMode::AllOrNothing if import.failed() == 0 => import.insert_all(manager).await?,
+            ImportMode::AllOrNothing => {}
+            ImportMode::SkipInvalid => import.insert_valid(manager, batch_size).await?,
+        }
+    }
+    Ok(import.into_response())
+}
+
+struct Import {
+    rows: Vec<ImportRow>,
+}
+
+struct ImportRow {
+    /// empty if the row could not be parsed
+    rsvp: Reservation,
+    report: ImportRowReport,
+}
+
+impl ImportRow {
+    fn is_ok(&self) -> bool {
+        self.report.status == ImportRowStatus::Ok as i32
+    }
+
+    /// cancelled reservations don't block the slot, as in the database
+    fn blocks_slot(&self) -> bool {
+        self.is_ok() && self.rsvp.status != ReservationStatus::Cancelled as i32
+    }
+
+    fn span(&self) -> (DateTime<Utc>, Option<DateTime<Utc>>) {
+        (
+            self.rsvp.start.clone().map(convert_to_utc_time).unwrap(),
+            self.rsvp.end.clone().map(convert_to_utc_time),
+        )
+    }
+
+    fn fail(&mut self, status: ImportRowStatus, reason: String) {
+        self.report.status = status as i32;
+        self.report.reason = reason;
+        self.report.reservation_id = 0;
+    }
+}
+
+impl Import {
+    /// a row per parsed reservation, the defaults of the request fill in the missing user and
+    /// resource before the reservation is validated
+    fn new(parsed: Vec<Result<Reservation, ReservationError>>, request: &ImportRequest) -> Self {
+        let rows = parsed
+            .into_iter()
+            .enumerate()
+            .map(|(index, rsvp)| {
+                let mut row = ImportRow {
+                    rsvp: Reservation::default(),
+                    report: ImportRowReport {
+                        row: index as i32 + 1,
+                        ..Default::default()
+                    },
+                };
+                let rsvp = rsvp.and_then(|mut rsvp| {
+                    if rsvp.user_id.is_empty() {
+                        rsvp.user_id = request.user_id.clone();
+                    }
+                    if rsvp.resource_id.is_empty() {
+                        rsvp.resource_id = request.resource_id.clone();
+                    }
+                    rsvp.validate()?;
+                    Ok(rsvp)
+                });
+                match rsvp {
+                    Ok(rsvp) => row.rsvp = rsvp,
+                    Err(e) => row.fail(ImportRowStatus::Invalid, e.to_string()),
+                }
+                row
+            })
+            .collect();
+        Self { rows }
+    }
+
+    fn failed(&self) -> usize {
+        self.rows.iter().filter(|row| !row.is_ok()).count()
+    }
+
+    /// mark the rows overlapping an earlier row of the file on the same resource
+    fn check_in_file(&mut self) {
+        // start -> (end, row) of the rows kept so far per resource, they never overlap each other
+        type Slots = BTreeMap<DateTime<Utc>, (Option<DateTime<Utc>>, i32)>;
+        let mut kept: HashMap<String, Slots> = HashMap::new();
+
+        for row in self.rows.iter_mut().filter(|row| row.blocks_slot()) {
+            let (start, end) = row.span();
+            let slots = kept.entry(row.rsvp.resource_id.clone()).or_default();
+            let before = slots
+                .range(..=start)
+                .next_back()
+                .filter(|(_, (e, _))| e.is_none_or(|e| e > start));
+            let after = slots
+                .range(start..)
+                .next()
+                .filter(|(s, _)| end.is_none_or(|e| **s < e));
+            match before.or(after).map(|(_, (_, r))| *r) {
+                Some(conflict_row) => {
+                    row.fail(
+                        ImportRowStatus::ConflictInFile,
+                        format!("overlaps row {}", conflict_row),
+                    );
+                    row.report.conflict_row = conflict_row;
+                }
+                None => {
+                    slots.insert(start, (end, row.report.row));
+                }
+            }
+        }
+    }
+
+    /// mark the rows overlapping a reservation in the database, a batch of rows per query
+    async fn check_database(
+        &mut self,
+        manager: &ReservationManager,
+        batch_size: usize,
+    ) -> Result<(), ReservationError> {
+        let mut rows: Vec<&mut ImportRow> = self
+            .rows
+            .iter_mut()
+            .filter(|row| row.blocks_slot())
+            .collect();
+
+        for batch in rows.chunks_mut(batch_size) {
+            let resource_ids: Vec<String> =
+                batch.iter().map(|r| r.rsvp.resource_id.clone()).collect();
+            let (starts, ends): (Vec<_>, Vec<_>) = batch.iter().map(|r| r.span()).unzip();
+
+            let conflicts: Vec<(i64, i64)> = sqlx::query_as(
+                r#"SELECT i.ord, r.id
+                FROM unnest($1::text[], $2::timestamptz[], $3::timestamptz[])
+                    WITH ORDINALITY AS i(resource_id, start_at, end_at, ord)
+                CROSS JOIN LATERAL (
+                    SELECT id FROM rsvp.reservations
+                    WHERE resource_id = i.resource_id AND status <> 'cancelled'
+                        AND timespan && tstzrange(i.start_at, i.end_at)
+                    ORDER BY id LIMIT 1
+                ) r"#,
+            )
+            .bind(resource_ids)
+            .bind(starts)
+            .bind(ends)
+            .fetch_all(&manager.pool)
+            .await?;
+
+            for (ord, id) in conflicts {
+                let row = &mut batch[ord as usize - 1];
+                row.fail(
+                    ImportRowStatus::Conflict,
+                    format!("overlaps reservation {}", id),
+                );
+                row.report.conflict_id = id;
+            }
+        }
+        Ok(())
+    }
+
+    /// insert every row in one transaction, nothing is imported if a row fails
+    async fn insert_all(&mut self, manager: &ReservationManager) -> Result<(), ReservationError> {
+        let mut tx = manager.begin(&manager.context).await?;
+        let mut failed = false;
+        for row in self.rows.iter_mut() {
+            match insert_row(&mut tx, &mut row.rsvp, None).await {
+                Ok(()) => row.report.reservation_id = row.rsvp.id,
+                // another writer took the slot after the check
+                Err(e @ ReservationError::ConflictReservation(_)) => {
+                    row.fail(ImportRowStatus::Conflict, e.to_string());
+                    failed = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        if failed {
+            tx.rollback().await?;
+            for row in self.rows.iter_mut() {
+                row.report.reservation_id = 0;
+            }
+        } else {
+            manager.commit(tx).await?;
+        }
+        Ok(())
+    }
+
+    /// insert the rows which passed the checks a batch per transaction, a row which fails is
+    /// skipped
+    async fn insert_valid(
+        &mut self,
+        manager: &ReservationManager,
+        batch_size: usize,
+    ) -> Result<(), ReservationError> {
+        let mut rows: Vec<&mut ImportRow> =
+            self.rows.iter_mut().filter(|row| row.is_ok()).collect();
+
+        for batch in rows.chunks_mut(batch_size) {
+            let mut tx = manager.begin(&manager.context).await?;
+            for row in batch.iter_mut() {
+                // a savepoint per row, so a failed row doesn't abort the batch
+                let mut row_tx = tx.begin().await?;
+                match insert_row(&mut row_tx, &mut row.rsvp, None).await {
+                    Ok(()) => {
+                        row_tx.commit().await?;
+                        row.report.reservation_id = row.rsvp.id;
+                    }
+                    Err(e @ ReservationError::ConflictReservation(_)) => {
+                        row_tx.rollback().await?;
+                        row.fail(ImportRowStatus::Conflict, e.to_string());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            manager.commit(tx).await?;
+        }
+        Ok(())
+    }
+
+    fn into_response(self) -> ImportResponse {
+        let imported = self
+            .rows
+            .iter()
+            .filter(|row| row.report.reservation_id != 0)
+            .count();
+        let failed = self.failed();
+        ImportResponse {
+            rows: self.rows.into_iter().map(|row| row.report).collect(),
+            imported: imported as i32,
+            failed: failed as i32,
+        }
+    }
+}
+
+/// the fields of a row, whatever the format of the file
+struct ImportFields<'a> {
+    user_id: &'a str,
+    resource_id: &'a str,
+    status: &'a str,
+    start: &'a str,
+    end: &'a str,
+    note: &'a str,
+    tags: Value,
+    attributes: Value,
+}
+
+/// a row per record, a file without a header of the required columns is rejected
+fn parse_csv(data: &[u8]) -> Result<Vec<Result<Reservation, ReservationError>>, ReservationError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader.headers().map_err(import_error)?.clone();
+    let columns: HashMap<&str, usize> = headers.iter().enumerate().map(|(i, h)| (h, i)).collect();
+    if let Some(missing) = REQUIRED_COLUMNS.iter().find(|c| !columns.contains_key(*c)) {
+        return Err(ReservationError::InvalidImportData(format!(
+            "missing column {}",
+            missing
+        )));
+    }
+
+    let rows = reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(import_error)?;
+            let text = |name: &str| columns.get(name).and_then(|&i| record.get(i)).unwrap_or("");
+            let json = |name: &str| match text(name) {
+                "" => Ok(Value::Null),
+                v => serde_json::from_str(v)
+                    .map_err(|e| ReservationError::InvalidImportData(format!("{}: {}", name, e))),
+            };
+            to_reservation(ImportFields {
+                user_id: text("user_id"),
+                resource_id: text("resource_id"),
+                status: text("status"),
+                start: text("start_utc"),
+                end: text("end_utc"),
+                note: text("note"),
+                tags: json("tags")?,
+                attributes: json("attributes")?,
+            })
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// a row per object of the array, a file which isn't a json array is rejected
+fn parse_json(data: &[u8]) -> Result<Vec<Result<Reservation, ReservationError>>, ReservationError> {
+    let values: Vec<Value> = serde_json::from_slice(data).map_err(import_error)?;
+    let rows = values
+        .into_iter()
+        .map(|value| {
+            let Value::Object(mut row) = value else {
+                return Err(ReservationError::InvalidImportData(
+                    "row is not an object".into(),
+                ));
+            };
+            let tags = row.remove("tags").unwrap_or(Value::Null);
+            let attributes = row.remove("attributes").unwrap_or(Value::Null);
+            let text = |name: &str| match row.get(name) {
+                None | Some(Value::Null) => Ok(""),
+                Some(Value::String(v)) => Ok(v.as_str()),
+                Some(_) => Err(ReservationError::InvalidImportData(format!(
+                    "{} is not a string",
+                    name
+                ))),
+            };
+            to_reservation(ImportFields {
+                user_id: text("user_id")?,
+                resource_id: text("resource_id")?,
+                status: text("status")?,
+                start: text("start_utc")?,
+                end: text("end_utc")?,
+                note: text("note")?,
+                tags,
+                attributes,
+            })
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// the reservation of the fields, pending if the status is empty. It's validated by the caller
+fn to_reservation(fields: ImportFields) -> Result<Reservation, ReservationError> {
+    let status = match fields.status {
+        "" => ReservationStatus::Pending,
+        v => parse_status(v)?,
+    };
+    let tags = match fields.tags {
+        Value::Null => vec![],
+        Value::Array(tags) => tags
+            .into_iter()
+            .map(|tag| match tag {
+                Value::String(tag) => Ok(tag),
+                v => Err(ReservationError::InvalidTag(v.to_string())),
+            })
+            .collect::<Result<_, _>>()?,
+        v => {
+            return Err(ReservationError::InvalidImportData(format!(
+                "tags is not an array: {}",
+                v
+            )))
+        }
+    };
+    let attributes = match fields.attributes {
+        Value::Null => HashMap::new(),
+        v @ Value::Object(_) => attributes_from_json(&v),
+        v => {
+            return Err(ReservationError::InvalidImportData(format!(
+                "attributes is not an object: {}",
+                v
+            )))
+        }
+    };
+
+    Ok(Reservation {
+        user_id: fields.user_id.to_string(),
+        resource_id: fields.resource_id.to_string(),
+        status: status as i32,
+        start: parse_time("start_utc", fields.start)?.map(convert_to_timestamp),
+        end: parse_time("end_utc", fields.end)?.map(convert_to_timestamp),
+        note: fields.note.to_string(),
+        tags,
+        attributes,
+        ..Default::default()
+    })
+}
+
+/// any status but unknown, so past reservations could be imported as they ended
+fn parse_status(s: &str) -> Result<ReservationStatus, ReservationError> {
+    use ReservationStatus::*;
+
+    [
+        Pending, Blocked, Confirmed, CheckedIn, Completed, Cancelled, Expired, NoShow,
+    ]
+    .into_iter()
+    .find(|status| status.to_string() == s)
+    .ok_or_else(|| ReservationError::InvalidImportData(format!("unknown status: {}", s)))
+}
+
+/// an rfc 3339 time, None if empty
+fn parse_time(name: &str, s: &str) -> Result<Option<DateTime<Utc>>, ReservationError> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| Some(t.with_timezone(&Utc)))
+        .map_err(|_| ReservationError::InvalidImportData(format!("{}: {}", name, s)))
+}
+
+fn import_error(e: impl std::fmt::Display) -> ReservationError {
+    ReservationError::InvalidImportData(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::ReservationQueryBuilder;
+
+    use super::*;
+    use crate::Rsvp;
+
+    const CSV: &str = "\
+id,user_id,resource_id,status,start_utc,end_utc,note,tags,attributes
+1,hyx,room-1,completed,2023-01-02T00:00:00Z,2023-01-02T01:00:00Z,\"你好, 旧系统\",\"[\"\"vip\"\"]\",\"{\"\"attendees\"\":12}\"
+2,hyx,room-1,,2023-01-02T00:30:00Z,2023-01-02T02:00:00Z,,,
+3,hyx,room-1,cancelled,2023-01-02T00:30:00Z,2023-01-02T02:00:00Z,,,
+4,hyx,room-2,confirmed,2023-01-02T00:00:00Z,,,,
+5,hyx,room-3,pending,yesterday,,,,
+6,,room-3,pending,2023-01-02T00:00:00Z,,,,
+";
+
+    fn statuses(resp: &ImportResponse) -> Vec<ImportRowStatus> {
+        resp.rows
+            .iter()
+            .map(|r| ImportRowStatus::from_i32(r.status).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parse_csv_should_work() {
+        let rows = parse_csv(CSV.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 6);
+
+        let rsvp = rows[0].as_ref().unwrap();
+        assert_eq!(rsvp.status, ReservationStatus::Completed as i32);
+        assert_eq!(rsvp.note, "你好, 旧系统");
+        assert_eq!(rsvp.tags, vec!["vip"]);
+        assert_eq!(
+            rsvp.attributes["attendees"].value,
+            Some(abi::attribute_value::Value::IntValue(12))
+        );
+        assert_eq!(
+            rows[1].as_ref().unwrap().status,
+            ReservationStatus::Pending as i32
+        );
+        assert_eq!(rows[3].as_ref().unwrap().end, None);
+        assert_eq!(
+            rows[4].as_ref().unwrap_err(),
+            &ReservationError::InvalidImportData("start_utc: yesterday".into())
+        );
+        // validated with the defaults of the request
+        assert_eq!(rows[5].as_ref().unwrap().user_id, "");
+
+        assert_eq!(
+            parse_csv(b"user_id,start_utc\nhyx,2023-01-02T00:00:00Z\n").unwrap_err(),
+            ReservationError::InvalidImportData("missing column resource_id".into())
+        );
+    }
+
+    #[test]
+    fn parse_json_should_work() {
+        let data = r#"[
+            {"user_id": "hyx", "resource_id": "room-1", "start_utc": "2023-01-02T08:00:00+08:00",
+             "tags": ["vip"], "attributes": {"catering": true}},
+            {"user_id": "hyx", "resource_id": "room-1", "start_utc": 42},
+            "hyx"
+        ]"#;
+        let rows = parse_json(data.as_bytes()).unwrap();
+        let rsvp = rows[0].as_ref().unwrap();
+        assert_eq!(
+            convert_to_utc_time(rsvp.start.clone().unwrap()).to_rfc3339(),
+            "2023-01-02T00:00:00+00:00"
+        );
+        assert_eq!(rsvp.tags, vec!["vip"]);
+        assert_eq!(
+            rows[1].as_ref().unwrap_err(),
+            &ReservationError::InvalidImportData("start_utc is not a string".into())
+        );
+        assert!(rows[2].is_err());
+
+        assert!(matches!(
+            parse_json(b"{}"),
+            Err(ReservationError::InvalidImportData(_))
+        ));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn import_should_check_and_insert_rows() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // takes the slot of row 4
+        let existing = manager
+            .reserve(Reservation::new_pending(
+                "tyr",
+                "room-2",
+                "2023-01-05T00:00:00Z".parse().unwrap(),
+                "2023-01-06T00:00:00Z".parse().unwrap(),
+                "",
+            ))
+            .await
+            .unwrap();
+        let count = || async {
+            let query = ReservationQueryBuilder::default()
+                .user_id("hyx")
+                .include_cancelled(true)
+                .build()
+                .unwrap();
+            manager.query(query).await.unwrap().0.len()
+        };
+
+        let request = ImportRequest {
+            data: CSV.as_bytes().to_vec(),
+            dry_run: true,
+            ..Default::default()
+        };
+        let resp = manager.import(request.clone()).await.unwrap();
+        assert_eq!(
+            statuses(&resp),
+            vec![
+                ImportRowStatus::Ok,
+                ImportRowStatus::ConflictInFile,
+                ImportRowStatus::Ok,
+                ImportRowStatus::Conflict,
+                ImportRowStatus::Invalid,
+                ImportRowStatus::Invalid,
+            ]
+        );
+        assert_eq!(resp.rows[1].conflict_row, 1);
+        assert_eq!(resp.rows[1].reason, "overlaps row 1");
+        assert_eq!(resp.rows[3].conflict_id, existing.id);
+        assert_eq!((resp.imported, resp.failed), (0, 4));
+
+        // all or nothing imports nothing as some rows failed
+        let resp = manager
+            .import(ImportRequest {
+                dry_run: false,
+                ..request.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!((resp.imported, resp.failed), (0, 4));
+        assert_eq!(count().await, 0);
+
+        let resp = manager
+            .import(ImportRequest {
+                dry_run: false,
+                mode: ImportMode::SkipInvalid as i32,
+                batch_size: 1,
+                ..request
+            })
+            .await
+            .unwrap();
+        assert_eq!((resp.imported, resp.failed), (2, 4));
+        assert_ne!(resp.rows[0].reservation_id, 0);
+        assert_eq!(resp.rows[1].reservation_id, 0);
+        let cancelled = manager.get(resp.rows[2].reservation_id).await.unwrap();
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+        assert_eq!(count().await, 2);
+
+        // the rows are in the database now, so all of them conflict
+        let data = r#"[{"user_id": "hyx", "resource_id": "room-1",
+            "start_utc": "2023-01-02T00:00:00Z", "end_utc": "2023-01-02T01:00:00Z"}]"#;
+        let resp = manager
+            .import(ImportRequest {
+                data: data.as_bytes().to_vec(),
+                format: ImportFormat::Json as i32,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(statuses(&resp), vec![ImportRowStatus::Conflict]);
+    }
+}
@@ -1,43 +1,222 @@
-use abi::{FilterPager, ReservationError};
+use abi::{FilterPager, PageTokenKey, ReservationError};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use prost_types::FieldMask;
 
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{watch, OnceCell};
 
+mod caldav;
+mod calendar;
+mod export;
+mod feed;
+mod idempotency;
+mod import;
+mod listen;
 mod manager;
+mod query;
+mod resource;
 
-#[derive(Debug)]
+pub use export::EXPORT_COLUMNS;
+pub use import::IMPORT_COLUMNS;
+
+#[derive(Debug, Clone)]
 pub struct ReservationManager {
     pool: PgPool,
+    context: ChangeContext,
+    /// signs the page tokens of query results
+    page_token_key: PageTokenKey,
+    /// the notifications of the change log, shared by the change streams of the manager and
+    /// its clones, started by the first stream
+    notifications: Arc<OnceCell<watch::Receiver<()>>>,
+    /// the claimed idempotency key of the call the manager makes changes for
+    idempotent_call: Option<IdempotentCall>,
+}
+
+/// who makes the changes and why, recorded with every change in rsvp.reservation_changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeContext {
+    pub actor: String,
+    pub reason: String,
+}
+
+/// result of claiming an idempotency key
+#[derive(Debug, Clone)]
+pub enum IdempotencyClaim {
+    /// the key is new, the caller should make the call with a manager of the claim and
+    /// complete the key with its response
+    Claimed(IdempotentCall),
+    /// the call was made before, with the encoded response
+    Completed(Vec<u8>),
+}
+
+/// a claimed idempotency key, held by an open transaction. The changes of the call are made in
+/// the transaction and committed with the response, a call with the same key waits until then
+#[derive(Debug, Clone)]
+pub struct IdempotentCall {
+    key: String,
+    method: String,
+    request_hash: Vec<u8>,
+    window: Duration,
+    tx: Arc<Mutex<Option<Transaction<'static, Postgres>>>>,
+}
+
+/// version of the content of a calendar feed, for conditional requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedVersion {
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+/// a resource mirrored into a caldav collection, with the state of its last sync
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct CalDavCollection {
+    pub id: i64,
+    pub resource_id: String,
+    pub url: String,
+    pub sync_token: String,
+    pub pushed_at: Option<DateTime<Utc>>,
+}
+
+/// changes of a caldav collection since a sync token
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncDelta {
+    /// token of the collection after the changes
+    pub token: String,
+    /// href and etag of the new or changed events
+    pub changed: Vec<(String, String)>,
+    /// hrefs of the removed events
+    pub removed: Vec<String>,
+}
+
+/// what a sync of a caldav collection did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// reservations written to the collection
+    pub pushed: usize,
+    /// events of reservations removed from the collection
+    pub removed: usize,
+    /// events of the caldav side reserved
+    pub reserved: usize,
+    /// events of the caldav side declined
+    pub declined: usize,
+    /// reservations cancelled because their event was cancelled, deleted or moved into a conflict
+    pub cancelled: usize,
+}
+
+/// the calls the sync makes to a caldav server, events are named by the hrefs the server lists
+#[async_trait]
+pub trait CalDav {
+    /// the changes of the collection since the sync token, every event for an empty token.
+    /// A token the server doesn't know anymore is an InvalidSyncToken
+    async fn sync_collection(&self, url: &str, token: &str) -> Result<SyncDelta, ReservationError>;
+    /// content and etag of an event, None if it is gone
+    async fn get_event(&self, href: &str) -> Result<Option<(Vec<u8>, String)>, ReservationError>;
+    /// create or replace an event, returns its etag if the server gives one
+    async fn put_event(
+        &self,
+        href: &str,
+        data: Vec<u8>,
+    ) -> Result<Option<String>, ReservationError>;
+    /// remove an event, a missing event is not an error
+    async fn delete_event(&self, href: &str) -> Result<(), ReservationError>;
 }
 
 #[async_trait]
 pub trait Rsvp {
     /// make a reservation
     async fn reserve(&self, rsvp: abi::Reservation) -> Result<abi::Reservation, ReservationError>;
-    /// change reservation status, if current status is pending, change it to confirmed
+    /// change reservation status, the transition must be allowed by the reservation lifecycle.
+    /// if expected_version is given, the reservation must be at that version
     async fn change_status(
         &self,
         id: abi::ReservationId,
+        status: abi::ReservationStatus,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, ReservationError>;
+    /// update the fields listed in mask with the values from the partial reservation
+    async fn update(
+        &self,
+        id: abi::ReservationId,
+        rsvp: abi::Reservation,
+        mask: FieldMask,
+        expected_version: Option<i64>,
     ) -> Result<abi::Reservation, ReservationError>;
-    /// update note
-    async fn update_note(
+    /// cancel reservation, the reservation is kept with cancelled status
+    async fn cancel(
         &self,
         id: abi::ReservationId,
-        note: String,
+        reason: String,
+        actor: String,
+        expected_version: Option<i64>,
     ) -> Result<abi::Reservation, ReservationError>;
-    /// delete reservation
-    async fn delete(&self, id: abi::ReservationId) -> Result<(), ReservationError>;
+    /// remove reservation physically
+    async fn purge(&self, id: abi::ReservationId) -> Result<abi::Reservation, ReservationError>;
     /// get reservation by id
     async fn get(&self, id: abi::ReservationId) -> Result<abi::Reservation, ReservationError>;
-    /// query reservations
+    /// get the change history of a reservation, oldest first
+    async fn get_history(
+        &self,
+        id: abi::ReservationId,
+    ) -> Result<Vec<abi::ReservationChange>, ReservationError>;
+    /// make several reservations in one transaction under a shared group id
+    async fn reserve_group(
+        &self,
+        rsvps: Vec<abi::Reservation>,
+    ) -> Result<Vec<abi::Reservation>, ReservationError>;
+    /// confirm all pending reservations of a group
+    async fn confirm_group(
+        &self,
+        group_id: &str,
+    ) -> Result<Vec<abi::Reservation>, ReservationError>;
+    /// cancel all reservations of a group
+    async fn cancel_group(
+        &self,
+        group_id: &str,
+        reason: String,
+        actor: String,
+    ) -> Result<Vec<abi::Reservation>, ReservationError>;
+    /// get all reservations of a group
+    async fn get_group(&self, group_id: &str) -> Result<Vec<abi::Reservation>, ReservationError>;
+    /// query a page of reservations, with the token of the next page if there is one
     async fn query(
         &self,
         query: abi::ReservationQuery,
-    ) -> Result<Vec<abi::Reservation>, ReservationError>;
+    ) -> Result<(Vec<abi::Reservation>, Option<String>), ReservationError>;
 
     /// query reservations order by reservation id
     async fn filter(
         &self,
         query: abi::ReservationFilter,
     ) -> Result<(Vec<abi::Reservation>, FilterPager), ReservationError>;
+
+    /// full text search over notes, tags and attribute values, best match first
+    async fn search(
+        &self,
+        search: abi::ReservationSearch,
+    ) -> Result<Vec<abi::SearchResult>, ReservationError>;
+
+    /// utilization of the resources in the window, per resource and bucket
+    async fn analytics(
+        &self,
+        query: abi::AnalyticsQuery,
+    ) -> Result<Vec<abi::AnalyticsBucket>, ReservationError>;
+
+    /// the reservations matching the query as a csv or parquet file, streamed in chunks
+    fn export(
+        &self,
+        query: abi::ReservationQuery,
+        format: abi::ExportFormat,
+    ) -> BoxStream<'static, Result<Vec<u8>, ReservationError>>;
+
+    /// check the reservations of a csv or json file, and import them unless it's a dry run
+    async fn import(
+        &self,
+        request: abi::ImportRequest,
+    ) -> Result<abi::ImportResponse, ReservationError>;
 }
@@ -0,0 +1,276 @@
+use std::{collections::VecDeque, time::Duration};
+
+use abi::{ListenRequest, ListenResponse, ReservationChange, ReservationError};
+use futures::stream::{self, BoxStream};
+use sqlx::{postgres::PgListener, FromRow, Row};
+use tokio::{sync::watch, time::timeout};
+
+use crate::ReservationManager;
+
+/// changes read from the change log at a time
+const BATCH_SIZE: i64 = 256;
+/// the change log is read again after this long without a notification, in case one was lost
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// the change log is read this often while a position is missing, the end of the transaction
+/// holding it notifies nothing if it rolls back
+const GAP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl ReservationManager {
+    /// the changes of the reservations passing the filters of the request, in the order of
+    /// their positions in the change log. Starts after the position in the request, or with
+    /// the next change if it is 0
+    pub fn listen(
+        &self,
+        request: ListenRequest,
+    ) -> BoxStream<'static, Result<ListenResponse, ReservationError>> {
+        let listener = Listener {
+            manager: self.clone(),
+            request,
+            last: None,
+            gap: None,
+            pending: VecDeque::new(),
+        };
+        Box::pin(stream::try_unfold(listener, |mut listener| async move {
+            let resp = listener.next().await?;
+            Ok(Some((resp, listener)))
+        }))
+    }
+
+    /// changed whenever the change log trigger notifies reservation_update
+    async fn notifications(&self) -> Result<watch::Receiver<()>, ReservationError> {
+        let rx = self
+            .notifications
+            .get_or_try_init(|| async {
+                let mut listener = PgListener::connect_with(&self.pool).await?;
+                listener.listen("reservation_update").await?;
+                let (tx, rx) = watch::channel(());
+                tokio::spawn(async move {
+                    loop {
+                        tokio::select! {
+                            // the manager and all its streams are gone
+                            _ = tx.closed() => break,
+                            notification = listener.recv() => match notification {
+                                Ok(_) => {
+                                    tx.send_replace(());
+                                }
+                                // the streams poll until the listener is connected again
+                                Err(_) => tokio::time::sleep(POLL_INTERVAL).await,
+                            },
+                        }
+                    }
+                });
+                Ok::<_, ReservationError>(rx)
+            })
+            .await?;
+        Ok(rx.clone())
+    }
+}
+
+struct Listener {
+    manager: ReservationManager,
+    request: ListenRequest,
+    /// position of the last change read, None before the first read
+    last: Option<i64>,
+    /// the next transaction id when the position after the last one was first seen missing.
+    /// The transaction which took the position had its id by then, since the change log trigger
+    /// runs after the write, so the position is rolled back once every transaction before this
+    /// id has ended
+    gap: Option<i64>,
+    /// changes read but not yet sent
+    pending: VecDeque<ListenResponse>,
+}
+
+impl Listener {
+    async fn next(&mut self) -> Result<ListenResponse, ReservationError> {
+        let mut notifications = self.manager.notifications().await?;
+        let mut last = match self.last {
+            Some(last) => last,
+            None if self.request.after > 0 => self.request.after,
+            None => {
+                sqlx::query_scalar("SELECT coalesce(max(seq), 0) FROM rsvp.reservation_changes")
+                    .fetch_one(&self.manager.pool)
+                    .await?
+            }
+        };
+        self.last = Some(last);
+
+        loop {
+            if let Some(resp) = self.pending.pop_front() {
+                return Ok(resp);
+            }
+            // a notification from now on wakes the wait below
+            notifications.borrow_and_update();
+            let more = self.read(&mut last).await?;
+            self.last = Some(last);
+            if more || !self.pending.is_empty() {
+                continue;
+            }
+            let wait = match self.gap {
+                Some(_) => GAP_POLL_INTERVAL,
+                None => POLL_INTERVAL,
+            };
+            let _ = timeout(wait, notifications.changed()).await;
+        }
+    }
+
+    /// read the changes after the last position, up to a missing one which may still commit.
+    /// Returns whether there may be more to read right away
+    async fn read(&mut self, last: &mut i64) -> Result<bool, ReservationError> {
+        // the transaction ids are of the snapshot the changes are read in, so a transaction
+        // which ended before it has its change in the rows if it committed
+        let rows = sqlx::query(
+            "SELECT seq, op, old, new, actor, reason, changed_at, pg_snapshot_xmin(pg_current_snapshot())::text::bigint AS running, pg_snapshot_xmax(pg_current_snapshot())::text::bigint AS next_xid FROM rsvp.reservation_changes WHERE seq > $1 ORDER BY seq LIMIT $2",
+        )
+        .bind(*last)
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.manager.pool)
+        .await?;
+        let full = rows.len() as i64 == BATCH_SIZE;
+
+        for row in rows {
+            let seq: i64 = row.try_get("seq")?;
+            if seq != *last + 1 {
+                // the oldest transaction still running
+                let running: i64 = row.try_get("running")?;
+                let next_xid = *self.gap.get_or_insert(row.try_get("next_xid")?);
+                if running < next_xid {
+                    return Ok(false);
+                }
+            }
+            self.gap = None;
+            *last = seq;
+
+            let change = ReservationChange::from_row(&row)?;
+            if self.matches(&change) {
+                self.pending.push_back(ListenResponse {
+                    op: change.op,
+                    reservation: change.after.or(change.before),
+                    seq,
+                });
+            }
+        }
+        Ok(full)
+    }
+
+    /// a change passes the filters if the reservation passes them before or after it
+    fn matches(&self, change: &ReservationChange) -> bool {
+        let rsvps = || change.before.iter().chain(&change.after);
+        let ListenRequest {
+            resource_ids,
+            user_ids,
+            ..
+        } = &self.request;
+        (resource_ids.is_empty() || rsvps().any(|r| resource_ids.contains(&r.resource_id)))
+            && (user_ids.is_empty() || rsvps().any(|r| user_ids.contains(&r.user_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{Reservation, ReservationUpdateType};
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::Rsvp;
+
+    fn rsvp(user_id: &str, resource_id: &str) -> Reservation {
+        Reservation::new_pending(
+            user_id,
+            resource_id,
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "note",
+        )
+    }
+
+    async fn next(
+        changes: &mut BoxStream<'static, Result<ListenResponse, ReservationError>>,
+    ) -> ListenResponse {
+        timeout(Duration::from_secs(10), changes.next())
+            .await
+            .expect("a change within 10s")
+            .unwrap()
+            .unwrap()
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn listen_should_send_filtered_changes_and_resume() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // changes made before the stream are not sent
+        let before = manager.reserve(rsvp("alice", "room-1")).await.unwrap();
+        manager.purge(before.id).await.unwrap();
+
+        let mut changes = manager.listen(ListenRequest {
+            resource_ids: vec!["room-1".into()],
+            ..Default::default()
+        });
+        let waiting = tokio::spawn(async move {
+            let first = next(&mut changes).await;
+            (first, next(&mut changes).await)
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        manager.reserve(rsvp("bob", "room-2")).await.unwrap();
+        let reserved = manager.reserve(rsvp("carol", "room-1")).await.unwrap();
+        manager.purge(reserved.id).await.unwrap();
+        let (created, deleted) = waiting.await.unwrap();
+        assert_eq!(created.op, ReservationUpdateType::Create as i32);
+        assert_eq!(created.reservation.as_ref(), Some(&reserved));
+        assert_eq!(deleted.op, ReservationUpdateType::Delete as i32);
+        assert_eq!(deleted.reservation.as_ref().unwrap().id, reserved.id);
+        assert!(deleted.seq > created.seq);
+
+        // the change of bob is before the position
+        let mut changes = manager.listen(ListenRequest {
+            user_ids: vec!["bob".into(), "carol".into()],
+            after: created.seq - 1,
+            ..Default::default()
+        });
+        assert_eq!(next(&mut changes).await, created);
+        assert_eq!(next(&mut changes).await, deleted);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn listen_should_wait_for_changes_committed_out_of_order() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut changes = manager.listen(ListenRequest::default());
+        // the stream starts at the end of the change log when it is first polled
+        assert!(timeout(Duration::from_millis(200), changes.next())
+            .await
+            .is_err());
+
+        let mut tx = migrated_pool.begin().await.unwrap();
+        sqlx::query(
+            "INSERT INTO rsvp.reservations (user_id, resource_id, timespan) VALUES ('alice', 'room-1', '[2022-12-25, 2022-12-28)')",
+        )
+        .execute(&mut tx)
+        .await
+        .unwrap();
+        let later = manager.reserve(rsvp("bob", "room-2")).await.unwrap();
+        // the change of bob waits for the uncommitted one before it, however long it takes
+        assert!(timeout(Duration::from_secs(3), changes.next())
+            .await
+            .is_err());
+
+        tx.commit().await.unwrap();
+        let first = next(&mut changes).await;
+        assert_eq!(first.reservation.unwrap().user_id, "alice");
+        let second = next(&mut changes).await;
+        assert_eq!(second.reservation.unwrap(), later);
+        assert_eq!(second.seq, first.seq + 1);
+
+        // a rolled back change is skipped once its transaction ended
+        let mut tx = migrated_pool.begin().await.unwrap();
+        sqlx::query(
+            "INSERT INTO rsvp.reservations (user_id, resource_id, timespan) VALUES ('carol', 'room-3', '[2022-12-25, 2022-12-28)')",
+        )
+        .execute(&mut tx)
+        .await
+        .unwrap();
+        tx.rollback().await.unwrap();
+        let last = manager.reserve(rsvp("dave", "room-4")).await.unwrap();
+        let resp = next(&mut changes).await;
+        assert_eq!(resp.reservation.unwrap(), last);
+        assert_eq!(resp.seq, second.seq + 2);
+    }
+}
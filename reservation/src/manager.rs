@@ -1,12 +1,24 @@
-use abi::{DbConfig, FilterPager, Reservation, ReservationError, ReservationId, Validator};
+use abi::{
+    attributes_to_json, convert_to_timestamp, normalize_tags, search_snippet, AnalyticsBucket,
+    DbConfig, FilterPager, PageToken, PageTokenKey, Reservation, ReservationError,
+    ReservationField, ReservationId, ReservationItemError, SearchResult, Validator,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use prost_types::FieldMask;
 use sqlx::{
     postgres::{types::PgRange, PgPoolOptions},
-    PgPool, Row,
+    types::{Json, Uuid},
+    Acquire, FromRow, PgExecutor, PgPool, Postgres, Row, Transaction,
 };
 
-use crate::{ReservationManager, Rsvp};
+use crate::{
+    export::export,
+    import::import,
+    query::{build_analytics, build_filter, build_query, build_search},
+    ChangeContext, ReservationManager, Rsvp,
+};
 
 #[async_trait]
 impl Rsvp for ReservationManager {
@@ -14,83 +26,126 @@ impl Rsvp for ReservationManager {
         &self,
         mut rsvp: abi::Reservation,
     ) -> Result<abi::Reservation, ReservationError> {
-        rsvp.validate()?;
-
-        // let start = convert_to_utc_time(rsvp.start.as_ref().unwrap().clone());
-        // let end = convert_to_utc_time(rsvp.end.as_ref().unwrap().clone());
+        let mut tx = self.begin(&self.context).await?;
+        insert_reservation(&mut tx, &mut rsvp, None).await?;
+        self.commit(tx).await?;
+        Ok(rsvp)
+    }
 
-        // let Range{ start, end} = rsvp.get_timestamp();
+    // validate the transition against the current status, then change it
+    async fn change_status(
+        &self,
+        id: ReservationId,
+        status: abi::ReservationStatus,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, ReservationError> {
+        id.validate()?;
+        let mut tx = self.begin(&self.context).await?;
+        let current = fetch_for_update(&mut tx, id).await?;
+        current.check_version(expected_version)?;
+
+        let from = abi::ReservationStatus::from_i32(current.status)
+            .unwrap_or(abi::ReservationStatus::Unknown);
+        from.change_to(status)?;
+        if from == status {
+            return Ok(current);
+        }
 
-        let status = abi::ReservationStatus::from_i32(rsvp.status)
-            .unwrap_or(abi::ReservationStatus::Pending);
+        let rsvp = sqlx::query_as(
+            "UPDATE rsvp.reservations SET status = $1::rsvp.reservation_status WHERE id = $2 RETURNING *",
+        )
+        .bind(status.to_string())
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await?;
 
-        // let timespan: PgRange<DateTime<Utc>> = (start..end).into();
+        self.commit(tx).await?;
+        Ok(rsvp)
+    }
 
-        let timespan: PgRange<DateTime<Utc>> = rsvp.get_timestamp();
+    async fn update(
+        &self,
+        id: ReservationId,
+        rsvp: abi::Reservation,
+        mask: FieldMask,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, ReservationError> {
+        id.validate()?;
+        let fields = ReservationField::parse_mask(&mask)?;
 
-        // generate a insert sql for the reservation
+        let mut tx = self.begin(&self.context).await?;
+        let mut current = fetch_for_update(&mut tx, id).await?;
+        current.check_version(expected_version)?;
 
-        let id = sqlx::query(
-            "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, status, note) VALUES ($1, $2, $3, $4::rsvp.reservation_status, $5) RETURNING id")
-            .bind(rsvp.user_id.clone())
-            .bind(rsvp.resource_id.clone())
-            .bind(timespan)
-            .bind(status.to_string())
-            .bind(rsvp.note.clone())
-            .fetch_one(&self.pool)
-            .await?
-            .get(0);
+        current.apply_update(&rsvp, &fields)?;
 
-        rsvp.id = id;
-        Ok(rsvp)
-    }
+        let status = abi::ReservationStatus::from_i32(current.status)
+            .unwrap_or(abi::ReservationStatus::Pending);
 
-    // if current status is pending, then change to confirmed
-    async fn change_status(&self, id: ReservationId) -> Result<abi::Reservation, ReservationError> {
-        // let id =
-        //     Uuid::parse_str(&id).map_err(|_| ReservationError::InvalidReservationId(id.clone()))?;
-        id.validate()?;
-        let status = sqlx::query_as(
-            "UPDATE rsvp.reservations SET status = 'confirmed' WHERE id = $1 RETURNING *",
+        let rsvp = sqlx::query_as(
+            "UPDATE rsvp.reservations SET user_id = $1, status = $2::rsvp.reservation_status, resource_id = $3, timespan = $4, note = $5, tags = $6, attributes = $7 WHERE id = $8 RETURNING *",
         )
+        .bind(&current.user_id)
+        .bind(status.to_string())
+        .bind(&current.resource_id)
+        .bind(current.get_timestamp())
+        .bind(&current.note)
+        .bind(Json(&current.tags))
+        .bind(Json(attributes_to_json(&current.attributes)?))
         .bind(id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut tx)
         .await?;
 
-        Ok(status)
+        self.commit(tx).await?;
+        Ok(rsvp)
     }
 
-    async fn update_note(
+    // 取消预约，保留记录并标记为cancelled
+    async fn cancel(
         &self,
         id: ReservationId,
-        note: String,
+        reason: String,
+        actor: String,
+        expected_version: Option<i64>,
     ) -> Result<abi::Reservation, ReservationError> {
-        // let id =
-        //     Uuid::parse_str(&id).map_err(|_| ReservationError::InvalidReservationId(id.clone()))?;
         id.validate()?;
-        let status =
-            sqlx::query_as("UPDATE rsvp.reservations SET note = $1 WHERE id = $2 RETURNING *")
-                .bind(note)
-                .bind(id)
-                .fetch_one(&self.pool)
-                .await?;
+        let context = self.context.merge(actor, reason);
+        let mut tx = self.begin(&context).await?;
+        let current = fetch_for_update(&mut tx, id).await?;
+        current.check_version(expected_version)?;
+
+        let from = abi::ReservationStatus::from_i32(current.status)
+            .unwrap_or(abi::ReservationStatus::Unknown);
+        from.transition_to(abi::ReservationStatus::Cancelled)?;
+        if from == abi::ReservationStatus::Cancelled {
+            return Ok(current);
+        }
 
-        Ok(status)
+        let rsvp = sqlx::query_as(
+            "UPDATE rsvp.reservations SET status = 'cancelled', cancel_reason = $1, cancelled_by = $2, cancelled_at = now() WHERE id = $3 RETURNING *",
+        )
+        .bind(str_to_option(&context.reason))
+        .bind(str_to_option(&context.actor))
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await?;
+
+        self.commit(tx).await?;
+        Ok(rsvp)
     }
 
-    // 根据ID删除预约
-    async fn delete(&self, id: ReservationId) -> Result<(), ReservationError> {
-        // let id = Uuid::parse_str(&id).map_err(|_| ReservationError::InvalidReservationId(id))?;
+    // 根据ID物理删除预约
+    async fn purge(&self, id: ReservationId) -> Result<abi::Reservation, ReservationError> {
         id.validate()?;
-        let result = sqlx::query("DELETE FROM rsvp.reservations WHERE id = $1")
+        let mut tx = self.begin(&self.context).await?;
+        let rsvp = sqlx::query_as("DELETE FROM rsvp.reservations WHERE id = $1 RETURNING *")
             .bind(id)
-            .execute(&self.pool)
-            .await?;
-        if result.rows_affected() == 0 {
-            return Err(ReservationError::ReservationNotFound(id));
-        } else {
-            return Ok(());
-        }
+            .fetch_optional(&mut tx)
+            .await?
+            .ok_or(ReservationError::ReservationNotFound(id))?;
+
+        self.commit(tx).await?;
+        Ok(rsvp)
     }
 
     // 查看某个Reservation
@@ -106,63 +161,191 @@ impl Rsvp for ReservationManager {
         Ok(status)
     }
 
-    async fn query(
+    async fn get_history(
         &self,
-        query: abi::ReservationQuery,
+        id: ReservationId,
+    ) -> Result<Vec<abi::ReservationChange>, ReservationError> {
+        id.validate()?;
+        let changes: Vec<abi::ReservationChange> = sqlx::query_as(
+            "SELECT op, old, new, actor, reason, changed_at FROM rsvp.reservation_changes WHERE reservation_id = $1 ORDER BY changed_at",
+        )
+        .bind(id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        if changes.is_empty() {
+            return Err(ReservationError::ReservationNotFound(id));
+        }
+
+        Ok(changes)
+    }
+
+    async fn reserve_group(
+        &self,
+        rsvps: Vec<abi::Reservation>,
     ) -> Result<Vec<abi::Reservation>, ReservationError> {
-        let user_id = str_to_option(&query.user_id);
-        let resource_id = str_to_option(&query.resource_id);
-        let range = query.get_timespan();
-        let status = abi::ReservationStatus::from_i32(query.status)
-            .unwrap_or(abi::ReservationStatus::Pending);
+        if rsvps.is_empty() {
+            return Err(ReservationError::EmptyReservationGroup);
+        }
+
+        let group_id = Uuid::new_v4();
+        let mut tx = self.begin(&self.context).await?;
+        let mut reserved = Vec::with_capacity(rsvps.len());
+        let mut failures = vec![];
+
+        for (index, mut rsvp) in rsvps.into_iter().enumerate() {
+            // a savepoint per item, so one failed item doesn't hide the errors of the others
+            let mut item_tx = tx.begin().await?;
+            match insert_reservation(&mut item_tx, &mut rsvp, Some(group_id)).await {
+                Ok(()) => {
+                    item_tx.commit().await?;
+                    reserved.push(rsvp);
+                }
+                Err(error) => {
+                    item_tx.rollback().await?;
+                    failures.push(ReservationItemError { index, error });
+                }
+            }
+        }
 
-        let rsvps = sqlx::query_as(
-            "SELECT * FROM rsvp.query($1, $2, $3, $4::rsvp.reservation_status, $5, $6, $7)",
+        if !failures.is_empty() {
+            tx.rollback().await?;
+            return Err(ReservationError::GroupReservationFailed(failures));
+        }
+
+        self.commit(tx).await?;
+        Ok(reserved)
+    }
+
+    async fn confirm_group(
+        &self,
+        group_id: &str,
+    ) -> Result<Vec<abi::Reservation>, ReservationError> {
+        let id = parse_group_id(group_id)?;
+        let mut tx = self.begin(&self.context).await?;
+        sqlx::query(
+            "UPDATE rsvp.reservations SET status = 'confirmed' WHERE group_id = $1 AND status = 'pending'",
         )
-        .bind(user_id)
-        .bind(resource_id)
-        .bind(range)
-        .bind(status.to_string())
-        .bind(query.page)
-        .bind(query.desc)
-        .bind(query.page_size)
-        .fetch_all(&self.pool)
+        .bind(id)
+        .execute(&mut tx)
         .await?;
 
+        let rsvps: Vec<Reservation> =
+            sqlx::query_as("SELECT * FROM rsvp.reservations WHERE group_id = $1 ORDER BY id")
+                .bind(id)
+                .fetch_all(&mut tx)
+                .await?;
+        if rsvps.is_empty() {
+            return Err(ReservationError::GroupNotFound(group_id.to_string()));
+        }
+
+        self.commit(tx).await?;
         Ok(rsvps)
     }
 
-    async fn filter(
+    async fn cancel_group(
         &self,
-        filter: abi::ReservationFilter,
-    ) -> Result<(Vec<abi::Reservation>, FilterPager), ReservationError> {
-        let user_id = str_to_option(&filter.user_id);
-        let resource_id = str_to_option(&filter.resource_id);
-        let status = abi::ReservationStatus::from_i32(filter.status)
-            .unwrap_or(abi::ReservationStatus::Pending);
+        group_id: &str,
+        reason: String,
+        actor: String,
+    ) -> Result<Vec<abi::Reservation>, ReservationError> {
+        let id = parse_group_id(group_id)?;
+        let context = self.context.merge(actor, reason);
+        let mut tx = self.begin(&context).await?;
+        let current: Vec<Reservation> = sqlx::query_as(
+            "SELECT * FROM rsvp.reservations WHERE group_id = $1 ORDER BY id FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_all(&mut tx)
+        .await?;
+        if current.is_empty() {
+            return Err(ReservationError::GroupNotFound(group_id.to_string()));
+        }
 
-        let page_size = if filter.page_size < 10 || filter.page_size > 100 {
-            10
-        } else {
-            filter.page_size
-        };
+        for rsvp in &current {
+            abi::ReservationStatus::from_i32(rsvp.status)
+                .unwrap_or(abi::ReservationStatus::Unknown)
+                .transition_to(abi::ReservationStatus::Cancelled)?;
+        }
 
-        let rsvps: Vec<Reservation> = sqlx::query_as(
-            "SELECT * FROM rsvp.filter($1, $2, $3::rsvp.reservation_status, $4, $5, $6::int)",
+        sqlx::query(
+            "UPDATE rsvp.reservations SET status = 'cancelled', cancel_reason = $1, cancelled_by = $2, cancelled_at = now() WHERE group_id = $3 AND status <> 'cancelled'",
         )
-        .bind(user_id)
-        .bind(resource_id)
-        .bind(status.to_string())
-        .bind(filter.cursor)
-        .bind(filter.desc)
-        .bind(page_size as i32)
-        .fetch_all(&self.pool)
+        .bind(str_to_option(&context.reason))
+        .bind(str_to_option(&context.actor))
+        .bind(id)
+        .execute(&mut tx)
         .await?;
 
+        let rsvps =
+            sqlx::query_as("SELECT * FROM rsvp.reservations WHERE group_id = $1 ORDER BY id")
+                .bind(id)
+                .fetch_all(&mut tx)
+                .await?;
+
+        self.commit(tx).await?;
+        Ok(rsvps)
+    }
+
+    async fn get_group(&self, group_id: &str) -> Result<Vec<abi::Reservation>, ReservationError> {
+        let id = parse_group_id(group_id)?;
+        let rsvps: Vec<Reservation> =
+            sqlx::query_as("SELECT * FROM rsvp.reservations WHERE group_id = $1 ORDER BY id")
+                .bind(id)
+                .fetch_all(&self.pool)
+                .await?;
+        if rsvps.is_empty() {
+            return Err(ReservationError::GroupNotFound(group_id.to_string()));
+        }
+
+        Ok(rsvps)
+    }
+
+    async fn query(
+        &self,
+        query: abi::ReservationQuery,
+    ) -> Result<(Vec<abi::Reservation>, Option<String>), ReservationError> {
+        let after = match query.page_token.as_str() {
+            "" => None,
+            token => {
+                let token = PageToken::decode(token, &self.page_token_key)?;
+                if token.sort != query.get_sort()? {
+                    return Err(ReservationError::InvalidPageToken);
+                }
+                Some(token)
+            }
+        };
+        let page_size = query.get_page_size()? as usize;
+
+        let mut rsvps: Vec<Reservation> = build_query(&query, after.as_ref())?
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let next_page_token = if rsvps.len() > page_size {
+            rsvps.truncate(page_size);
+            let token = PageToken::after(&rsvps[page_size - 1], query.get_sort()?);
+            Some(token.encode(&self.page_token_key))
+        } else {
+            None
+        };
+
+        Ok((rsvps, next_page_token))
+    }
+
+    async fn filter(
+        &self,
+        filter: abi::ReservationFilter,
+    ) -> Result<(Vec<abi::Reservation>, FilterPager), ReservationError> {
+        let rsvps: Vec<Reservation> = build_filter(&filter)?
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await?;
+        let page_size = filter.get_page_size()?;
+
         let has_prev = !rsvps.is_empty() && rsvps[0].id == filter.cursor;
         let start = if has_prev { 1 } else { 0 };
 
-        let has_next = rsvps.len() - start > filter.page_size as usize;
+        let has_next = rsvps.len() - start > page_size as usize;
         let end = if has_next {
             rsvps.len() - 1
         } else {
@@ -184,11 +367,143 @@ impl Rsvp for ReservationManager {
         };
         Ok((result, pager))
     }
+
+    async fn search(
+        &self,
+        search: abi::ReservationSearch,
+    ) -> Result<Vec<SearchResult>, ReservationError> {
+        let rows = build_search(&search)?.build().fetch_all(&self.pool).await?;
+        let terms = search.get_terms();
+
+        rows.iter()
+            .map(|row| {
+                let rsvp = Reservation::from_row(row)?;
+                Ok(SearchResult {
+                    rank: row.try_get("rank")?,
+                    snippet: search_snippet(&rsvp, &terms),
+                    reservation: Some(rsvp),
+                })
+            })
+            .collect()
+    }
+
+    async fn analytics(
+        &self,
+        query: abi::AnalyticsQuery,
+    ) -> Result<Vec<AnalyticsBucket>, ReservationError> {
+        let rows = build_analytics(&query)?
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| match e {
+                // the time zone is the only parameter postgres could reject
+                sqlx::Error::Database(e) if e.code().as_deref() == Some("22023") => {
+                    ReservationError::InvalidTimeZone(query.time_zone.clone())
+                }
+                e => e.into(),
+            })?;
+
+        rows.iter()
+            .map(|row| {
+                let reservations: i64 = row.try_get("reservations")?;
+                let cancelled: i64 = row.try_get("cancelled")?;
+                let total = reservations + cancelled;
+                Ok(AnalyticsBucket {
+                    resource_id: row.try_get("resource_id")?,
+                    start: Some(convert_to_timestamp(row.try_get("start")?)),
+                    end: Some(convert_to_timestamp(row.try_get("end")?)),
+                    booked_minutes: row.try_get("booked_minutes")?,
+                    utilization: row.try_get("utilization")?,
+                    reservations,
+                    cancelled,
+                    cancellation_rate: if total > 0 {
+                        cancelled as f64 / total as f64
+                    } else {
+                        0.0
+                    },
+                    peak_hour: row.try_get("peak_hour")?,
+                })
+            })
+            .collect()
+    }
+
+    fn export(
+        &self,
+        query: abi::ReservationQuery,
+        format: abi::ExportFormat,
+    ) -> BoxStream<'static, Result<Vec<u8>, ReservationError>> {
+        export(self.clone(), query, format)
+    }
+
+    async fn import(
+        &self,
+        request: abi::ImportRequest,
+    ) -> Result<abi::ImportResponse, ReservationError> {
+        import(self, request).await
+    }
 }
 
 impl ReservationManager {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            context: ChangeContext::default(),
+            page_token_key: PageTokenKey::random(),
+            notifications: Default::default(),
+            idempotent_call: None,
+        }
+    }
+
+    /// a manager sharing the same pool, which records the changes it makes with the given context
+    pub fn with_context(&self, context: ChangeContext) -> Self {
+        Self {
+            context,
+            ..self.clone()
+        }
+    }
+
+    /// sign page tokens with the given key, so the tokens are accepted by every manager with it
+    pub fn with_page_token_key(self, key: PageTokenKey) -> Self {
+        Self {
+            page_token_key: key,
+            ..self
+        }
+    }
+
+    /// begin a transaction, the change context is visible to the change log trigger
+    pub(crate) async fn begin(
+        &self,
+        context: &ChangeContext,
+    ) -> Result<Transaction<'static, Postgres>, ReservationError> {
+        // a call made with an idempotency key makes its changes in the transaction of the key
+        let mut tx = match self
+            .idempotent_call
+            .as_ref()
+            .and_then(|call| call.take_tx())
+        {
+            Some(tx) => tx,
+            None => self.pool.begin().await?,
+        };
+        sqlx::query(
+            "SELECT set_config('rsvp.actor', $1, true), set_config('rsvp.reason', $2, true)",
+        )
+        .bind(&context.actor)
+        .bind(&context.reason)
+        .execute(&mut tx)
+        .await?;
+        Ok(tx)
+    }
+
+    /// commit a transaction from begin. The transaction of an idempotency key is kept, it is
+    /// committed with the response of the call
+    pub(crate) async fn commit(
+        &self,
+        tx: Transaction<'static, Postgres>,
+    ) -> Result<(), ReservationError> {
+        match &self.idempotent_call {
+            Some(call) => call.put_tx(tx),
+            None => tx.commit().await?,
+        }
+        Ok(())
     }
 
     pub async fn from_config(config: &DbConfig) -> Result<Self, ReservationError> {
@@ -202,6 +517,90 @@ impl ReservationManager {
     }
 }
 
+/// validate the reservation and insert it, the generated id is written back to rsvp
+async fn insert_reservation(
+    executor: impl PgExecutor<'_>,
+    rsvp: &mut abi::Reservation,
+    group_id: Option<Uuid>,
+) -> Result<(), ReservationError> {
+    rsvp.validate()?;
+
+    let status = match abi::ReservationStatus::from_i32(rsvp.status) {
+        Some(abi::ReservationStatus::Unknown) | None => abi::ReservationStatus::Pending,
+        Some(status) if status.is_initial() => status,
+        Some(_) => return Err(ReservationError::InvalidStatus(rsvp.status)),
+    };
+    rsvp.status = status as i32;
+    insert_row(executor, rsvp, group_id).await
+}
+
+/// insert a validated reservation with its status as is
+pub(crate) async fn insert_row(
+    executor: impl PgExecutor<'_>,
+    rsvp: &mut abi::Reservation,
+    group_id: Option<Uuid>,
+) -> Result<(), ReservationError> {
+    let status =
+        abi::ReservationStatus::from_i32(rsvp.status).unwrap_or(abi::ReservationStatus::Pending);
+    rsvp.tags = normalize_tags(&rsvp.tags)?;
+
+    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timestamp();
+
+    // generate a insert sql for the reservation
+    let row = sqlx::query(
+        "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, status, note, group_id, tags, attributes) VALUES ($1, $2, $3, $4::rsvp.reservation_status, $5, $6, $7, $8) RETURNING id, version, created_at")
+        .bind(rsvp.user_id.clone())
+        .bind(rsvp.resource_id.clone())
+        .bind(timespan)
+        .bind(status.to_string())
+        .bind(rsvp.note.clone())
+        .bind(group_id)
+        .bind(Json(&rsvp.tags))
+        .bind(Json(attributes_to_json(&rsvp.attributes)?))
+        .fetch_one(executor)
+        .await?;
+
+    rsvp.id = row.get(0);
+    rsvp.version = row.get(1);
+    rsvp.created_at = Some(convert_to_timestamp(row.get(2)));
+    rsvp.group_id = group_id.map(|id| id.to_string()).unwrap_or_default();
+    Ok(())
+}
+
+impl ChangeContext {
+    /// explicit actor and reason take precedence over the ones of the context
+    fn merge(&self, actor: String, reason: String) -> Self {
+        Self {
+            actor: if actor.is_empty() {
+                self.actor.clone()
+            } else {
+                actor
+            },
+            reason: if reason.is_empty() {
+                self.reason.clone()
+            } else {
+                reason
+            },
+        }
+    }
+}
+
+/// fetch the reservation and lock the row until the transaction ends
+async fn fetch_for_update(
+    executor: impl PgExecutor<'_>,
+    id: ReservationId,
+) -> Result<Reservation, ReservationError> {
+    sqlx::query_as("SELECT * FROM rsvp.reservations WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or(ReservationError::ReservationNotFound(id))
+}
+
+fn parse_group_id(group_id: &str) -> Result<Uuid, ReservationError> {
+    Uuid::parse_str(group_id).map_err(|_| ReservationError::InvalidGroupId(group_id.to_string()))
+}
+
 fn str_to_option(s: &str) -> Option<String> {
     if s.is_empty() {
         None
@@ -213,9 +612,13 @@ fn str_to_option(s: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
 
+    use std::collections::HashMap;
+
     use abi::{
-        Reservation, ReservationConflictInfo, ReservationFilterBuilder, ReservationQueryBuilder,
-        ReservationStatus,
+        attribute_value, AnalyticsBucketSize, AnalyticsQuery, AttributeFilter, AttributeValue,
+        Reservation, ReservationConflictInfo, ReservationFilterBuilder, ReservationMatchMode,
+        ReservationQuery, ReservationQueryBuilder, ReservationSearch, ReservationSort,
+        ReservationSortField, ReservationStatus, ReservationUpdateType,
     };
     use chrono::FixedOffset;
     use prost_types::Timestamp;
@@ -266,11 +669,17 @@ mod tests {
             assert_eq!(info.new.rid, "ocean-view-room-731");
 
             assert_eq!(info.new.start.to_rfc3339(), "2022-12-25T19:00:00+00:00");
-            assert_eq!(info.new.end.to_rfc3339(), "2022-12-27T19:00:00+00:00");
+            assert_eq!(
+                info.new.end.unwrap().to_rfc3339(),
+                "2022-12-27T19:00:00+00:00"
+            );
 
             assert_eq!(info.old.rid, "ocean-view-room-731");
             assert_eq!(info.old.start.to_rfc3339(), "2022-12-24T19:00:00+00:00");
-            assert_eq!(info.old.end.to_rfc3339(), "2022-12-28T19:00:00+00:00");
+            assert_eq!(
+                info.old.end.unwrap().to_rfc3339(),
+                "2022-12-28T19:00:00+00:00"
+            );
         } else {
             println!("{err:?}");
             panic!("expect conflict reservation error");
@@ -291,7 +700,10 @@ mod tests {
 
         assert_eq!(rsvp.id, 1);
 
-        let rsvp2 = manager.change_status(rsvp.id).await.unwrap();
+        let rsvp2 = manager
+            .change_status(rsvp.id, ReservationStatus::Confirmed, None)
+            .await
+            .unwrap();
 
         assert_eq!(rsvp2.status, ReservationStatus::Confirmed as i32)
     }
@@ -311,11 +723,17 @@ mod tests {
 
         assert_eq!(rsvp.id, 1);
 
-        let rsvp = manager.change_status(rsvp.id).await.unwrap();
+        let rsvp = manager
+            .change_status(rsvp.id, ReservationStatus::Confirmed, None)
+            .await
+            .unwrap();
 
         assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
 
-        let rsvp = manager.change_status(rsvp.id).await.unwrap();
+        let rsvp = manager
+            .change_status(rsvp.id, ReservationStatus::Confirmed, None)
+            .await
+            .unwrap();
 
         assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
 
@@ -323,37 +741,34 @@ mod tests {
     }
 
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
-    async fn reserve_change_note_should_work() {
+    async fn reserve_change_status_illegal_transition_should_reject() {
         let manager = ReservationManager::new(migrated_pool.clone());
-        let rsvp = Reservation::new_pending(
+        let mut rsvp = Reservation::new_pending(
             "first_id",
             "ocean-view-room-731",
             "2022-12-24T12:00:00-0700".parse().unwrap(),
             "2022-12-28T12:00:00-0700".parse().unwrap(),
             "hello",
         );
-
+        rsvp.status = ReservationStatus::Blocked as i32;
         let rsvp = manager.reserve(rsvp).await.unwrap();
 
-        assert_eq!(rsvp.id, 1);
-
-        let rsvp = manager
-            .update_note(
-                rsvp.id,
-                "I'll arrive at 3pm. Please help to upgrade to executive room if possible"
-                    .to_string(),
-            )
+        let err = manager
+            .change_status(rsvp.id, ReservationStatus::Confirmed, None)
             .await
-            .unwrap();
-
+            .unwrap_err();
         assert_eq!(
-            rsvp.note,
-            "I'll arrive at 3pm. Please help to upgrade to executive room if possible"
-        )
+            err,
+            ReservationError::InvalidTransition(
+                ReservationStatus::Blocked,
+                ReservationStatus::Confirmed
+            )
+        );
+        assert_eq!(manager.get(rsvp.id).await.unwrap(), rsvp);
     }
 
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
-    async fn reserve_delete_should_work() {
+    async fn reserve_change_status_should_record_transitions() {
         let manager = ReservationManager::new(migrated_pool.clone());
         let rsvp = Reservation::new_pending(
             "first_id",
@@ -362,16 +777,39 @@ mod tests {
             "2022-12-28T12:00:00-0700".parse().unwrap(),
             "hello",
         );
-
         let rsvp = manager.reserve(rsvp).await.unwrap();
 
-        assert_eq!(rsvp.id, 1);
+        for status in [
+            ReservationStatus::Confirmed,
+            ReservationStatus::CheckedIn,
+            ReservationStatus::Completed,
+        ] {
+            let rsvp = manager.change_status(rsvp.id, status, None).await.unwrap();
+            assert_eq!(rsvp.status, status as i32);
+        }
+
+        let changes: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT from_status::text, to_status::text FROM rsvp.reservation_changes WHERE reservation_id = $1 ORDER BY reservation_changes.to_status",
+        )
+        .bind(rsvp.id.to_string())
+        .fetch_all(&migrated_pool)
+        .await
+        .unwrap();
 
-        assert!(manager.delete(rsvp.id).await.is_ok());
+        let pair = |from: Option<&str>, to: &str| (from.map(String::from), Some(to.to_string()));
+        assert_eq!(
+            changes,
+            vec![
+                pair(None, "pending"),
+                pair(Some("pending"), "confirmed"),
+                pair(Some("confirmed"), "checked_in"),
+                pair(Some("checked_in"), "completed"),
+            ]
+        );
     }
 
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
-    async fn reserve_delete_not_exists_should_work() {
+    async fn reserve_update_note_should_work() {
         let manager = ReservationManager::new(migrated_pool.clone());
         let rsvp = Reservation::new_pending(
             "first_id",
@@ -384,19 +822,26 @@ mod tests {
         let rsvp = manager.reserve(rsvp).await.unwrap();
 
         assert_eq!(rsvp.id, 1);
-        let id = rsvp.id;
 
-        assert!(manager.delete(id).await.is_ok());
-        let err = manager.delete(id).await.err().unwrap();
-        assert!(manager.delete(id).await.is_err());
+        let partial = Reservation {
+            note: "I'll arrive at 3pm. Please help to upgrade to executive room if possible"
+                .to_string(),
+            ..Default::default()
+        };
+        let rsvp = manager
+            .update(rsvp.id, partial, field_mask(&["note"]), None)
+            .await
+            .unwrap();
+
         assert_eq!(
-            err.to_string(),
-            ReservationError::ReservationNotFound(id).to_string()
+            rsvp.note,
+            "I'll arrive at 3pm. Please help to upgrade to executive room if possible"
         );
+        assert_eq!(rsvp.user_id, "first_id");
     }
 
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
-    async fn reserve_get_reservation_should_work() {
+    async fn reserve_update_multiple_fields_should_work() {
         let manager = ReservationManager::new(migrated_pool.clone());
         let rsvp = Reservation::new_pending(
             "first_id",
@@ -405,19 +850,35 @@ mod tests {
             "2022-12-28T12:00:00-0700".parse().unwrap(),
             "hello",
         );
-
         let rsvp = manager.reserve(rsvp).await.unwrap();
-        let id = rsvp.id;
-
-        assert_eq!(id, 1);
 
-        let rsvp2 = manager.get(id).await.unwrap();
+        let partial = Reservation::new_pending(
+            "second_id",
+            "ignored",
+            "2022-12-25T12:00:00-0700".parse().unwrap(),
+            "2022-12-26T12:00:00-0700".parse().unwrap(),
+            "ignored",
+        );
+        let updated = manager
+            .update(
+                rsvp.id,
+                partial.clone(),
+                field_mask(&["user_id", "start", "end"]),
+                None,
+            )
+            .await
+            .unwrap();
 
-        assert_eq!(rsvp, rsvp2);
+        assert_eq!(updated.user_id, "second_id");
+        assert_eq!(updated.resource_id, "ocean-view-room-731");
+        assert_eq!(updated.start, partial.start);
+        assert_eq!(updated.end, partial.end);
+        assert_eq!(updated.note, "hello");
+        assert_eq!(manager.get(rsvp.id).await.unwrap(), updated);
     }
 
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
-    async fn reserve_get_not_exist_id_should_err() {
+    async fn reserve_stale_version_should_reject() {
         let manager = ReservationManager::new(migrated_pool.clone());
         let rsvp = Reservation::new_pending(
             "first_id",
@@ -426,29 +887,389 @@ mod tests {
             "2022-12-28T12:00:00-0700".parse().unwrap(),
             "hello",
         );
-
         let rsvp = manager.reserve(rsvp).await.unwrap();
-        let id = rsvp.id;
+        assert_eq!(rsvp.version, 1);
 
-        assert_eq!(id, 1);
-        let result: ReservationId = id;
+        let partial = Reservation {
+            note: "first admin".into(),
+            ..Default::default()
+        };
+        let updated = manager
+            .update(rsvp.id, partial, field_mask(&["note"]), Some(1))
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
 
-        let err = manager.get(result + 1).await.err().unwrap();
-        println!("{result:?}");
+        // the second admin still edits version 1
+        let partial = Reservation {
+            note: "second admin".into(),
+            ..Default::default()
+        };
+        let err = manager
+            .update(rsvp.id, partial, field_mask(&["note"]), Some(1))
+            .await
+            .unwrap_err();
+        let mismatch = ReservationError::VersionMismatch {
+            id: rsvp.id,
+            expected: 1,
+            actual: 2,
+        };
+        assert_eq!(err, mismatch);
+
+        let err = manager
+            .change_status(rsvp.id, ReservationStatus::Confirmed, Some(1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, mismatch);
+        let confirmed = manager
+            .change_status(rsvp.id, ReservationStatus::Confirmed, Some(2))
+            .await
+            .unwrap();
+        assert_eq!(confirmed.version, 3);
+
+        let err = manager
+            .cancel(rsvp.id, String::new(), String::new(), Some(2))
+            .await
+            .unwrap_err();
         assert_eq!(
-            err.to_string(),
-            ReservationError::ReservationNotFound(2).to_string()
+            err,
+            ReservationError::VersionMismatch {
+                id: rsvp.id,
+                expected: 2,
+                actual: 3,
+            }
         );
+
+        // a change which changes nothing keeps the version
+        let same = manager
+            .update(rsvp.id, updated.clone(), field_mask(&["note"]), None)
+            .await
+            .unwrap();
+        assert_eq!(same.version, 3);
+
+        let err = manager
+            .update(rsvp.id, updated, field_mask(&["version"]), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::ImmutableField("version".into()));
     }
 
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
-    async fn reservation_query_should_work() {
+    async fn reserve_update_invalid_mask_should_reject() {
         let manager = ReservationManager::new(migrated_pool.clone());
         let rsvp = Reservation::new_pending(
-            "hyx",
-            "room-421",
-            "2022-11-20T12:00:00-0700".parse().unwrap(),
-            "2022-11-22T12:00:00-0700".parse().unwrap(),
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        let err = manager
+            .update(rsvp.id, rsvp.clone(), field_mask(&["id"]), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::ImmutableField("id".into()));
+
+        let partial = Reservation {
+            start: rsvp.end.clone(),
+            ..Default::default()
+        };
+        let err = manager
+            .update(rsvp.id, partial, field_mask(&["start", "note"]), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::InvalidTimespan);
+
+        // cancelling through update would skip the cancel record
+        let partial = Reservation {
+            status: ReservationStatus::Cancelled as i32,
+            ..Default::default()
+        };
+        let err = manager
+            .update(rsvp.id, partial, field_mask(&["status"]), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::CancelWithStatus);
+        let err = manager
+            .change_status(rsvp.id, ReservationStatus::Cancelled, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::CancelWithStatus);
+        assert_eq!(manager.get(rsvp.id).await.unwrap(), rsvp);
+
+        let err = manager
+            .update(rsvp.id + 1, rsvp, field_mask(&["note"]), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::ReservationNotFound(2));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_cancel_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        assert_eq!(rsvp.id, 1);
+
+        let cancelled = manager
+            .cancel(rsvp.id, "plan changed".into(), "admin".into(), None)
+            .await
+            .unwrap();
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+        assert_eq!(cancelled.cancel_reason, "plan changed");
+        assert_eq!(cancelled.cancelled_by, "admin");
+        assert!(cancelled.cancelled_at.is_some());
+
+        // the reservation is kept, and cancelling it again does nothing
+        assert_eq!(manager.get(rsvp.id).await.unwrap(), cancelled);
+        let again = manager
+            .cancel(rsvp.id, "again".into(), "someone".into(), None)
+            .await
+            .unwrap();
+        assert_eq!(again, cancelled);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_cancelled_slot_should_be_reservable() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let rsvp1 = manager.reserve(rsvp.clone()).await.unwrap();
+        manager
+            .cancel(rsvp1.id, String::new(), String::new(), None)
+            .await
+            .unwrap();
+
+        let rsvp2 = manager.reserve(rsvp).await.unwrap();
+        assert_eq!(rsvp2.id, 2);
+
+        // cancelled reservations are hidden unless asked for
+        let query = ReservationQueryBuilder::default()
+            .resource_id("ocean-view-room-731")
+            .start("2022-12-01T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-31T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .status(ReservationStatus::Pending as i32)
+            .build()
+            .unwrap();
+        let rsvps = manager.query(query.clone()).await.unwrap().0;
+        assert_eq!(rsvps, vec![rsvp2]);
+
+        let query = ReservationQuery {
+            status: ReservationStatus::Cancelled as i32,
+            ..query
+        };
+        let rsvps = manager.query(query).await.unwrap().0;
+        assert_eq!(rsvps.len(), 1);
+        assert_eq!(rsvps[0].id, rsvp1.id);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_cancel_not_exists_should_err() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let err = manager
+            .cancel(1, String::new(), String::new(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::ReservationNotFound(1));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_purge_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let id = rsvp.id;
+
+        let purged = manager.purge(id).await.unwrap();
+        assert_eq!(purged, rsvp);
+        let err = manager.purge(id).await.err().unwrap();
+        assert!(manager.get(id).await.is_err());
+        assert_eq!(
+            err.to_string(),
+            ReservationError::ReservationNotFound(id).to_string()
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_get_history_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let created = manager.reserve(rsvp).await.unwrap();
+        let confirmed = manager
+            .change_status(created.id, ReservationStatus::Confirmed, None)
+            .await
+            .unwrap();
+        let cancelled = manager
+            .cancel(created.id, "plan changed".into(), "admin".into(), None)
+            .await
+            .unwrap();
+        manager.purge(created.id).await.unwrap();
+
+        let changes = manager.get_history(created.id).await.unwrap();
+        let ops: Vec<i32> = changes.iter().map(|c| c.op).collect();
+        assert_eq!(
+            ops,
+            vec![
+                ReservationUpdateType::Create as i32,
+                ReservationUpdateType::Update as i32,
+                ReservationUpdateType::Update as i32,
+                ReservationUpdateType::Delete as i32,
+            ]
+        );
+
+        assert_eq!(changes[0].before, None);
+        assert_eq!(changes[0].after.as_ref(), Some(&created));
+        assert_eq!(changes[1].before.as_ref(), Some(&created));
+        assert_eq!(changes[1].after.as_ref(), Some(&confirmed));
+        assert_eq!(changes[1].changed_fields, vec!["status"]);
+        assert_eq!(changes[2].after.as_ref(), Some(&cancelled));
+        assert_eq!(
+            changes[2].changed_fields,
+            vec!["status", "cancel_reason", "cancelled_by", "cancelled_at"]
+        );
+        assert_eq!(changes[3].before.as_ref(), Some(&cancelled));
+        assert_eq!(changes[3].after, None);
+        assert!(changes.iter().all(|c| c.at.is_some()));
+
+        let err = manager.get_history(created.id + 1).await.unwrap_err();
+        assert_eq!(err, ReservationError::ReservationNotFound(created.id + 1));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_history_should_record_every_change_with_context() {
+        let manager = ReservationManager::new(migrated_pool.clone()).with_context(ChangeContext {
+            actor: "front-desk".into(),
+            reason: "guest request".into(),
+        });
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let created = manager.reserve(rsvp).await.unwrap();
+        let partial = Reservation {
+            note: "late check-in".into(),
+            ..Default::default()
+        };
+        let mask = FieldMask {
+            paths: vec!["note".into()],
+        };
+        manager
+            .update(created.id, partial.clone(), mask.clone(), None)
+            .await
+            .unwrap();
+        // an update without any actual change is not recorded
+        manager
+            .update(created.id, partial, mask, None)
+            .await
+            .unwrap();
+
+        let changes = manager.get_history(created.id).await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].changed_fields, vec!["note"]);
+        assert!(changes
+            .iter()
+            .all(|c| c.actor == "front-desk" && c.reason == "guest request"));
+
+        // explicit cancel reason and actor win over the context
+        manager
+            .cancel(created.id, "double booked".into(), "admin".into(), None)
+            .await
+            .unwrap();
+        let changes = manager.get_history(created.id).await.unwrap();
+        let last = changes.last().unwrap();
+        assert_eq!(last.actor, "admin");
+        assert_eq!(last.reason, "double booked");
+        assert_eq!(last.after.as_ref().unwrap().cancelled_by, "admin");
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_get_reservation_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let id = rsvp.id;
+
+        assert_eq!(id, 1);
+
+        let rsvp2 = manager.get(id).await.unwrap();
+
+        assert_eq!(rsvp, rsvp2);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_get_not_exist_id_should_err() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "first_id",
+            "ocean-view-room-731",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let id = rsvp.id;
+
+        assert_eq!(id, 1);
+        let result: ReservationId = id;
+
+        let err = manager.get(result + 1).await.err().unwrap();
+        println!("{result:?}");
+        assert_eq!(
+            err.to_string(),
+            ReservationError::ReservationNotFound(2).to_string()
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_query_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-11-20T12:00:00-0700".parse().unwrap(),
+            "2022-11-22T12:00:00-0700".parse().unwrap(),
             "hello",
         );
 
@@ -469,11 +1290,324 @@ mod tests {
         println!("{query:?}");
         println!("{rsvp:?}");
 
-        let result = manager.query(query).await.unwrap();
+        let result = manager.query(query).await.unwrap().0;
         assert_eq!(result.len(), 1);
         assert_eq!(result[0], rsvp);
     }
 
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_query_match_mode_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // straddles midnight
+        let night = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-12-01T22:00:00+0000".parse().unwrap(),
+            "2022-12-02T02:00:00+0000".parse().unwrap(),
+            "night",
+        );
+        // ends right when the day starts
+        let evening = Reservation::new_pending(
+            "hyx",
+            "room-422",
+            "2022-12-01T20:00:00+0000".parse().unwrap(),
+            "2022-12-02T00:00:00+0000".parse().unwrap(),
+            "evening",
+        );
+        // starts right when the day starts
+        let morning = Reservation::new_pending(
+            "hyx",
+            "room-423",
+            "2022-12-02T00:00:00+0000".parse().unwrap(),
+            "2022-12-02T08:00:00+0000".parse().unwrap(),
+            "morning",
+        );
+        let night = manager.reserve(night).await.unwrap();
+        let evening = manager.reserve(evening).await.unwrap();
+        let morning = manager.reserve(morning).await.unwrap();
+
+        let query = |start: &str, end: &str, mode: ReservationMatchMode| {
+            ReservationQueryBuilder::default()
+                .user_id("hyx")
+                .start(start.parse::<Timestamp>().unwrap())
+                .end(end.parse::<Timestamp>().unwrap())
+                .status(ReservationStatus::Pending as i32)
+                .match_mode(mode as i32)
+                .desc(false)
+                .build()
+                .unwrap()
+        };
+        let ids = |rsvps: Vec<Reservation>| rsvps.iter().map(|r| r.id).collect::<Vec<_>>();
+
+        let day = ("2022-12-02T00:00:00Z", "2022-12-03T00:00:00Z");
+        let result = manager
+            .query(query(day.0, day.1, ReservationMatchMode::Within))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(ids(result), vec![morning.id]);
+
+        let result = manager
+            .query(query(day.0, day.1, ReservationMatchMode::Overlaps))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(ids(result), vec![night.id, morning.id]);
+
+        let result = manager
+            .query(query(day.0, day.1, ReservationMatchMode::StartsIn))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(ids(result), vec![morning.id]);
+
+        let result = manager
+            .query(query(
+                "2022-12-01T00:00:00Z",
+                "2022-12-02T00:00:00Z",
+                ReservationMatchMode::StartsIn,
+            ))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(ids(result), vec![evening.id, night.id]);
+
+        let result = manager
+            .query(query(
+                "2022-12-01T23:00:00Z",
+                "2022-12-02T00:00:00Z",
+                ReservationMatchMode::Contains,
+            ))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(ids(result), vec![evening.id, night.id]);
+
+        let result = manager
+            .query(query(day.0, day.1, ReservationMatchMode::Contains))
+            .await
+            .unwrap()
+            .0;
+        assert!(result.is_empty());
+
+        let mut invalid = query(day.0, day.1, ReservationMatchMode::Within);
+        invalid.match_mode = 42;
+        let err = manager.query(invalid).await.unwrap_err();
+        assert_eq!(err, ReservationError::InvalidMatchMode(42));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_open_ended_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = Reservation {
+            end: None,
+            ..Reservation::new_pending(
+                "hyx",
+                "room-421",
+                "2022-11-20T12:00:00-0700".parse().unwrap(),
+                "2022-11-22T12:00:00-0700".parse().unwrap(),
+                "maintenance",
+            )
+        };
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        assert_eq!(manager.get(rsvp.id).await.unwrap(), rsvp);
+
+        // the block conflicts with anything after its start
+        let later = Reservation::new_pending(
+            "tyr",
+            "room-421",
+            "2030-01-01T12:00:00-0700".parse().unwrap(),
+            "2030-01-02T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+        let err = manager.reserve(later).await.unwrap_err();
+        if let ReservationError::ConflictReservation(ReservationConflictInfo::Parsed(info)) = err {
+            assert_eq!(info.old.end, None);
+        } else {
+            panic!("expect conflict reservation error, got {err:?}");
+        }
+
+        // an unbounded query window contains the open ended reservation
+        let query = ReservationQueryBuilder::default()
+            .resource_id("room-421")
+            .status(ReservationStatus::Pending as i32)
+            .build()
+            .unwrap();
+        let result = manager.query(query).await.unwrap().0;
+        assert_eq!(result, vec![rsvp.clone()]);
+
+        let query = ReservationQueryBuilder::default()
+            .resource_id("room-421")
+            .start("2022-11-01T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .status(ReservationStatus::Pending as i32)
+            .build()
+            .unwrap();
+        let result = manager.query(query).await.unwrap().0;
+        assert_eq!(result, vec![rsvp.clone()]);
+
+        let query = ReservationQueryBuilder::default()
+            .start("2022-11-30T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-11-01T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .build()
+            .unwrap();
+        let err = manager.query(query).await.unwrap_err();
+        assert_eq!(err, ReservationError::InvalidTimespan);
+
+        // closing the block by giving it an end
+        let partial = Reservation {
+            end: Some("2022-12-01T12:00:00-0700".parse::<Timestamp>().unwrap()),
+            ..Default::default()
+        };
+        let closed = manager
+            .update(rsvp.id, partial, field_mask(&["end"]), None)
+            .await
+            .unwrap();
+        assert!(closed.end.is_some());
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_query_page_token_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // rooms 0 and 1 share start times, so pages are ordered by id within a start
+        let mut ids = vec![];
+        for i in 0..5 {
+            let rsvp = Reservation::new_pending(
+                "hyx",
+                format!("room-{}", i % 2),
+                format!("2022-12-0{}T12:00:00Z", i / 2 + 1).parse().unwrap(),
+                format!("2022-12-0{}T13:00:00Z", i / 2 + 1).parse().unwrap(),
+                "hello",
+            );
+            ids.push(manager.reserve(rsvp).await.unwrap().id);
+        }
+
+        let read_all = |desc: bool| {
+            let manager = manager.clone();
+            async move {
+                let mut query = ReservationQueryBuilder::default()
+                    .page_size(2)
+                    .desc(desc)
+                    .build()
+                    .unwrap();
+                let mut seen = vec![];
+                loop {
+                    let (rsvps, token) = manager.query(query.clone()).await.unwrap();
+                    seen.extend(rsvps.iter().map(|r| r.id));
+                    match token {
+                        Some(token) => query.page_token = token,
+                        None => break,
+                    }
+                }
+                seen
+            }
+        };
+        assert_eq!(read_all(false).await, ids);
+        assert_eq!(
+            read_all(true).await,
+            vec![ids[4], ids[3], ids[2], ids[1], ids[0]]
+        );
+
+        // a reservation made before the current page doesn't shift the next one
+        let query = ReservationQueryBuilder::default()
+            .page_size(2)
+            .build()
+            .unwrap();
+        let (_, token) = manager.query(query.clone()).await.unwrap();
+        let earlier = Reservation::new_pending(
+            "hyx",
+            "room-2",
+            "2022-11-30T12:00:00Z".parse().unwrap(),
+            "2022-11-30T13:00:00Z".parse().unwrap(),
+            "hello",
+        );
+        manager.reserve(earlier).await.unwrap();
+        let next = ReservationQuery {
+            page_token: token.clone().unwrap(),
+            ..query.clone()
+        };
+        let (rsvps, _) = manager.query(next).await.unwrap();
+        assert_eq!(rsvps.iter().map(|r| r.id).collect::<Vec<_>>(), ids[2..4]);
+
+        // the token only works for its own sort direction and manager
+        let reversed = ReservationQuery {
+            page_token: token.clone().unwrap(),
+            desc: true,
+            ..query.clone()
+        };
+        let err = manager.query(reversed).await.unwrap_err();
+        assert_eq!(err, ReservationError::InvalidPageToken);
+
+        let other = ReservationManager::new(migrated_pool.clone());
+        let next = ReservationQuery {
+            page_token: token.unwrap(),
+            ..query
+        };
+        let err = other.query(next).await.unwrap_err();
+        assert_eq!(err, ReservationError::InvalidPageToken);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_query_sort_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut rsvps = vec![];
+        for i in 0..7 {
+            let rsvp = Reservation {
+                user_id: ["hyx", "tyr"][i % 2].to_string(),
+                status: [ReservationStatus::Pending, ReservationStatus::Confirmed][i % 3 / 2]
+                    as i32,
+                resource_id: format!("room-{}", i),
+                start: Some("2022-12-01T12:00:00Z".parse().unwrap()),
+                // every third reservation is open ended
+                end: match i % 3 {
+                    0 => None,
+                    n => Some(format!("2022-12-0{}T12:00:00Z", n + 1).parse().unwrap()),
+                },
+                ..Default::default()
+            };
+            rsvps.push(manager.reserve(rsvp).await.unwrap());
+        }
+
+        let sort = vec![
+            ReservationSort::new(ReservationSortField::Status, false),
+            ReservationSort::new(ReservationSortField::End, true),
+            ReservationSort::new(ReservationSortField::UserId, false),
+        ];
+        let mut query = ReservationQueryBuilder::default()
+            .sort(sort)
+            .page_size(2)
+            .build()
+            .unwrap();
+        let mut paged = vec![];
+        loop {
+            let (page, token) = manager.query(query.clone()).await.unwrap();
+            paged.extend(page.into_iter().map(|r| r.id));
+            match token {
+                Some(token) => query.page_token = token,
+                None => break,
+            }
+        }
+
+        // pending before confirmed, open ended first in descending end, then by user and id
+        let key = |r: &Reservation| {
+            (
+                r.status,
+                std::cmp::Reverse(r.end.clone().map(|ts| ts.seconds).unwrap_or(i64::MAX)),
+                r.user_id.clone(),
+                r.id,
+            )
+        };
+        rsvps.sort_by_key(key);
+        assert_eq!(paged, rsvps.iter().map(|r| r.id).collect::<Vec<_>>());
+
+        // a token is bound to its sort
+        let other = ReservationQuery {
+            sort: vec![ReservationSort::new(ReservationSortField::CreatedAt, false)],
+            ..query
+        };
+        let err = manager.query(other).await.unwrap_err();
+        assert_eq!(err, ReservationError::InvalidPageToken);
+    }
+
     #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
     async fn reservation_filter_should_work() {
         let manager = ReservationManager::new(migrated_pool.clone());
@@ -512,4 +1646,481 @@ mod tests {
         assert_eq!(pager.prev, -1);
         assert_eq!(pager.next, -1);
     }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_filter_multiple_values_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut rsvps = vec![];
+        for (user, room) in [
+            ("hyx", "room-a"),
+            ("tyr", "room-b"),
+            ("hyx", "room-c"),
+            ("hyx", "room-d"),
+        ] {
+            let rsvp = Reservation::new_pending(
+                user,
+                room,
+                "2022-11-20T12:00:00-0700".parse().unwrap(),
+                "2022-11-22T12:00:00-0700".parse().unwrap(),
+                "hello",
+            );
+            rsvps.push(manager.reserve(rsvp).await.unwrap());
+        }
+        let confirmed = manager
+            .change_status(rsvps[1].id, ReservationStatus::Confirmed, None)
+            .await
+            .unwrap();
+        let cancelled = manager
+            .cancel(rsvps[2].id, String::new(), String::new(), None)
+            .await
+            .unwrap();
+
+        // confirmed or pending across room a, b and c
+        let filter = ReservationFilterBuilder::default()
+            .resource_ids(vec!["room-a".into(), "room-b".into(), "room-c".into()])
+            .statuses(vec![
+                ReservationStatus::Confirmed as i32,
+                ReservationStatus::Pending as i32,
+            ])
+            .build()
+            .unwrap();
+        let (result, _) = manager.filter(filter).await.unwrap();
+        assert_eq!(result, vec![rsvps[0].clone(), confirmed.clone()]);
+
+        // UNKNOWN matches all statuses, cancelled ones still need include_cancelled
+        let query = ReservationQueryBuilder::default()
+            .resource_id("room-a")
+            .resource_ids(vec!["room-b".into(), "room-c".into()])
+            .status(ReservationStatus::Unknown as i32)
+            .include_cancelled(true)
+            .desc(false)
+            .build()
+            .unwrap();
+        let result = manager.query(query).await.unwrap().0;
+        let ids: Vec<i64> = result.iter().map(|r| r.id).collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&cancelled.id));
+
+        let filter = ReservationFilterBuilder::default()
+            .user_ids(vec!["hyx".into()])
+            .build()
+            .unwrap();
+        let (result, _) = manager.filter(filter).await.unwrap();
+        assert_eq!(result, vec![rsvps[0].clone(), rsvps[3].clone()]);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_tags_and_attributes_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let int = |v| AttributeValue {
+            value: Some(attribute_value::Value::IntValue(v)),
+        };
+        let text = |v: &str| AttributeValue {
+            value: Some(attribute_value::Value::StringValue(v.into())),
+        };
+
+        let mut rsvps = vec![];
+        for (i, (tags, attendees)) in [(vec!["vip", "board"], 12), (vec!["vip"], 4), (vec![], 30)]
+            .into_iter()
+            .enumerate()
+        {
+            let rsvp = Reservation {
+                tags: tags.into_iter().map(String::from).collect(),
+                attributes: HashMap::from([
+                    ("attendees".to_string(), int(attendees)),
+                    ("cost_center".to_string(), text(&format!("cc-{}", i % 2))),
+                ]),
+                ..Reservation::new_pending(
+                    "hyx",
+                    format!("room-{}", i),
+                    "2022-12-01T12:00:00-0700".parse().unwrap(),
+                    "2022-12-02T12:00:00-0700".parse().unwrap(),
+                    "hello",
+                )
+            };
+            rsvps.push(manager.reserve(rsvp).await.unwrap());
+        }
+        // tags are kept sorted
+        assert_eq!(rsvps[0].tags, vec!["board", "vip"]);
+        assert_eq!(manager.get(rsvps[0].id).await.unwrap(), rsvps[0]);
+
+        let ids = |rsvps: Vec<Reservation>| rsvps.iter().map(|r| r.id).collect::<Vec<_>>();
+        let query = ReservationQueryBuilder::default()
+            .tags(vec!["vip".to_string()])
+            .build()
+            .unwrap();
+        let (result, _) = manager.query(query).await.unwrap();
+        assert_eq!(ids(result), vec![rsvps[0].id, rsvps[1].id]);
+
+        let filter = ReservationFilterBuilder::default()
+            .attributes(vec![
+                AttributeFilter {
+                    key: "attendees".into(),
+                    gte: Some(int(10)),
+                    lt: Some(int(30)),
+                    ..Default::default()
+                },
+                AttributeFilter {
+                    key: "cost_center".into(),
+                    eq: Some(text("cc-0")),
+                    ..Default::default()
+                },
+            ])
+            .build()
+            .unwrap();
+        let (result, _) = manager.filter(filter).await.unwrap();
+        assert_eq!(ids(result), vec![rsvps[0].id]);
+
+        // a string attribute is never in a numeric range
+        let filter = ReservationFilterBuilder::default()
+            .attributes(vec![AttributeFilter {
+                key: "cost_center".into(),
+                gte: Some(int(0)),
+                ..Default::default()
+            }])
+            .build()
+            .unwrap();
+        let (result, _) = manager.filter(filter).await.unwrap();
+        assert!(result.is_empty());
+
+        let partial = Reservation {
+            tags: vec!["board".into()],
+            ..Default::default()
+        };
+        let updated = manager
+            .update(rsvps[1].id, partial, field_mask(&["tags"]), None)
+            .await
+            .unwrap();
+        assert_eq!(updated.tags, vec!["board"]);
+
+        let changes = manager.get_history(rsvps[1].id).await.unwrap();
+        assert_eq!(changes[1].changed_fields, vec!["tags"]);
+        assert_eq!(changes[1].after.as_ref().unwrap().tags, vec!["board"]);
+        assert_eq!(
+            changes[1].after.as_ref().unwrap().attributes,
+            rsvps[1].attributes
+        );
+
+        let invalid = ReservationFilterBuilder::default()
+            .attributes(vec![AttributeFilter {
+                key: "attendees".into(),
+                ..Default::default()
+            }])
+            .build()
+            .unwrap();
+        assert!(matches!(
+            manager.filter(invalid).await.unwrap_err(),
+            ReservationError::InvalidAttribute(_)
+        ));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_group_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = vec![
+            Reservation::new_pending(
+                "hyx",
+                "room-421",
+                "2022-12-24T12:00:00-0700".parse().unwrap(),
+                "2022-12-24T14:00:00-0700".parse().unwrap(),
+                "meeting",
+            ),
+            Reservation::new_pending(
+                "hyx",
+                "projector-1",
+                "2022-12-24T12:00:00-0700".parse().unwrap(),
+                "2022-12-24T14:00:00-0700".parse().unwrap(),
+                "meeting",
+            ),
+        ];
+
+        let rsvps = manager.reserve_group(rsvps).await.unwrap();
+        assert_eq!(rsvps.len(), 2);
+        assert!(!rsvps[0].group_id.is_empty());
+        assert_eq!(rsvps[0].group_id, rsvps[1].group_id);
+
+        let group_id = rsvps[0].group_id.clone();
+        assert_eq!(manager.get_group(&group_id).await.unwrap(), rsvps);
+
+        let confirmed = manager.confirm_group(&group_id).await.unwrap();
+        assert!(confirmed
+            .iter()
+            .all(|rsvp| rsvp.status == ReservationStatus::Confirmed as i32));
+
+        let cancelled = manager
+            .cancel_group(&group_id, "meeting moved".into(), "hyx".into())
+            .await
+            .unwrap();
+        assert_eq!(cancelled.len(), 2);
+        assert!(cancelled.iter().all(|rsvp| {
+            rsvp.status == ReservationStatus::Cancelled as i32
+                && rsvp.cancel_reason == "meeting moved"
+        }));
+        assert_eq!(manager.get_group(&group_id).await.unwrap(), cancelled);
+        assert_eq!(
+            manager
+                .get_group("00000000-0000-0000-0000-000000000000")
+                .await
+                .unwrap_err(),
+            ReservationError::GroupNotFound("00000000-0000-0000-0000-000000000000".into())
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_group_conflict_should_rollback_all_items() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let existing = manager
+            .reserve(Reservation::new_pending(
+                "tyr",
+                "projector-1",
+                "2022-12-24T10:00:00-0700".parse().unwrap(),
+                "2022-12-24T13:00:00-0700".parse().unwrap(),
+                "existing",
+            ))
+            .await
+            .unwrap();
+
+        let room = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-24T14:00:00-0700".parse().unwrap(),
+            "meeting",
+        );
+        let projector = Reservation::new_pending(
+            "hyx",
+            "projector-1",
+            "2022-12-24T12:00:00-0700".parse().unwrap(),
+            "2022-12-24T14:00:00-0700".parse().unwrap(),
+            "meeting",
+        );
+        let rsvps = vec![room.clone(), projector, room];
+
+        let err = manager.reserve_group(rsvps).await.unwrap_err();
+        let failures = match err {
+            ReservationError::GroupReservationFailed(failures) => failures,
+            _ => panic!("expect group reservation failed error, got {err:?}"),
+        };
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].index, 1);
+        assert_eq!(failures[1].index, 2);
+        for failure in failures {
+            assert!(matches!(
+                failure.error,
+                ReservationError::ConflictReservation(ReservationConflictInfo::Parsed(_))
+            ));
+        }
+
+        // the room reserved by the first item was rolled back
+        let filter = ReservationFilterBuilder::default()
+            .status(ReservationStatus::Pending as i32)
+            .page_size(10)
+            .build()
+            .unwrap();
+        let (rsvps, _) = manager.filter(filter).await.unwrap();
+        assert_eq!(rsvps, vec![existing]);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reserve_group_invalid_input_should_reject() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        assert_eq!(
+            manager.reserve_group(vec![]).await.unwrap_err(),
+            ReservationError::EmptyReservationGroup
+        );
+        assert_eq!(
+            manager.get_group("not-a-uuid").await.unwrap_err(),
+            ReservationError::InvalidGroupId("not-a-uuid".into())
+        );
+    }
+
+    fn field_mask(paths: &[&str]) -> FieldMask {
+        FieldMask {
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_search_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let fixtures = [
+            ("Acme demo for the board", vec![], "cc-1"),
+            ("明天下午和客户演示新产品", vec![], "cc-2"),
+            ("weekly sync", vec!["acme"], "cc-3"),
+            ("quarterly review", vec![], "Acme Inc"),
+        ];
+        let mut rsvps = vec![];
+        for (i, (note, tags, customer)) in fixtures.into_iter().enumerate() {
+            let rsvp = Reservation {
+                tags: tags.into_iter().map(String::from).collect(),
+                attributes: HashMap::from([(
+                    "customer".to_string(),
+                    AttributeValue {
+                        value: Some(attribute_value::Value::StringValue(customer.into())),
+                    },
+                )]),
+                ..Reservation::new_pending(
+                    "hyx",
+                    format!("room-{}", i),
+                    "2022-12-01T12:00:00-0700".parse().unwrap(),
+                    "2022-12-02T12:00:00-0700".parse().unwrap(),
+                    note,
+                )
+            };
+            rsvps.push(manager.reserve(rsvp).await.unwrap());
+        }
+
+        let search = |query: &str| ReservationSearch {
+            query: query.to_string(),
+            ..Default::default()
+        };
+        let ids = |results: &[SearchResult]| {
+            results
+                .iter()
+                .map(|r| r.reservation.as_ref().unwrap().id)
+                .collect::<Vec<_>>()
+        };
+
+        // a match in the note ranks above tags, tags above attributes
+        let results = manager.search(search("acme")).await.unwrap();
+        assert_eq!(ids(&results), vec![rsvps[0].id, rsvps[2].id, rsvps[3].id]);
+        assert!(results[0].rank > results[1].rank && results[1].rank > results[2].rank);
+        assert_eq!(results[0].snippet, "<b>Acme</b> demo for the board");
+        // the snippet comes from the field which matched
+        assert_eq!(results[1].snippet, "<b>acme</b>");
+        assert_eq!(results[2].snippet, "<b>Acme</b> Inc");
+
+        // stemmed english words
+        let results = manager.search(search("demos")).await.unwrap();
+        assert_eq!(ids(&results), vec![rsvps[0].id]);
+
+        // chinese words match inside a sentence without spaces
+        let results = manager.search(search("客户 演示")).await.unwrap();
+        assert_eq!(ids(&results), vec![rsvps[1].id]);
+        assert_eq!(results[0].snippet, "明天下午和<b>客户演示</b>新产品");
+        let results = manager.search(search("演")).await.unwrap();
+        assert_eq!(ids(&results), vec![rsvps[1].id]);
+        let results = manager.search(search("演出")).await.unwrap();
+        assert!(results.is_empty());
+
+        let results = manager.search(search("acme -board")).await.unwrap();
+        assert_eq!(ids(&results), vec![rsvps[2].id, rsvps[3].id]);
+
+        manager
+            .cancel(rsvps[2].id, String::new(), String::new(), None)
+            .await
+            .unwrap();
+        let results = manager.search(search("sync")).await.unwrap();
+        assert!(results.is_empty());
+        let results = manager
+            .search(ReservationSearch {
+                include_cancelled: true,
+                ..search("sync")
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&results), vec![rsvps[2].id]);
+
+        assert_eq!(
+            manager.search(search("")).await.unwrap_err(),
+            ReservationError::InvalidSearchQuery("empty query".into())
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn reservation_analytics_should_work() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut ids = vec![];
+        for (start, end) in [
+            ("2023-01-02T09:00:00Z", "2023-01-02T11:00:00Z"),
+            ("2023-01-02T14:00:00Z", "2023-01-02T14:30:00Z"),
+            ("2023-01-03T23:00:00Z", "2023-01-04T01:00:00Z"),
+            ("2023-01-02T16:00:00Z", "2023-01-02T17:00:00Z"),
+            ("2023-01-03T10:00:00Z", "2023-01-03T11:00:00Z"),
+        ] {
+            let rsvp = Reservation::new_pending(
+                "hyx",
+                "room-1",
+                start.parse().unwrap(),
+                end.parse().unwrap(),
+                "",
+            );
+            ids.push(manager.reserve(rsvp).await.unwrap().id);
+        }
+        // a purged cancellation still counts, it is read from the change log
+        manager
+            .cancel(ids[3], String::new(), String::new(), None)
+            .await
+            .unwrap();
+        manager
+            .cancel(ids[4], String::new(), String::new(), None)
+            .await
+            .unwrap();
+        manager.purge(ids[4]).await.unwrap();
+
+        let query = AnalyticsQuery {
+            resource_ids: vec!["room-1".into(), "room-2".into()],
+            start: Some("2023-01-02T00:00:00Z".parse().unwrap()),
+            end: Some("2023-01-04T00:00:00Z".parse().unwrap()),
+            bucket_size: AnalyticsBucketSize::Day as i32,
+            ..Default::default()
+        };
+        let buckets = manager.analytics(query.clone()).await.unwrap();
+        assert_eq!(buckets.len(), 4);
+
+        let day = &buckets[0];
+        assert_eq!(day.resource_id, "room-1");
+        assert_eq!(day.start, Some("2023-01-02T00:00:00Z".parse().unwrap()));
+        assert_eq!(day.end, Some("2023-01-03T00:00:00Z".parse().unwrap()));
+        assert_eq!(day.booked_minutes, 150);
+        assert!((day.utilization - 150.0 / 1440.0 * 100.0).abs() < 1e-9);
+        assert_eq!((day.reservations, day.cancelled), (2, 1));
+        assert!((day.cancellation_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(day.peak_hour, 9);
+
+        // a reservation across midnight only counts its part in the window
+        let day = &buckets[1];
+        assert_eq!(day.booked_minutes, 60);
+        assert_eq!((day.reservations, day.cancelled), (1, 1));
+        assert_eq!(day.peak_hour, 23);
+
+        let idle = &buckets[2];
+        assert_eq!(idle.resource_id, "room-2");
+        assert_eq!((idle.booked_minutes, idle.reservations), (0, 0));
+        assert_eq!((idle.cancellation_rate, idle.peak_hour), (0.0, -1));
+
+        // local days of UTC+8 end at 16:00 UTC, the peak hour is in local time too
+        let local = AnalyticsQuery {
+            resource_ids: vec!["room-1".into()],
+            time_zone: "Asia/Shanghai".into(),
+            ..query.clone()
+        };
+        let buckets = manager.analytics(local).await.unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(
+            buckets[0].end,
+            Some("2023-01-02T16:00:00Z".parse().unwrap())
+        );
+        assert_eq!(buckets[0].booked_minutes, 150);
+        assert_eq!(buckets[0].peak_hour, 17);
+
+        let hourly = AnalyticsQuery {
+            resource_ids: vec!["room-1".into()],
+            bucket_size: AnalyticsBucketSize::Hour as i32,
+            ..query.clone()
+        };
+        let buckets = manager.analytics(hourly).await.unwrap();
+        assert_eq!(buckets.len(), 48);
+        assert_eq!(buckets[14].booked_minutes, 30);
+        assert!((buckets[14].utilization - 50.0).abs() < 1e-9);
+
+        let invalid = AnalyticsQuery {
+            time_zone: "Mars/Olympus_Mons".into(),
+            ..query
+        };
+        assert_eq!(
+            manager.analytics(invalid).await.unwrap_err(),
+            ReservationError::InvalidTimeZone("Mars/Olympus_Mons".into())
+        );
+    }
 }
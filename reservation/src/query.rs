@@ -0,0 +1,552 @@
+use std::ops::Bound;
+
+use abi::{
+    AnalyticsQuery, AttributeFilter, PageToken, ReservationError, ReservationFilter,
+    ReservationMatchMode, ReservationQuery, ReservationSearch, ReservationSortField,
+    ReservationStatus, SortValue, Validator,
+};
+use serde_json::{Map, Value};
+use sqlx::{postgres::PgArguments, query::Query, types::Json, Postgres, QueryBuilder};
+
+/// select a page of the reservations matching the query, ordered by its sort keys and id.
+/// a page continues after the token if there is one, otherwise it is picked by page number.
+/// one more row than the page size is selected to tell if there is a next page.
+/// all the values are bound as parameters
+pub(crate) fn build_query(
+    query: &ReservationQuery,
+    after: Option<&PageToken>,
+) -> Result<QueryBuilder<'static, Postgres>, ReservationError> {
+    query.validate()?;
+    let page_size = query.get_page_size()?;
+    let range = query.get_timespan();
+    let unbounded = matches!(
+        (&range.start, &range.end),
+        (Bound::Unbounded, Bound::Unbounded)
+    );
+
+    let mut builder = QueryBuilder::new("SELECT * FROM rsvp.reservations WHERE TRUE");
+    match query.get_match_mode() {
+        // every reservation is within and overlaps a window without start and end
+        ReservationMatchMode::Within | ReservationMatchMode::Overlaps if unbounded => {}
+        ReservationMatchMode::Within => {
+            builder.push(" AND timespan <@ ").push_bind(range);
+        }
+        ReservationMatchMode::Overlaps => {
+            builder.push(" AND timespan && ").push_bind(range);
+        }
+        ReservationMatchMode::Contains => {
+            builder.push(" AND timespan @> ").push_bind(range);
+        }
+        // the overlap is implied, it lets the timespan index narrow the rows
+        ReservationMatchMode::StartsIn => {
+            builder
+                .push(" AND timespan && ")
+                .push_bind(range.clone())
+                .push(" AND lower(timespan) <@ ")
+                .push_bind(range);
+        }
+    }
+    push_conditions(
+        &mut builder,
+        Conditions {
+            user_ids: query.get_user_ids(),
+            resource_ids: query.get_resource_ids(),
+            statuses: query.get_statuses()?,
+            include_cancelled: query.include_cancelled,
+            tags: query.get_tags()?,
+            attributes: &query.attributes,
+        },
+    )?;
+
+    let sort = query.get_sort()?;
+    // ties are broken by id, in the direction of the last key
+    let id_desc = sort.last().map(|key| key.desc).unwrap_or(query.desc);
+    if let Some(token) = after {
+        push_after(&mut builder, token, id_desc)?;
+    }
+
+    let mut order = Vec::with_capacity(sort.len() + 1);
+    for key in &sort {
+        order.push(format!(
+            "{} {}",
+            sort_expr(key.get_field()?),
+            direction(key.desc)
+        ));
+    }
+    order.push(format!("id {}", direction(id_desc)));
+    builder
+        .push(format!(" ORDER BY {} LIMIT ", order.join(", ")))
+        .push_bind(page_size + 1);
+    if after.is_none() {
+        builder
+            .push(" OFFSET ")
+            .push_bind((query.get_page() - 1) * page_size);
+    }
+
+    Ok(builder)
+}
+
+/// select the reservations matching the filter from its cursor on, ordered by id.
+/// one more row than the page size is selected to tell if there is a next page
+pub(crate) fn build_filter(
+    filter: &ReservationFilter,
+) -> Result<QueryBuilder<'static, Postgres>, ReservationError> {
+    filter.validate()?;
+    let page_size = filter.get_page_size()?;
+
+    let mut builder = QueryBuilder::new("SELECT * FROM rsvp.reservations WHERE TRUE");
+    // a cursor of 0 or less starts from the first reservation in the sort direction
+    if filter.cursor > 0 {
+        builder
+            .push(if filter.desc {
+                " AND id <= "
+            } else {
+                " AND id >= "
+            })
+            .push_bind(filter.cursor);
+    }
+    push_conditions(
+        &mut builder,
+        Conditions {
+            user_ids: filter.get_user_ids(),
+            resource_ids: filter.get_resource_ids(),
+            statuses: filter.get_statuses()?,
+            include_cancelled: filter.include_cancelled,
+            tags: filter.get_tags()?,
+            attributes: &filter.attributes,
+        },
+    )?;
+
+    builder
+        .push(format!(" ORDER BY id {} LIMIT ", direction(filter.desc)))
+        .push_bind(page_size + 1);
+
+    Ok(builder)
+}
+
+/// the searchable words of a reservation, the expression of the search index
+const SEARCH_DOCUMENT: &str = "rsvp.search_document(note, tags, attributes)";
+
+/// select a page of the reservations matching the search with their rank, best match first
+pub(crate) fn build_search(
+    search: &ReservationSearch,
+) -> Result<QueryBuilder<'static, Postgres>, ReservationError> {
+    search.validate()?;
+    let page_size = search.get_page_size()?;
+
+    let mut builder = QueryBuilder::new(format!(
+        "SELECT *, ts_rank({SEARCH_DOCUMENT}, rsvp.search_query("
+    ));
+    builder
+        .push_bind(search.query.clone())
+        .push(format!(
+            ")) AS rank FROM rsvp.reservations WHERE {SEARCH_DOCUMENT} @@ rsvp.search_query("
+        ))
+        .push_bind(search.query.clone())
+        .push(")");
+    if !search.include_cancelled {
+        builder.push(" AND status <> 'cancelled'");
+    }
+    builder
+        .push(" ORDER BY rank DESC, id DESC LIMIT ")
+        .push_bind(page_size)
+        .push(" OFFSET ")
+        .push_bind((search.get_page() - 1) * page_size);
+
+    Ok(builder)
+}
+
+/// booked minutes, utilization, reservation count, cancellations and peak hour of every bucket
+/// of the resources. Cancellations are read from the change log, so purged reservations count.
+/// buckets are aligned in local time, so a day starts at midnight across daylight saving
+/// changes, and split into hours of absolute time to find the hour of day with most bookings
+const ANALYTICS_SQL: &str = r#"
+WITH params AS (
+    SELECT $1::text AS tz, $2::timestamptz AS window_start, $3::timestamptz AS window_end,
+        $4::text AS size, $5::interval AS step, $6::text[] AS resource_ids
+), cancellations AS (
+    SELECT DISTINCT ON (c.reservation_id) c.new ->> 'resource_id' AS resource_id,
+        (c.new ->> 'timespan')::tstzrange AS timespan
+    FROM rsvp.reservation_changes c CROSS JOIN params p
+    WHERE c.op = 'update' AND c.to_status = 'cancelled' AND c.from_status <> 'cancelled'
+        AND (c.new ->> 'timespan')::tstzrange && tstzrange(p.window_start, p.window_end)
+        AND (cardinality(p.resource_ids) = 0 OR c.new ->> 'resource_id' = ANY(p.resource_ids))
+), booked AS (
+    SELECT r.resource_id, r.timespan
+    FROM rsvp.reservations r CROSS JOIN params p
+    WHERE r.status <> 'cancelled' AND r.timespan && tstzrange(p.window_start, p.window_end)
+        AND (cardinality(p.resource_ids) = 0 OR r.resource_id = ANY(p.resource_ids))
+), resources AS (
+    SELECT unnest(resource_ids) AS resource_id FROM params
+    UNION SELECT resource_id FROM booked
+    UNION SELECT resource_id FROM cancellations
+), buckets AS (
+    SELECT r.resource_id,
+        tstzrange(greatest(l.local AT TIME ZONE p.tz, p.window_start),
+            least((l.local + p.step) AT TIME ZONE p.tz, p.window_end)) AS span
+    FROM resources r CROSS JOIN params p
+    CROSS JOIN LATERAL generate_series(date_trunc(p.size, p.window_start AT TIME ZONE p.tz),
+        p.window_end AT TIME ZONE p.tz, p.step) AS l(local)
+    WHERE l.local AT TIME ZONE p.tz < p.window_end
+), hours AS (
+    SELECT b.resource_id, b.span, extract(hour FROM h.hour AT TIME ZONE p.tz)::int AS hour_of_day,
+        coalesce(sum(extract(epoch FROM upper(s.slot * k.timespan) - lower(s.slot * k.timespan))), 0)::float8 AS seconds
+    FROM buckets b CROSS JOIN params p
+    CROSS JOIN LATERAL generate_series(date_trunc('hour', lower(b.span), p.tz), upper(b.span),
+        interval '1 hour') AS h(hour)
+    CROSS JOIN LATERAL (SELECT tstzrange(greatest(h.hour, lower(b.span)),
+        least(h.hour + interval '1 hour', upper(b.span))) AS slot) s
+    LEFT JOIN booked k ON k.resource_id = b.resource_id AND k.timespan && s.slot
+    WHERE h.hour < upper(b.span)
+    GROUP BY b.resource_id, b.span, h.hour, p.tz
+), totals AS (
+    SELECT resource_id, span, sum(seconds) AS seconds,
+        (array_agg(hour_of_day ORDER BY seconds DESC, hour_of_day) FILTER (WHERE seconds > 0))[1] AS peak_hour
+    FROM (SELECT resource_id, span, hour_of_day, sum(seconds) AS seconds FROM hours GROUP BY 1, 2, 3) t
+    GROUP BY resource_id, span
+)
+SELECT t.resource_id, lower(t.span) AS start, upper(t.span) AS end,
+    round(t.seconds / 60)::bigint AS booked_minutes,
+    (t.seconds / extract(epoch FROM upper(t.span) - lower(t.span)) * 100)::float8 AS utilization,
+    (SELECT count(*) FROM booked k
+        WHERE k.resource_id = t.resource_id AND k.timespan && t.span) AS reservations,
+    (SELECT count(*) FROM cancellations c
+        WHERE c.resource_id = t.resource_id AND c.timespan && t.span) AS cancelled,
+    coalesce(t.peak_hour, -1) AS peak_hour
+FROM totals t
+ORDER BY t.resource_id, lower(t.span)
+"#;
+
+/// the analytics report of the query, see ANALYTICS_SQL
+pub(crate) fn build_analytics(
+    query: &AnalyticsQuery,
+) -> Result<Query<'static, Postgres, PgArguments>, ReservationError> {
+    query.validate()?;
+    let (start, end) = query.get_window()?;
+    let size = query.get_bucket_size();
+
+    Ok(sqlx::query(ANALYTICS_SQL)
+        .bind(query.get_time_zone().to_string())
+        .bind(start)
+        .bind(end)
+        .bind(size.to_string())
+        .bind(format!("1 {size}"))
+        .bind(query.get_resource_ids()))
+}
+
+/// rows which come after the token in its sort. Keys in one direction without nulls compare as
+/// a row, which the index on (lower(timespan), id) could serve. Otherwise a row comes after the
+/// token if it is equal on the first keys and comes after it on the next one
+fn push_after(
+    builder: &mut QueryBuilder<'static, Postgres>,
+    token: &PageToken,
+    id_desc: bool,
+) -> Result<(), ReservationError> {
+    let fields = token
+        .sort
+        .iter()
+        .map(|key| key.get_field())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let same_direction = token.sort.iter().all(|key| key.desc == id_desc);
+    if same_direction && !fields.contains(&ReservationSortField::End) {
+        builder.push(" AND (");
+        for field in &fields {
+            builder.push(sort_expr(*field)).push(", ");
+        }
+        builder.push(if id_desc { "id) < (" } else { "id) > (" });
+        for (field, value) in fields.iter().zip(&token.values) {
+            push_value(builder, *field, value);
+            builder.push(", ");
+        }
+        builder.push_bind(token.id).push(")");
+        return Ok(());
+    }
+
+    builder.push(" AND (");
+    for i in 0..=fields.len() {
+        if i > 0 {
+            builder.push(" OR ");
+        }
+        builder.push("(");
+        for (&field, value) in fields.iter().zip(&token.values).take(i) {
+            let expr = sort_expr(field);
+            match value {
+                SortValue::Null => {
+                    builder.push(format!("{expr} IS NULL"));
+                }
+                value => {
+                    builder.push(format!("{expr} = "));
+                    push_value(builder, field, value);
+                }
+            }
+            builder.push(" AND ");
+        }
+        match fields.get(i) {
+            Some(&field) => push_beyond(builder, field, &token.values[i], token.sort[i].desc),
+            None => {
+                builder
+                    .push(if id_desc { "id < " } else { "id > " })
+                    .push_bind(token.id);
+            }
+        }
+        builder.push(")");
+    }
+    builder.push(")");
+    Ok(())
+}
+
+/// rows whose field comes after the value. Nulls sort as the largest values, as postgres does
+fn push_beyond(
+    builder: &mut QueryBuilder<'static, Postgres>,
+    field: ReservationSortField,
+    value: &SortValue,
+    desc: bool,
+) {
+    let expr = sort_expr(field);
+    match (value, desc) {
+        (SortValue::Null, false) => {
+            builder.push("FALSE");
+        }
+        (SortValue::Null, true) => {
+            builder.push(format!("{expr} IS NOT NULL"));
+        }
+        (value, false) if field == ReservationSortField::End => {
+            builder.push(format!("({expr} > "));
+            push_value(builder, field, value);
+            builder.push(format!(" OR {expr} IS NULL)"));
+        }
+        (value, false) => {
+            builder.push(format!("{expr} > "));
+            push_value(builder, field, value);
+        }
+        (value, true) => {
+            builder.push(format!("{expr} < "));
+            push_value(builder, field, value);
+        }
+    }
+}
+
+fn push_value(
+    builder: &mut QueryBuilder<'static, Postgres>,
+    field: ReservationSortField,
+    value: &SortValue,
+) {
+    match value {
+        SortValue::Time(time) => {
+            builder.push_bind(*time);
+        }
+        SortValue::Text(text) => {
+            builder.push_bind(text.clone());
+        }
+        SortValue::Null => {
+            builder.push("NULL");
+        }
+    }
+    if field == ReservationSortField::Status {
+        builder.push("::rsvp.reservation_status");
+    }
+}
+
+fn sort_expr(field: ReservationSortField) -> &'static str {
+    match field {
+        ReservationSortField::Start => "lower(timespan)",
+        ReservationSortField::End => "upper(timespan)",
+        ReservationSortField::CreatedAt => "created_at",
+        ReservationSortField::UserId => "user_id",
+        ReservationSortField::ResourceId => "resource_id",
+        ReservationSortField::Status => "status",
+    }
+}
+
+fn direction(desc: bool) -> &'static str {
+    if desc {
+        "DESC"
+    } else {
+        "ASC"
+    }
+}
+
+/// conditions shared by query and filter, an empty list matches all values
+struct Conditions<'a> {
+    user_ids: Vec<String>,
+    resource_ids: Vec<String>,
+    statuses: Vec<ReservationStatus>,
+    include_cancelled: bool,
+    tags: Vec<String>,
+    attributes: &'a [AttributeFilter],
+}
+
+fn push_conditions(
+    builder: &mut QueryBuilder<'static, Postgres>,
+    conditions: Conditions,
+) -> Result<(), ReservationError> {
+    if !conditions.user_ids.is_empty() {
+        builder
+            .push(" AND user_id = ANY(")
+            .push_bind(conditions.user_ids)
+            .push(")");
+    }
+    if !conditions.resource_ids.is_empty() {
+        builder
+            .push(" AND resource_id = ANY(")
+            .push_bind(conditions.resource_ids)
+            .push(")");
+    }
+    let statuses = conditions.statuses;
+    if !conditions.include_cancelled && !statuses.contains(&ReservationStatus::Cancelled) {
+        builder.push(" AND status <> 'cancelled'");
+    }
+    if !statuses.is_empty() {
+        let names: Vec<String> = statuses.iter().map(|s| s.to_string()).collect();
+        builder
+            .push(" AND status = ANY(")
+            .push_bind(names)
+            .push("::rsvp.reservation_status[])");
+    }
+    if !conditions.tags.is_empty() {
+        builder
+            .push(" AND tags @> ")
+            .push_bind(Json(conditions.tags));
+    }
+    for filter in conditions.attributes {
+        push_attribute_filter(builder, filter)?;
+    }
+    Ok(())
+}
+
+/// equality is a containment, which the gin index on attributes serves. Ranges only compare
+/// values of the same json type, jsonb orders values of different types by type
+fn push_attribute_filter(
+    builder: &mut QueryBuilder<'static, Postgres>,
+    filter: &AttributeFilter,
+) -> Result<(), ReservationError> {
+    if let Some(eq) = &filter.eq {
+        let mut object = Map::new();
+        object.insert(filter.key.clone(), eq.to_json()?);
+        builder
+            .push(" AND attributes @> ")
+            .push_bind(Json(Value::Object(object)));
+    }
+    for (value, op) in [(&filter.gte, " >= "), (&filter.lt, " < ")] {
+        let Some(value) = value else { continue };
+        let value = value.to_json()?;
+        let json_type = match value {
+            Value::String(_) => "string",
+            Value::Number(_) => "number",
+            _ => "boolean",
+        };
+        builder
+            .push(" AND jsonb_typeof(attributes -> ")
+            .push_bind(filter.key.clone())
+            .push(format!(") = '{json_type}' AND attributes -> "))
+            .push_bind(filter.key.clone())
+            .push(op)
+            .push_bind(Json(value));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{ReservationFilterBuilder, ReservationQueryBuilder, ReservationSort};
+    use prost_types::Timestamp;
+
+    use super::*;
+
+    #[test]
+    fn build_query_should_bind_all_values() {
+        let query = ReservationQueryBuilder::default()
+            .user_id("hyx")
+            .resource_ids(vec!["room-421".to_string(), "room-422".to_string()])
+            .start("2022-11-20T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .match_mode(ReservationMatchMode::StartsIn as i32)
+            .page(3)
+            .build()
+            .unwrap();
+        let builder = build_query(&query, None).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM rsvp.reservations WHERE TRUE AND timespan && $1 AND lower(timespan) <@ $2 AND user_id = ANY($3) AND resource_id = ANY($4) AND status <> 'cancelled' ORDER BY lower(timespan) ASC, id ASC LIMIT $5 OFFSET $6"
+        );
+
+        let query = ReservationQueryBuilder::default()
+            .desc(true)
+            .page(3)
+            .build()
+            .unwrap();
+        let token = PageToken {
+            sort: query.get_sort().unwrap(),
+            values: vec![SortValue::Time("2022-11-20T19:00:00Z".parse().unwrap())],
+            id: 42,
+        };
+        let builder = build_query(&query, Some(&token)).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM rsvp.reservations WHERE TRUE AND status <> 'cancelled' AND (lower(timespan), id) < ($1, $2) ORDER BY lower(timespan) DESC, id DESC LIMIT $3"
+        );
+    }
+
+    #[test]
+    fn build_query_with_mixed_sort_should_work() {
+        let query = ReservationQueryBuilder::default()
+            .sort(vec![
+                ReservationSort::new(ReservationSortField::Status, false),
+                ReservationSort::new(ReservationSortField::End, true),
+            ])
+            .include_cancelled(true)
+            .build()
+            .unwrap();
+        let token = PageToken {
+            sort: query.get_sort().unwrap(),
+            values: vec![SortValue::Text("pending".into()), SortValue::Null],
+            id: 42,
+        };
+        let builder = build_query(&query, Some(&token)).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM rsvp.reservations WHERE TRUE AND ((status > $1::rsvp.reservation_status) OR (status = $2::rsvp.reservation_status AND upper(timespan) IS NOT NULL) OR (status = $3::rsvp.reservation_status AND upper(timespan) IS NULL AND id < $4)) ORDER BY status ASC, upper(timespan) DESC, id DESC LIMIT $5"
+        );
+    }
+
+    #[test]
+    fn build_search_should_bind_all_values() {
+        let search = ReservationSearch {
+            query: "acme 演示".to_string(),
+            page: 2,
+            ..Default::default()
+        };
+        let builder = build_search(&search).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT *, ts_rank(rsvp.search_document(note, tags, attributes), rsvp.search_query($1)) AS rank FROM rsvp.reservations WHERE rsvp.search_document(note, tags, attributes) @@ rsvp.search_query($2) AND status <> 'cancelled' ORDER BY rank DESC, id DESC LIMIT $3 OFFSET $4"
+        );
+
+        let search = ReservationSearch {
+            query: " ".to_string(),
+            ..Default::default()
+        };
+        assert!(build_search(&search).is_err());
+    }
+
+    #[test]
+    fn build_filter_should_bind_all_values() {
+        let filter = ReservationFilterBuilder::default()
+            .statuses(vec![ReservationStatus::Cancelled as i32])
+            .cursor(42)
+            .desc(true)
+            .build()
+            .unwrap();
+        let builder = build_filter(&filter).unwrap();
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM rsvp.reservations WHERE TRUE AND id <= $1 AND status = ANY($2::rsvp.reservation_status[]) ORDER BY id DESC LIMIT $3"
+        );
+
+        let filter = ReservationFilter {
+            page_size: -1,
+            ..Default::default()
+        };
+        assert!(build_filter(&filter).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use abi::ReservationError;
+use chrono_tz::Tz;
+
+use crate::ReservationManager;
+
+const MAX_RESOURCE_ID_LEN: usize = 64;
+
+impl ReservationManager {
+    /// set the time zone of a resource, used for the local times of its exports and calendars.
+    /// The name must be an IANA time zone, e.g. Asia/Shanghai
+    pub async fn set_time_zone(
+        &self,
+        resource_id: String,
+        time_zone: String,
+    ) -> Result<(), ReservationError> {
+        if resource_id.is_empty() || resource_id.len() > MAX_RESOURCE_ID_LEN {
+            return Err(ReservationError::InvalidResourceId(resource_id));
+        }
+        if time_zone.parse::<Tz>().is_err() {
+            return Err(ReservationError::InvalidTimeZone(time_zone));
+        }
+
+        sqlx::query(
+            "INSERT INTO rsvp.resources (id, time_zone) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET time_zone = excluded.time_zone",
+        )
+        .bind(resource_id)
+        .bind(time_zone)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn time_zone_of(manager: &ReservationManager, id: &str) -> Option<String> {
+        sqlx::query_scalar("SELECT time_zone FROM rsvp.resources WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&manager.pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn set_time_zone_should_insert_or_replace() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        manager
+            .set_time_zone("room-1".into(), "Asia/Shanghai".into())
+            .await
+            .unwrap();
+        assert_eq!(
+            time_zone_of(&manager, "room-1").await.as_deref(),
+            Some("Asia/Shanghai")
+        );
+
+        manager
+            .set_time_zone("room-1".into(), "America/New_York".into())
+            .await
+            .unwrap();
+        assert_eq!(
+            time_zone_of(&manager, "room-1").await.as_deref(),
+            Some("America/New_York")
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn set_time_zone_should_reject_invalid_names() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let err = manager
+            .set_time_zone("room-1".into(), "Mars/Olympus_Mons".into())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ReservationError::InvalidTimeZone("Mars/Olympus_Mons".into())
+        );
+        let err = manager
+            .set_time_zone("".into(), "UTC".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err, ReservationError::InvalidResourceId("".into()));
+        assert_eq!(time_zone_of(&manager, "room-1").await, None);
+    }
+}
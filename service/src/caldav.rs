@@ -0,0 +1,293 @@
+use abi::{CalDavConfig, ReservationError};
+use reqwest::{
+    header::{CONTENT_TYPE, ETAG},
+    Method, StatusCode, Url,
+};
+use reservation::{CalDav, SyncDelta};
+
+const DAV: &str = "DAV:";
+
+/// a caldav collection reached over http, the hrefs it lists are resolved against its url
+pub struct CalDavClient {
+    http: reqwest::Client,
+    url: Url,
+    username: String,
+    password: String,
+}
+
+impl CalDavClient {
+    pub fn new(
+        http: reqwest::Client,
+        config: &CalDavConfig,
+        url: &str,
+    ) -> Result<Self, ReservationError> {
+        Ok(Self {
+            http,
+            url: Url::parse(url).map_err(caldav_error)?,
+            username: config.username.clone(),
+            password: config.password.clone(),
+        })
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        href: &str,
+    ) -> Result<reqwest::RequestBuilder, ReservationError> {
+        let url = self.url.join(href).map_err(caldav_error)?;
+        let request = self.http.request(method, url);
+        Ok(match self.username.is_empty() {
+            true => request,
+            false => request.basic_auth(&self.username, Some(&self.password)),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl CalDav for CalDavClient {
+    async fn sync_collection(&self, url: &str, token: &str) -> Result<SyncDelta, ReservationError> {
+        let report = Method::from_bytes(b"REPORT").expect("REPORT is a valid method");
+        let resp = self
+            .request(report, url)?
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .header("Depth", "0")
+            .body(sync_collection_body(token))
+            .send()
+            .await
+            .map_err(caldav_error)?;
+        let status = resp.status();
+        let body = resp.text().await.map_err(caldav_error)?;
+        match status {
+            StatusCode::MULTI_STATUS => parse_multistatus(&body),
+            // RFC 6578 names the failed precondition in the body
+            StatusCode::FORBIDDEN | StatusCode::CONFLICT if body.contains("valid-sync-token") => {
+                Err(ReservationError::InvalidSyncToken)
+            }
+            status => Err(caldav_error(format!("REPORT {}: {}", url, status))),
+        }
+    }
+
+    async fn get_event(&self, href: &str) -> Result<Option<(Vec<u8>, String)>, ReservationError> {
+        let resp = self
+            .request(Method::GET, href)?
+            .send()
+            .await
+            .map_err(caldav_error)?;
+        match resp.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(None),
+            status if status.is_success() => {
+                let etag = etag(&resp);
+                let data = resp.bytes().await.map_err(caldav_error)?;
+                Ok(Some((data.to_vec(), etag.unwrap_or_default())))
+            }
+            status => Err(caldav_error(format!("GET {}: {}", href, status))),
+        }
+    }
+
+    async fn put_event(
+        &self,
+        href: &str,
+        data: Vec<u8>,
+    ) -> Result<Option<String>, ReservationError> {
+        let resp = self
+            .request(Method::PUT, href)?
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(data)
+            .send()
+            .await
+            .map_err(caldav_error)?;
+        match resp.status() {
+            status if status.is_success() => Ok(etag(&resp)),
+            status => Err(caldav_error(format!("PUT {}: {}", href, status))),
+        }
+    }
+
+    async fn delete_event(&self, href: &str) -> Result<(), ReservationError> {
+        let resp = self
+            .request(Method::DELETE, href)?
+            .send()
+            .await
+            .map_err(caldav_error)?;
+        match resp.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(()),
+            status if status.is_success() => Ok(()),
+            status => Err(caldav_error(format!("DELETE {}: {}", href, status))),
+        }
+    }
+}
+
+fn etag(resp: &reqwest::Response) -> Option<String> {
+    resp.headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+fn caldav_error(e: impl ToString) -> ReservationError {
+    ReservationError::CalDavError(e.to_string())
+}
+
+/// body of a sync-collection REPORT, an empty token asks for every event
+fn sync_collection_body(token: &str) -> String {
+    let token = token
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>{}</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+  </D:prop>
+</D:sync-collection>"#,
+        token
+    )
+}
+
+/// the changes listed by a sync-collection REPORT. A response with a 404 status is a removed
+/// event, the collection itself is skipped
+fn parse_multistatus(body: &str) -> Result<SyncDelta, ReservationError> {
+    let doc = roxmltree::Document::parse(body).map_err(caldav_error)?;
+    let is = |node: &roxmltree::Node, name: &str| {
+        node.is_element()
+            && node.tag_name().name() == name
+            && node.tag_name().namespace() == Some(DAV)
+    };
+    let text = |node: &roxmltree::Node, name: &str| {
+        node.descendants()
+            .find(|n| is(n, name))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    let mut delta = SyncDelta::default();
+    for node in doc.root_element().children() {
+        if is(&node, "sync-token") {
+            delta.token = node.text().unwrap_or_default().trim().to_string();
+        }
+        if !is(&node, "response") {
+            continue;
+        }
+        let Some(href) = text(&node, "href") else {
+            continue;
+        };
+        if href.ends_with('/') {
+            continue;
+        }
+        let removed = node
+            .children()
+            .find(|n| is(n, "status"))
+            .and_then(|n| n.text())
+            .is_some_and(|status| status.contains(" 404 "));
+        match removed {
+            true => delta.removed.push(href),
+            false => delta
+                .changed
+                .push((href, text(&node, "getetag").unwrap_or_default())),
+        }
+    }
+    Ok(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::Reservation;
+    use reservation::{ReservationManager, Rsvp};
+
+    use super::*;
+
+    #[test]
+    fn sync_collection_body_should_escape_token() {
+        let body = sync_collection_body("http://example.com/sync?a=1&b=<2>");
+        assert!(body
+            .contains("<D:sync-token>http://example.com/sync?a=1&amp;b=&lt;2&gt;</D:sync-token>"));
+        assert!(sync_collection_body("").contains("<D:sync-token></D:sync-token>"));
+    }
+
+    #[test]
+    fn parse_multistatus_should_work() {
+        let body = r#"<?xml version='1.0' encoding='utf-8'?>
+<multistatus xmlns="DAV:">
+  <response>
+    <href>/alice/room-1/</href>
+    <propstat><prop><getetag>"c"</getetag></prop><status>HTTP/1.1 200 OK</status></propstat>
+  </response>
+  <response>
+    <href>/alice/room-1/ext-1.ics</href>
+    <propstat><prop><getetag>"e1"</getetag></prop><status>HTTP/1.1 200 OK</status></propstat>
+  </response>
+  <response>
+    <href>/alice/room-1/ext-2.ics</href>
+    <status>HTTP/1.1 404 Not Found</status>
+  </response>
+  <sync-token>http://radicale.org/ns/sync/abc</sync-token>
+</multistatus>"#;
+        let delta = parse_multistatus(body).unwrap();
+        assert_eq!(
+            delta,
+            SyncDelta {
+                token: "http://radicale.org/ns/sync/abc".into(),
+                changed: vec![("/alice/room-1/ext-1.ics".into(), "\"e1\"".into())],
+                removed: vec!["/alice/room-1/ext-2.ics".into()],
+            }
+        );
+    }
+
+    /// runs against a real caldav server when CALDAV_TEST_URL names an empty calendar collection,
+    /// such as http://localhost:5232/alice/room-1/ of a local Radicale, with CALDAV_TEST_USER and
+    /// CALDAV_TEST_PASSWORD. It is skipped otherwise
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn sync_should_work_with_caldav_server() {
+        let Ok(url) = std::env::var("CALDAV_TEST_URL") else {
+            return;
+        };
+        let config = CalDavConfig {
+            username: std::env::var("CALDAV_TEST_USER").unwrap_or_default(),
+            password: std::env::var("CALDAV_TEST_PASSWORD").unwrap_or_default(),
+            interval: 60,
+            user_id: "caldav".into(),
+            mirrors: vec![],
+        };
+        let client = CalDavClient::new(reqwest::Client::new(), &config, &url).unwrap();
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-1",
+                "2023-03-01T10:00:00+0000".parse().unwrap(),
+                "2023-03-01T12:00:00+0000".parse().unwrap(),
+                "standup",
+            ))
+            .await
+            .unwrap();
+        let event = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//test//EN\r\nBEGIN:VEVENT\r\nUID:ext-1@example\r\nDTSTAMP:20230101T000000Z\r\nDTSTART:20230301T130000Z\r\nDTEND:20230301T140000Z\r\nSUMMARY:team meeting\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        client
+            .put_event("ext-1.ics", event.as_bytes().to_vec())
+            .await
+            .unwrap();
+
+        let report = manager
+            .sync_caldav(&client, "room-1", &url, "caldav")
+            .await
+            .unwrap();
+        assert_eq!((report.pushed, report.reserved), (1, 1));
+        let (data, _) = client.get_event("ext-1.ics").await.unwrap().unwrap();
+        assert!(String::from_utf8(data)
+            .unwrap()
+            .contains("PARTSTAT=ACCEPTED"));
+        let href = format!("reservation-{}.ics", rsvp.id);
+        assert!(client.get_event(&href).await.unwrap().is_some());
+
+        // the sync token of the server moves only the changes
+        let report = manager
+            .sync_caldav(&client, "room-1", &url, "caldav")
+            .await
+            .unwrap();
+        assert_eq!(report, Default::default());
+
+        client.delete_event("ext-1.ics").await.unwrap();
+        client.delete_event(&href).await.unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+use std::{collections::HashMap, time::Duration};
+
+use abi::ListenRequest;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive},
+        IntoResponse, Response, Sse,
+    },
+    routing::get,
+    Router,
+};
+use futures::StreamExt;
+use reservation::ReservationManager;
+use serde_json::json;
+use tokio::time::{interval_at, Instant};
+
+use crate::RsvpService;
+
+/// idle connections get a heartbeat this often, so proxies keep them open and clients can tell
+/// the stream is alive
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+impl RsvpService {
+    /// http bridges of the listen call, server-sent events at /v1/events and a websocket at
+    /// /v1/events/ws. Every change is a json ListenResponse with its position as the event id
+    pub fn events_router(&self) -> Router {
+        router(self.manager.clone(), HEARTBEAT_INTERVAL)
+    }
+}
+
+#[derive(Clone)]
+struct Events {
+    manager: ReservationManager,
+    heartbeat: Duration,
+}
+
+fn router(manager: ReservationManager, heartbeat: Duration) -> Router {
+    Router::new()
+        .route("/v1/events", get(sse))
+        .route("/v1/events/ws", get(websocket))
+        .with_state(Events { manager, heartbeat })
+}
+
+async fn sse(
+    State(events): State<Events>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    let request = match listen_request(&params, &headers) {
+        Ok(request) => request,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let changes = events.manager.listen(request).map(|change| match change {
+        Ok(change) => Event::default()
+            .event("change")
+            .id(change.seq.to_string())
+            .json_data(change),
+        // the stream ends after an error, the client reconnects with the last id it got
+        Err(e) => Ok(Event::default().event("error").data(e.to_string())),
+    });
+    let heartbeat = KeepAlive::new()
+        .interval(events.heartbeat)
+        .event(Event::default().event("heartbeat"));
+    Sse::new(changes).keep_alive(heartbeat).into_response()
+}
+
+async fn websocket(
+    State(events): State<Events>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    match listen_request(&params, &headers) {
+        Ok(request) => upgrade.on_upgrade(move |socket| forward(events, request, socket)),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+/// send the changes as text messages, json objects with the fields of the server-sent events:
+/// the event, and the id and data of a change
+async fn forward(events: Events, request: ListenRequest, mut socket: WebSocket) {
+    let mut changes = events.manager.listen(request);
+    let mut heartbeat = interval_at(Instant::now() + events.heartbeat, events.heartbeat);
+    loop {
+        let message = tokio::select! {
+            change = changes.next() => match change {
+                Some(Ok(change)) => {
+                    json!({ "event": "change", "id": change.seq.to_string(), "data": change })
+                }
+                Some(Err(e)) => json!({ "event": "error", "data": e.to_string() }),
+                None => break,
+            },
+            _ = heartbeat.tick() => json!({ "event": "heartbeat" }),
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                // the client has nothing to say
+                Some(Ok(_)) => continue,
+            },
+        };
+        if socket
+            .send(Message::Text(message.to_string()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// the filters of the listen call are comma separated resourceIds and userIds. The position to
+/// resume after is the Last-Event-ID header an EventSource sends when it reconnects, or the
+/// lastEventId parameter
+fn listen_request(
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> Result<ListenRequest, String> {
+    let list = |name: &str| -> Vec<String> {
+        params
+            .get(name)
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let last_event_id = match headers.get("last-event-id") {
+        Some(v) => Some(String::from_utf8_lossy(v.as_bytes()).into_owned()),
+        None => params.get("lastEventId").cloned(),
+    };
+    let after = match last_event_id {
+        Some(id) => id
+            .parse()
+            .map_err(|_| format!("invalid last event id: {}", id))?,
+        None => 0,
+    };
+    Ok(ListenRequest {
+        resource_ids: list("resourceIds"),
+        user_ids: list("userIds"),
+        after,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::Reservation;
+    use axum::{body::Body, http::Request};
+    use hyper::body::HttpBody;
+    use reservation::Rsvp;
+    use serde_json::Value;
+    use tokio::time::timeout;
+    use tokio_tungstenite::tungstenite;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn rsvp(user_id: &str, resource_id: &str) -> Reservation {
+        Reservation::new_pending(
+            user_id,
+            resource_id,
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "note",
+        )
+    }
+
+    /// the json of the next text message
+    async fn next_message<S>(socket: &mut S) -> Value
+    where
+        S: futures::Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+    {
+        let message = timeout(Duration::from_secs(10), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match message {
+            tungstenite::Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("unexpected message {:?}", message),
+        }
+    }
+
+    /// the fields of the server-sent events in the text, by event
+    fn parse_events(text: &str) -> Vec<HashMap<String, String>> {
+        text.split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                event
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .map(|(name, value)| (name.to_string(), value.trim_start().to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn listen_request_should_read_filters_and_last_event_id() {
+        let params: HashMap<_, _> = [
+            ("resourceIds", "room-1, room-2,"),
+            ("userIds", "alice"),
+            ("lastEventId", "7"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let request = listen_request(&params, &HeaderMap::new()).unwrap();
+        assert_eq!(request.resource_ids, ["room-1", "room-2"]);
+        assert_eq!(request.user_ids, ["alice"]);
+        assert_eq!(request.after, 7);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("last-event-id", "12".parse().unwrap());
+        assert_eq!(listen_request(&params, &headers).unwrap().after, 12);
+        headers.insert("last-event-id", "abc".parse().unwrap());
+        assert!(listen_request(&params, &headers).is_err());
+        assert_eq!(
+            listen_request(&HashMap::new(), &HeaderMap::new()).unwrap(),
+            ListenRequest::default()
+        );
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn sse_should_resume_after_last_event_id() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        manager.reserve(rsvp("carol", "room-1")).await.unwrap();
+        let first = manager.reserve(rsvp("alice", "room-3")).await.unwrap();
+        manager.reserve(rsvp("bob", "room-2")).await.unwrap();
+        let seq: i64 = sqlx::query_scalar(
+            "SELECT seq FROM rsvp.reservation_changes WHERE reservation_id = $1",
+        )
+        .bind(first.id.to_string())
+        .fetch_one(&migrated_pool)
+        .await
+        .unwrap();
+
+        let request = Request::get("/v1/events?resourceIds=room-2,room-3&userIds=alice")
+            .header("last-event-id", (seq - 1).to_string())
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(manager, Duration::from_millis(100))
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+
+        // the change of alice and heartbeats, carol is before the position and bob is filtered out
+        let mut body = resp.into_body();
+        let mut text = String::new();
+        while !text
+            .split_once("event:change")
+            .is_some_and(|(_, rest)| rest.contains("event:heartbeat"))
+        {
+            let chunk = timeout(Duration::from_secs(10), body.data())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        let events = parse_events(&text);
+        let changes: Vec<_> = events.iter().filter(|e| e["event"] == "change").collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0]["id"], seq.to_string());
+        let data: Value = serde_json::from_str(&changes[0]["data"]).unwrap();
+        assert_eq!(data["op"], "RESERVATION_UPDATE_TYPE_CREATE");
+        assert_eq!(data["reservation"]["id"], first.id);
+        assert_eq!(data["reservation"]["userId"], "alice");
+
+        let request = Request::get("/v1/events?lastEventId=x")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(
+            ReservationManager::new(migrated_pool.clone()),
+            HEARTBEAT_INTERVAL,
+        )
+        .oneshot(request)
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn websocket_should_send_changes_and_heartbeats() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let app = router(manager.clone(), Duration::from_millis(100));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let url = format!("ws://{}/v1/events/ws?userIds=alice", addr);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        // the stream is listening once it sends a heartbeat
+        assert_eq!(
+            next_message(&mut socket).await,
+            json!({ "event": "heartbeat" })
+        );
+        manager.reserve(rsvp("bob", "room-2")).await.unwrap();
+        let reserved = manager.reserve(rsvp("alice", "room-1")).await.unwrap();
+        let message = loop {
+            let message = next_message(&mut socket).await;
+            if message["event"] != "heartbeat" {
+                break message;
+            }
+        };
+        assert_eq!(message["event"], "change");
+        assert!(message["id"].as_str().unwrap().parse::<i64>().is_ok());
+        assert_eq!(message["data"]["reservation"]["id"], reserved.id);
+        assert_eq!(message["data"]["reservation"]["userId"], "alice");
+    }
+}
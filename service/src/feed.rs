@@ -0,0 +1,209 @@
+use abi::{ExportFormat, ReservationError};
+use axum::{
+    body::StreamBody,
+    extract::{Path, State},
+    http::{
+        header::{
+            CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+        },
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::DateTime;
+use reservation::{FeedVersion, ReservationManager, Rsvp};
+
+use crate::RsvpService;
+
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+impl RsvpService {
+    /// http routes of the calendar feeds, served at /feeds/<token>.ics
+    pub fn feed_router(&self) -> Router {
+        router(self.manager.clone())
+    }
+}
+
+fn router(manager: ReservationManager) -> Router {
+    Router::new()
+        .route("/feeds/:file", get(feed))
+        .with_state(manager)
+}
+
+async fn feed(
+    State(manager): State<ReservationManager>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    match serve_feed(&manager, &file, &headers).await {
+        Ok(resp) => resp,
+        // revoked and unknown tokens look the same
+        Err(ReservationError::InvalidFeedToken) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn serve_feed(
+    manager: &ReservationManager,
+    file: &str,
+    headers: &HeaderMap,
+) -> Result<Response, ReservationError> {
+    let token = file
+        .strip_suffix(".ics")
+        .ok_or(ReservationError::InvalidFeedToken)?;
+    let feed = manager.get_feed(token).await?;
+    let version = manager.feed_version(&feed).await?;
+
+    let validators = [
+        (ETAG, version.etag.clone()),
+        (
+            LAST_MODIFIED,
+            version.last_modified.format(HTTP_DATE).to_string(),
+        ),
+        // clients may keep the feed but should check it is still fresh before using it
+        (CACHE_CONTROL, "private, no-cache".to_string()),
+    ];
+    if not_modified(headers, &version) {
+        return Ok((StatusCode::NOT_MODIFIED, validators).into_response());
+    }
+
+    let body = StreamBody::new(manager.export(feed.query()?, ExportFormat::Icalendar));
+    Ok((
+        validators,
+        [(CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        body,
+    )
+        .into_response())
+}
+
+/// If-Modified-Since is only looked at without If-None-Match, as RFC 9110 asks
+fn not_modified(headers: &HeaderMap, version: &FeedVersion) -> bool {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(tags) = header(IF_NONE_MATCH) {
+        return tags
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == version.etag);
+    }
+    match header(IF_MODIFIED_SINCE).and_then(|v| DateTime::parse_from_rfc2822(v).ok()) {
+        // http dates have no fraction of a second
+        Some(since) => version.last_modified.timestamp() <= since.timestamp(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{CalendarFeedKind, Reservation};
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn get_feed(
+        manager: &ReservationManager,
+        path: &str,
+        headers: &[(&str, &str)],
+    ) -> Response {
+        let mut request = Request::get(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        router(manager.clone())
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn header<'a>(resp: &'a Response, name: &str) -> &'a str {
+        resp.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn feed_should_support_conditional_get() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        manager
+            .reserve(Reservation::new_pending(
+                "alice",
+                "room-1",
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "note",
+            ))
+            .await
+            .unwrap();
+        let (_, token) = manager
+            .create_feed(CalendarFeedKind::Resource, "room-1".into())
+            .await
+            .unwrap();
+        let path = abi::feed_path(&token);
+
+        let resp = get_feed(&manager, &path, &[]).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            header(&resp, "content-type"),
+            "text/calendar; charset=utf-8"
+        );
+        let etag = header(&resp, "etag").to_string();
+        let last_modified = header(&resp, "last-modified").to_string();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(body.contains("LOCATION:room-1\r\n"));
+
+        let resp = get_feed(&manager, &path, &[("if-none-match", &etag)]).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(header(&resp, "etag"), etag);
+        let resp = get_feed(&manager, &path, &[("if-modified-since", &last_modified)]).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        // If-None-Match wins over If-Modified-Since
+        let resp = get_feed(
+            &manager,
+            &path,
+            &[
+                ("if-none-match", "\"other\""),
+                ("if-modified-since", &last_modified),
+            ],
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        manager
+            .reserve(Reservation::new_pending(
+                "bob",
+                "room-1",
+                "2023-01-25T15:00:00-0700".parse().unwrap(),
+                "2023-01-28T12:00:00-0700".parse().unwrap(),
+                "note",
+            ))
+            .await
+            .unwrap();
+        let resp = get_feed(&manager, &path, &[("if-none-match", &etag)]).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_ne!(header(&resp, "etag"), etag);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn revoked_feed_should_not_be_found() {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let (feed, token) = manager
+            .create_feed(CalendarFeedKind::User, "alice".into())
+            .await
+            .unwrap();
+        let path = abi::feed_path(&token);
+        assert_eq!(
+            get_feed(&manager, &path, &[]).await.status(),
+            StatusCode::OK
+        );
+
+        manager.revoke_feed(feed.id).await.unwrap();
+        let resp = get_feed(&manager, &path, &[]).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = get_feed(&manager, "/feeds/unknown.ics", &[]).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = get_feed(&manager, &format!("/feeds/{}", token), &[]).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
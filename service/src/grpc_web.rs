@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use abi::ServerConfig;
+use axum::http::{
+    header::{HeaderName, InvalidHeaderValue},
+    HeaderValue, Method,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// headers a grpc-web client sends, with the metadata the calls look at
+const ALLOW_HEADERS: [&str; 7] = [
+    "content-type",
+    "x-grpc-web",
+    "x-user-agent",
+    "grpc-timeout",
+    "x-actor",
+    "x-change-reason",
+    "x-idempotency-key",
+];
+/// the status of a call is in these headers when the call fails before any message
+const EXPOSE_HEADERS: [&str; 3] = ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+/// cors of the grpc-web calls, for the origins of the config
+pub fn grpc_web_cors(config: &ServerConfig) -> Result<CorsLayer, InvalidHeaderValue> {
+    let origins = match config.cors_origins.iter().any(|origin| origin == "*") {
+        true => AllowOrigin::any(),
+        false => AllowOrigin::list(
+            config
+                .cors_origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::POST])
+        .allow_headers(ALLOW_HEADERS.map(HeaderName::from_static))
+        .expose_headers(EXPOSE_HEADERS.map(HeaderName::from_static))
+        .max_age(Duration::from_secs(24 * 60 * 60)))
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{
+        reservation_service_server::ReservationServiceServer, ExportChunk, ExportRequest,
+        ListenRequest, ListenResponse, QueryRequest, Reservation, ReservationQuery, ReserveRequest,
+        ReserveResponse,
+    };
+    use axum::http::{Request, Response, StatusCode};
+    use hyper::{body::HttpBody, Body};
+    use prost::Message;
+    use reservation::{ReservationManager, Rsvp};
+    use tokio::time::timeout;
+    use tonic_web::GrpcWebLayer;
+    use tower::{Layer, ServiceExt};
+
+    use super::*;
+    use crate::RsvpService;
+
+    fn config(origins: &[&str]) -> ServerConfig {
+        ServerConfig {
+            host: "localhost".into(),
+            port: 50001,
+            idempotency_window: 60,
+            page_token_secret: "".into(),
+            http_port: None,
+            cors_origins: origins.iter().map(|o| o.to_string()).collect(),
+            admin_token: "".into(),
+        }
+    }
+
+    async fn call(pool: &sqlx::PgPool, request: Request<Body>) -> Response<tonic::body::BoxBody> {
+        let svc = RsvpService {
+            manager: ReservationManager::new(pool.clone()),
+            idempotency_window: Duration::from_secs(60),
+            admin_token: "".into(),
+        };
+        let cors = grpc_web_cors(&config(&["https://app.example.com"])).unwrap();
+        cors.layer(GrpcWebLayer::new().layer(ReservationServiceServer::new(svc)))
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    /// a grpc-web request of a call, the message in a data frame
+    fn grpc_web_request(method: &str, message: impl Message) -> Request<Body> {
+        let message = message.encode_to_vec();
+        let mut body = vec![0];
+        body.extend((message.len() as u32).to_be_bytes());
+        body.extend(message);
+        Request::post(format!("/reservation.ReservationService/{}", method))
+            .header("content-type", "application/grpc-web+proto")
+            .header("x-grpc-web", "1")
+            .header("origin", "https://app.example.com")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    /// the data frames and the trailers of a grpc-web response
+    async fn frames(resp: Response<tonic::body::BoxBody>) -> (Vec<Vec<u8>>, String) {
+        let mut body = &hyper::body::to_bytes(resp.into_body()).await.unwrap()[..];
+        let (mut messages, mut trailers) = (vec![], String::new());
+        while !body.is_empty() {
+            let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+            let frame = body[5..5 + len].to_vec();
+            match body[0] {
+                0 => messages.push(frame),
+                _ => trailers = String::from_utf8(frame).unwrap(),
+            }
+            body = &body[5 + len..];
+        }
+        (messages, trailers)
+    }
+
+    /// the next frame of a grpc-web response which is still streaming
+    async fn next_frame(body: &mut tonic::body::BoxBody, buf: &mut Vec<u8>) -> Vec<u8> {
+        loop {
+            if buf.len() >= 5 {
+                let len = u32::from_be_bytes(buf[1..5].try_into().unwrap()) as usize;
+                if buf.len() >= 5 + len {
+                    let frame = buf[5..5 + len].to_vec();
+                    buf.drain(..5 + len);
+                    return frame;
+                }
+            }
+            let chunk = timeout(Duration::from_secs(10), body.data())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            buf.extend_from_slice(&chunk);
+        }
+    }
+
+    #[test]
+    fn grpc_web_cors_should_reject_invalid_origin() {
+        assert!(grpc_web_cors(&config(&["*"])).is_ok());
+        assert!(grpc_web_cors(&config(&["https://bad\norigin"])).is_err());
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn grpc_web_preflight_should_follow_cors_origins() {
+        let preflight = |origin: &str| {
+            Request::options("/reservation.ReservationService/reserve")
+                .header("origin", origin)
+                .header("access-control-request-method", "POST")
+                .header(
+                    "access-control-request-headers",
+                    "content-type,x-grpc-web,x-idempotency-key",
+                )
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = call(&migrated_pool, preflight("https://app.example.com")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let headers = resp.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        assert!(headers["access-control-allow-headers"]
+            .to_str()
+            .unwrap()
+            .contains("x-idempotency-key"));
+
+        let resp = call(&migrated_pool, preflight("https://other.example.com")).await;
+        assert!(resp.headers().get("access-control-allow-origin").is_none());
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn grpc_web_should_serve_unary_and_streaming_calls() {
+        let rsvp = Reservation::new_pending(
+            "alice",
+            "room-1",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "note",
+        );
+        let request = ReserveRequest {
+            reservation: Some(rsvp),
+        };
+        let resp = call(&migrated_pool, grpc_web_request("reserve", request)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "application/grpc-web+proto");
+        assert_eq!(
+            resp.headers()["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        let (messages, trailers) = frames(resp).await;
+        let reserved = ReserveResponse::decode(messages[0].as_slice())
+            .unwrap()
+            .reservation
+            .unwrap();
+        assert_eq!(reserved.user_id, "alice");
+        assert!(trailers.contains("grpc-status:0"));
+
+        let resp = call(
+            &migrated_pool,
+            grpc_web_request("export", ExportRequest::default()),
+        )
+        .await;
+        let (messages, trailers) = frames(resp).await;
+        let csv: Vec<u8> = messages
+            .iter()
+            .flat_map(|m| ExportChunk::decode(m.as_slice()).unwrap().data)
+            .collect();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.contains(&format!("{},alice,", reserved.id)));
+        assert!(trailers.contains("grpc-status:0"));
+
+        // more than a page of reservations, the stream reads them all
+        let manager = ReservationManager::new(migrated_pool.clone());
+        for i in 0..100 {
+            manager
+                .reserve(Reservation::new_pending(
+                    "alice",
+                    format!("room-{}", i + 2),
+                    "2022-12-25T15:00:00-0700".parse().unwrap(),
+                    "2022-12-28T12:00:00-0700".parse().unwrap(),
+                    "note",
+                ))
+                .await
+                .unwrap();
+        }
+        let request = QueryRequest {
+            query: Some(ReservationQuery {
+                user_id: "alice".into(),
+                page_size: 10,
+                ..Default::default()
+            }),
+        };
+        let resp = call(&migrated_pool, grpc_web_request("query_stream", request)).await;
+        let (messages, trailers) = frames(resp).await;
+        let ids: Vec<i64> = messages
+            .iter()
+            .map(|m| Reservation::decode(m.as_slice()).unwrap().id)
+            .collect();
+        assert_eq!(ids.len(), 101);
+        assert_eq!(ids[0], reserved.id);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(trailers.contains("grpc-status:0"));
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn grpc_web_should_stream_listen() {
+        let request = ListenRequest {
+            user_ids: vec!["alice".into()],
+            ..Default::default()
+        };
+        let resp = call(&migrated_pool, grpc_web_request("listen", request)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "application/grpc-web+proto");
+
+        // the changes arrive as data frames of a response which doesn't end
+        let mut body = resp.into_body();
+        let reading = tokio::spawn(async move { next_frame(&mut body, &mut vec![]).await });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = |user_id: &str| {
+            Reservation::new_pending(
+                user_id,
+                format!("room-{}", user_id),
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "note",
+            )
+        };
+        manager.reserve(rsvp("bob")).await.unwrap();
+        let reserved = manager.reserve(rsvp("alice")).await.unwrap();
+        let change = ListenResponse::decode(reading.await.unwrap().as_slice()).unwrap();
+        // the change of bob is filtered out
+        assert_eq!(change.reservation, Some(reserved));
+        assert!(change.seq > 0);
+    }
+}
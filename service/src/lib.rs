@@ -1,13 +1,27 @@
+mod caldav;
+mod events;
+mod feed;
+mod grpc_web;
+mod rest;
 mod service;
 
-use abi::Reservation;
+use abi::{ExportChunk, ListenResponse, Reservation};
 use futures::Stream;
 use reservation::ReservationManager;
-use std::pin::Pin;
+use std::{pin::Pin, time::Duration};
 use tonic::Status;
 
+pub use caldav::CalDavClient;
+pub use grpc_web::grpc_web_cors;
+
 pub struct RsvpService {
     manager: ReservationManager,
+    /// how long the result of a call is kept for its idempotency key
+    idempotency_window: Duration,
+    /// token of the admin calls, they are refused if it is empty
+    admin_token: String,
 }
 
-type ReservationStream = Pin<Box<dyn Stream<Item = Result<Reservation, Status>> + Send>>;
+type ListenStream = Pin<Box<dyn Stream<Item = Result<ListenResponse, Status>> + Send>>;
+type ExportStream = Pin<Box<dyn Stream<Item = Result<ExportChunk, Status>> + Send>>;
+type QueryStream = Pin<Box<dyn Stream<Item = Result<Reservation, Status>> + Send>>;
@@ -1,15 +1,148 @@
-use std::{net::SocketAddr, path::Path};
+use std::{net::SocketAddr, path::Path, path::PathBuf, sync::Arc, time::Duration};
 
-use abi::{reservation_service_server::ReservationServiceServer, Config};
-use anyhow::{Ok, Result};
-use reservation_service::RsvpService;
+use abi::{
+    convert_to_timestamp, reservation_service_client::ReservationServiceClient,
+    reservation_service_server::ReservationServiceServer, Config, ExportFormat, ExportRequest,
+    ImportFormat, ImportMode, ImportRequest, ImportRowStatus, ReservationQueryBuilder,
+    SetTimeZoneRequest,
+};
+use anyhow::{anyhow, Ok, Result};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use reservation::ReservationManager;
+use reservation_service::{grpc_web_cors, CalDavClient, RsvpService};
+use tokio::io::AsyncWriteExt;
+use tonic_web::GrpcWebLayer;
+
+#[derive(Debug, Parser)]
+#[command(about = "reservation service")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// run the grpc server, the default
+    Serve,
+    /// export reservations from a running server as csv, parquet or icalendar
+    Export(ExportArgs),
+    /// check the reservations of a csv, json or icalendar file and import them through a running
+    /// server
+    Import(ImportArgs),
+    /// set the time zone of a resource through a running server
+    TimeZone(TimeZoneArgs),
+    /// mirror the resources of the caldav section of the config into their collections, and
+    /// reserve the events created there
+    Sync(SyncArgs),
+    /// print the OpenAPI document of the http api
+    Openapi,
+}
+
+#[derive(Debug, clap::Args)]
+struct SyncArgs {
+    /// sync every mirror once and exit, instead of every interval
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct ExportArgs {
+    /// file to write, stdout if not given
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+    /// resource ids to export, all resources if not given
+    #[arg(long)]
+    resource: Vec<String>,
+    /// user ids to export, all users if not given
+    #[arg(long)]
+    user: Vec<String>,
+    /// start of the query window, RFC 3339
+    #[arg(long)]
+    start: Option<DateTime<Utc>>,
+    /// end of the query window, RFC 3339
+    #[arg(long)]
+    end: Option<DateTime<Utc>>,
+    /// export cancelled reservations too
+    #[arg(long)]
+    include_cancelled: bool,
+    /// the reservations must have all the tags
+    #[arg(long)]
+    tag: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+struct ImportArgs {
+    /// csv, json or ics file to import
+    file: PathBuf,
+    /// format of the file, guessed from its extension if not given
+    #[arg(short, long, value_enum)]
+    format: Option<InputFormat>,
+    /// only print the report, nothing is imported
+    #[arg(long)]
+    dry_run: bool,
+    /// import the valid rows when some rows are invalid or conflict, instead of nothing
+    #[arg(long)]
+    skip_invalid: bool,
+    /// rows checked or inserted together, the server default if not given
+    #[arg(long, default_value_t = 0)]
+    batch_size: i32,
+    /// user of the rows without one, such as calendar events
+    #[arg(long, default_value = "")]
+    user: String,
+    /// resource of the rows without one
+    #[arg(long, default_value = "")]
+    resource: String,
+}
+
+#[derive(Debug, clap::Args)]
+struct TimeZoneArgs {
+    /// resource to set the time zone of
+    resource: String,
+    /// IANA time zone name, e.g. Asia/Shanghai
+    time_zone: String,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum InputFormat {
+    Csv,
+    Json,
+    Ics,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Parquet,
+    Ics,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // we should first try to load RESERVE_CONFIG envar, then try "./reservation.yml", then try "~/.config/reservation.yml"
-    // then try "/etc/reservation.yml"
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Serve);
+    if let Command::Openapi = command {
+        println!("{}", serde_json::to_string_pretty(&abi::openapi())?);
+        return Ok(());
+    }
+    let config = Config::load(config_filename().as_str())?;
 
-    let filename = std::env::var("RESERVATION_CONFIG").unwrap_or_else(|_| {
+    match command {
+        Command::Serve => serve(config).await,
+        Command::Export(args) => export(config, args).await,
+        Command::Import(args) => import(config, args).await,
+        Command::TimeZone(args) => time_zone(config, args).await,
+        Command::Sync(args) => sync(config, args).await,
+        Command::Openapi => unreachable!("handled without a config"),
+    }
+}
+
+/// we should first try to load RESERVE_CONFIG envar, then try "./reservation.yml", then try
+/// "~/.config/reservation.yml", then try "/etc/reservation.yml"
+fn config_filename() -> String {
+    std::env::var("RESERVATION_CONFIG").unwrap_or_else(|_| {
         let p1 = Path::new("./reservation.yml");
         let path = shellexpand::tilde("~/.config/reservation.yml");
         let p2 = Path::new(path.as_ref());
@@ -21,21 +154,185 @@ async fn main() -> Result<()> {
             (_, _, true) => p3.to_str().unwrap().to_string(),
             _ => panic!("no config file found"),
         }
-    });
+    })
+}
 
-    let config = Config::load(filename.as_str())?;
+async fn serve(config: Config) -> Result<()> {
     println!("{config:?}");
 
     let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse()?;
+    // a key of its own would make the page tokens of an instance useless to the others
+    anyhow::ensure!(
+        !config.server.page_token_secret.is_empty(),
+        "server.page_token_secret is required to sign query page tokens"
+    );
+
+    let svc = Arc::new(RsvpService::from_config(&config).await);
+    let routes = svc
+        .feed_router()
+        .merge(svc.events_router())
+        .merge(svc.clone().rest_router());
+    let svc = ReservationServiceServer::from_arc(svc);
+    let cors = grpc_web_cors(&config.server)?;
+
+    let grpc = async move {
+        println!("Listening on {addr}");
+        // http/1.1 is accepted for the grpc-web calls of browsers
+        tonic::transport::Server::builder()
+            .accept_http1(true)
+            .layer(cors)
+            .layer(GrpcWebLayer::new())
+            .add_service(svc)
+            .serve(addr)
+            .await?;
+        Ok(())
+    };
+    let http = async move {
+        if let Some(port) = config.server.http_port {
+            let addr: SocketAddr = format!("{}:{}", config.server.host, port).parse()?;
+            println!("Serving calendar feeds and the http api on {addr}");
+            axum::Server::bind(&addr)
+                .serve(routes.into_make_service())
+                .await?;
+        }
+        Ok(())
+    };
+    tokio::try_join!(grpc, http)?;
+
+    Ok(())
+}
+
+async fn export(config: Config, args: ExportArgs) -> Result<()> {
+    let url = format!("http://{}:{}", config.server.host, config.server.port);
+    let mut client = ReservationServiceClient::connect(url).await?;
+
+    let mut query = ReservationQueryBuilder::default()
+        .resource_ids(args.resource)
+        .user_ids(args.user)
+        .include_cancelled(args.include_cancelled)
+        .tags(args.tag)
+        .build()?;
+    query.start = args.start.map(convert_to_timestamp);
+    query.end = args.end.map(convert_to_timestamp);
+    let format = match args.format {
+        Format::Csv => ExportFormat::Csv,
+        Format::Parquet => ExportFormat::Parquet,
+        Format::Ics => ExportFormat::Icalendar,
+    };
+    let request = ExportRequest {
+        query: Some(query),
+        format: format as i32,
+    };
+
+    let mut out: Box<dyn tokio::io::AsyncWrite + Unpin> = match args.output {
+        Some(path) => Box::new(tokio::fs::File::create(path).await?),
+        None => Box::new(tokio::io::stdout()),
+    };
+    let mut stream = client.export(request).await?.into_inner();
+    while let Some(chunk) = stream.message().await? {
+        out.write_all(&chunk.data).await?;
+    }
+    out.flush().await?;
+
+    Ok(())
+}
+
+async fn import(config: Config, args: ImportArgs) -> Result<()> {
+    let format = match args.format {
+        Some(format) => format,
+        None => match args.file.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => InputFormat::Json,
+            Some("ics") => InputFormat::Ics,
+            _ => InputFormat::Csv,
+        },
+    };
+    let request = ImportRequest {
+        data: tokio::fs::read(&args.file).await?,
+        format: match format {
+            InputFormat::Csv => ImportFormat::Csv,
+            InputFormat::Json => ImportFormat::Json,
+            InputFormat::Ics => ImportFormat::Icalendar,
+        } as i32,
+        mode: if args.skip_invalid {
+            ImportMode::SkipInvalid
+        } else {
+            ImportMode::AllOrNothing
+        } as i32,
+        dry_run: args.dry_run,
+        batch_size: args.batch_size,
+        user_id: args.user,
+        resource_id: args.resource,
+    };
 
-    let svc = RsvpService::from_config(&config).await;
-    let svc = ReservationServiceServer::new(svc);
+    let url = format!("http://{}:{}", config.server.host, config.server.port);
+    let mut client = ReservationServiceClient::connect(url).await?;
+    // import is an admin call
+    let mut request = tonic::Request::new(request);
+    let token = format!("Bearer {}", config.server.admin_token);
+    request
+        .metadata_mut()
+        .insert("authorization", token.parse()?);
+    let resp = client.import(request).await?.into_inner();
 
-    println!("Listening on {addr}");
-    tonic::transport::Server::builder()
-        .add_service(svc)
-        .serve(addr)
-        .await?;
+    for row in &resp.rows {
+        let status = ImportRowStatus::from_i32(row.status).unwrap_or(ImportRowStatus::Invalid);
+        match (status, row.reservation_id) {
+            (ImportRowStatus::Ok, 0) => println!("row {}: ok", row.row),
+            (ImportRowStatus::Ok, id) => println!("row {}: imported as {}", row.row, id),
+            (status, _) => println!("row {}: {:?}, {}", row.row, status, row.reason),
+        }
+    }
+    println!(
+        "{} row(s), {} imported, {} failed",
+        resp.rows.len(),
+        resp.imported,
+        resp.failed
+    );
+
+    Ok(())
+}
+
+async fn time_zone(config: Config, args: TimeZoneArgs) -> Result<()> {
+    let url = format!("http://{}:{}", config.server.host, config.server.port);
+    let mut client = ReservationServiceClient::connect(url).await?;
+    // set_time_zone is an admin call
+    let mut request = tonic::Request::new(SetTimeZoneRequest {
+        resource_id: args.resource,
+        time_zone: args.time_zone,
+    });
+    let token = format!("Bearer {}", config.server.admin_token);
+    request
+        .metadata_mut()
+        .insert("authorization", token.parse()?);
+    let resp = client.set_time_zone(request).await?.into_inner();
+    println!("{}: {}", resp.resource_id, resp.time_zone);
 
     Ok(())
 }
+
+async fn sync(config: Config, args: SyncArgs) -> Result<()> {
+    let caldav = config
+        .caldav
+        .ok_or_else(|| anyhow!("no caldav section in the config"))?;
+    let manager = ReservationManager::from_config(&config.db).await?;
+    let http = reqwest::Client::new();
+
+    loop {
+        for mirror in &caldav.mirrors {
+            let client = CalDavClient::new(http.clone(), &caldav, &mirror.url)?;
+            match manager
+                .sync_caldav(&client, &mirror.resource_id, &mirror.url, &caldav.user_id)
+                .await
+            {
+                std::result::Result::Ok(report) => println!("{}: {:?}", mirror.resource_id, report),
+                // an unreachable server doesn't hold back the other mirrors, the next round retries
+                Err(e) if !args.once => eprintln!("{}: {}", mirror.resource_id, e),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(caldav.interval)).await;
+    }
+}
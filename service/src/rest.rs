@@ -0,0 +1,287 @@
+use std::{future::Future, sync::Arc};
+
+use abi::{reservation_service_server::ReservationService, rest_path, ExportFormat, ExportRequest};
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures::TryStreamExt;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use tonic::{metadata::MetadataMap, Code, Status};
+
+use crate::RsvpService;
+
+/// a route for each unary call, taking and returning its messages as json
+macro_rules! unary_routes {
+    ($router:expr, $($method:ident),* $(,)?) => {
+        $router
+        $(
+            .route(
+                &rest_path(stringify!($method)),
+                post(
+                    |State(svc): State<Arc<RsvpService>>, headers: HeaderMap, body: Bytes| async move {
+                        unary(headers, body, |request| async move { svc.$method(request).await })
+                            .await
+                    },
+                ),
+            )
+        )*
+    };
+}
+
+impl RsvpService {
+    /// http routes of the calls but the admin ones, a POST of the json request to /v1/<call>,
+    /// and the openapi document at /v1/openapi.json. Headers are passed on as the metadata of
+    /// the call
+    pub fn rest_router(self: Arc<Self>) -> Router {
+        let router = unary_routes!(
+            Router::new(),
+            reserve,
+            confirm,
+            update,
+            cancel,
+            get,
+            get_history,
+            reserve_batch,
+            confirm_group,
+            cancel_group,
+            get_group,
+            query,
+            filter,
+            search,
+            analytics,
+        );
+        router
+            .route(&rest_path("export"), post(export))
+            .route(&rest_path("openapi.json"), get(openapi))
+            .with_state(self)
+    }
+}
+
+async fn openapi() -> Json<serde_json::Value> {
+    Json(abi::openapi())
+}
+
+async fn unary<Req, Resp, F, Fut>(headers: HeaderMap, body: Bytes, call: F) -> Response
+where
+    Req: DeserializeOwned,
+    Resp: Serialize,
+    F: FnOnce(tonic::Request<Req>) -> Fut,
+    Fut: Future<Output = Result<tonic::Response<Resp>, Status>>,
+{
+    let message = match parse(&body) {
+        Ok(message) => message,
+        Err(e) => return error(Status::invalid_argument(format!("invalid json: {}", e))),
+    };
+    match call(request(headers, message)).await {
+        Ok(resp) => Json(resp.into_inner()).into_response(),
+        Err(status) => error(status),
+    }
+}
+
+/// the file of the export, streamed as it is written
+async fn export(State(svc): State<Arc<RsvpService>>, headers: HeaderMap, body: Bytes) -> Response {
+    let message: ExportRequest = match parse(&body) {
+        Ok(message) => message,
+        Err(e) => return error(Status::invalid_argument(format!("invalid json: {}", e))),
+    };
+    let content_type = match ExportFormat::from_i32(message.format) {
+        Some(ExportFormat::Csv) => "text/csv; charset=utf-8",
+        Some(ExportFormat::Icalendar) => "text/calendar; charset=utf-8",
+        _ => "application/octet-stream",
+    };
+    match svc.export(request(headers, message)).await {
+        Ok(resp) => {
+            let chunks = resp.into_inner().map_ok(|chunk| chunk.data);
+            ([(CONTENT_TYPE, content_type)], StreamBody::new(chunks)).into_response()
+        }
+        Err(status) => error(status),
+    }
+}
+
+/// an empty body is the default message
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, serde_json::Error> {
+    let body = if body.is_empty() { b"{}" } else { body };
+    serde_json::from_slice(body)
+}
+
+fn request<T>(headers: HeaderMap, message: T) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    *request.metadata_mut() = MetadataMap::from_headers(headers);
+    request
+}
+
+/// the error as a google.rpc.Status, the http status follows the grpc code
+fn error(status: Status) -> Response {
+    let body = json!({ "code": status.code() as i32, "message": status.message() });
+    (http_status(status.code()), Json(body)).into_response()
+}
+
+/// the usual mapping of grpc codes, except that a failed precondition such as a conflicting
+/// reservation or a status transition the lifecycle rejects is a conflict
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::Cancelled => StatusCode::from_u16(499).expect("499 is a valid status"),
+        Code::InvalidArgument | Code::OutOfRange => StatusCode::BAD_REQUEST,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted | Code::FailedPrecondition => StatusCode::CONFLICT,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{body::Body, http::Request};
+    use reservation::ReservationManager;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn router(pool: &sqlx::PgPool) -> Router {
+        Arc::new(RsvpService {
+            manager: ReservationManager::new(pool.clone()),
+            idempotency_window: Duration::from_secs(60),
+            admin_token: "".into(),
+        })
+        .rest_router()
+    }
+
+    async fn call(
+        router: &Router,
+        method: &str,
+        body: Value,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, Value) {
+        let mut request = Request::post(rest_path(method)).header(CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let resp = router
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn reservation(user_id: &str) -> Value {
+        json!({
+            "reservation": {
+                "userId": user_id,
+                "resourceId": "room-1",
+                "status": "RESERVATION_STATUS_PENDING",
+                "start": "2022-12-25T22:00:00Z",
+                "end": "2022-12-28T19:00:00Z",
+                "note": "note",
+            }
+        })
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn rest_calls_should_use_json_mapping_and_http_status() {
+        let router = router(&migrated_pool);
+        let (status, body) = call(&router, "reserve", reservation("alice"), &[]).await;
+        assert_eq!(status, StatusCode::OK);
+        let rsvp = &body["reservation"];
+        assert_eq!(rsvp["status"], "RESERVATION_STATUS_PENDING");
+        assert_eq!(rsvp["start"], "2022-12-25T22:00:00Z");
+        let id = rsvp["id"].clone();
+
+        let (status, body) = call(&router, "get", json!({ "id": id }), &[]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["reservation"]["userId"], "alice");
+
+        let (status, body) = call(&router, "reserve", reservation("bob"), &[]).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], Code::FailedPrecondition as i32);
+        assert!(body["message"]
+            .as_str()
+            .unwrap()
+            .contains("reservation conflict"));
+
+        let (status, body) = call(&router, "get", json!({ "id": 999 }), &[]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], Code::NotFound as i32);
+
+        let (status, _) = call(
+            &router,
+            "query",
+            json!({ "query": { "status": "BOOKED" } }),
+            &[],
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn rest_headers_should_be_call_metadata() {
+        let router = router(&migrated_pool);
+        let key = [("x-idempotency-key", "key-1")];
+        let (_, first) = call(&router, "reserve", reservation("alice"), &key).await;
+        let (status, again) = call(&router, "reserve", reservation("alice"), &key).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first, again);
+    }
+
+    #[sqlx_database_tester::test(pool(variable = "migrated_pool", migrations = "../migrations"))]
+    async fn openapi_paths_should_be_routed() {
+        let router = router(&migrated_pool);
+        let resp = router
+            .clone()
+            .oneshot(
+                Request::get("/v1/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let doc: Value = serde_json::from_slice(&body).unwrap();
+
+        for path in doc["paths"].as_object().unwrap().keys() {
+            let resp = router
+                .clone()
+                .oneshot(Request::post(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            // unrouted paths get an empty 404 or 405, calls always answer with a body type
+            assert!(
+                resp.headers().contains_key(CONTENT_TYPE),
+                "{} is not routed",
+                path
+            );
+        }
+
+        // the admin calls are only served on the grpc port
+        for method in abi::ADMIN_METHODS {
+            let resp = router
+                .clone()
+                .oneshot(
+                    Request::post(rest_path(method))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{} is routed", method);
+        }
+    }
+}
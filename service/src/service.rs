@@ -1,99 +1,509 @@
 use abi::{
-    reservation_service_server::ReservationService, CancelRequest, CancelResponse, Config,
-    ConfirmRequest, ConfirmResponse, FilterRequest, FilterResponse, GetRequest, GetResponse,
-    ListenRequest, QueryRequest, ReserveRequest, ReserveResponse, UpdateRequest, UpdateResponse,
+    reservation_service_server::ReservationService, AnalyticsRequest, AnalyticsResponse,
+    CancelGroupRequest, CancelRequest, CancelResponse, Config, ConfirmRequest, ConfirmResponse,
+    CreateFeedRequest, CreateFeedResponse, ExportChunk, ExportFormat, ExportRequest, FilterRequest,
+    FilterResponse, GetHistoryRequest, GetHistoryResponse, GetRequest, GetResponse, GroupRequest,
+    GroupResponse, ImportRequest, ImportResponse, ListFeedsRequest, ListFeedsResponse,
+    ListenRequest, PageTokenKey, PurgeRequest, PurgeResponse, QueryRequest, QueryResponse,
+    ReservationError, ReservationQuery, ReservationStatus, ReserveBatchRequest,
+    ReserveBatchResponse, ReserveRequest, ReserveResponse, RevokeFeedRequest, RevokeFeedResponse,
+    SearchRequest, SearchResponse, SetTimeZoneRequest, SetTimeZoneResponse, UpdateRequest,
+    UpdateResponse, MAX_PAGE_SIZE,
 };
-use reservation::{ReservationManager, Rsvp};
+use futures::{stream, TryStreamExt};
+use prost::Message;
+use reservation::{ChangeContext, IdempotencyClaim, ReservationManager, Rsvp};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{future::Future, time::Duration};
 
-use crate::{ReservationStream, RsvpService};
+use crate::{ExportStream, ListenStream, QueryStream, RsvpService};
 
 impl RsvpService {
     pub async fn from_config(config: &Config) -> Self {
-        let pool = ReservationManager::from_config(&config.db).await;
+        let manager = ReservationManager::from_config(&config.db)
+            .await
+            .unwrap()
+            .with_page_token_key(PageTokenKey::new(&config.server.page_token_secret));
 
         Self {
-            manager: pool.unwrap(),
+            manager,
+            idempotency_window: Duration::from_secs(config.server.idempotency_window),
+            admin_token: config.server.admin_token.clone(),
+        }
+    }
+
+    /// the admin calls need the admin token of the config, they are refused if there is none
+    fn check_admin<T>(&self, request: &tonic::Request<T>) -> Result<(), ReservationError> {
+        let token = metadata(request, "authorization").and_then(|v| v.strip_prefix("Bearer "));
+        match token {
+            Some(token) if !self.admin_token.is_empty() && token_eq(token, &self.admin_token) => {
+                Ok(())
+            }
+            _ => Err(ReservationError::AdminTokenRequired),
         }
     }
+
+    /// a manager carrying the actor and reason from the request metadata
+    fn manager<T>(&self, request: &tonic::Request<T>) -> ReservationManager {
+        self.manager.with_context(ChangeContext {
+            actor: metadata(request, "x-actor").unwrap_or_default().to_string(),
+            reason: metadata(request, "x-change-reason")
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    /// make a mutating call. If the request carries an idempotency key, the response is stored
+    /// with the key in the transaction of the changes, and a repeated call with the same key and
+    /// request returns the stored response
+    async fn idempotent<Req, T, F, Fut>(
+        &self,
+        request: tonic::Request<Req>,
+        method: &str,
+        call: F,
+    ) -> Result<tonic::Response<T>, tonic::Status>
+    where
+        Req: Serialize,
+        T: Message + Default,
+        F: FnOnce(ReservationManager, Req) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+    {
+        let manager = self.manager(&request);
+        let key = match metadata(&request, "x-idempotency-key") {
+            Some(key) => key.to_string(),
+            None => return call(manager, request.into_inner()).await,
+        };
+
+        let request_hash = request_hash(request.get_ref())
+            .map_err(|e| tonic::Status::internal(format!("failed to encode request: {}", e)))?;
+        let claim = self
+            .manager
+            .claim_idempotency_key(&key, method, &request_hash, self.idempotency_window)
+            .await?;
+        let claimed = match claim {
+            IdempotencyClaim::Claimed(claimed) => claimed,
+            IdempotencyClaim::Completed(response) => {
+                let resp = T::decode(response.as_slice()).map_err(|e| {
+                    tonic::Status::internal(format!("failed to decode stored response: {}", e))
+                })?;
+                return Ok(tonic::Response::new(resp));
+            }
+        };
+
+        // a failed call drops the claim, which rolls back its changes and releases the key
+        let manager = manager.with_idempotent_call(claimed.clone());
+        let resp = call(manager, request.into_inner()).await?;
+        self.manager
+            .complete_idempotency_key(claimed, resp.get_ref().encode_to_vec())
+            .await?;
+        Ok(resp)
+    }
+}
+
+/// hash of the json of a request, the entries of its maps are in key order in a json value
+fn request_hash<T: Serialize>(request: &T) -> Result<Vec<u8>, serde_json::Error> {
+    let json = serde_json::to_vec(&serde_json::to_value(request)?)?;
+    Ok(Sha256::digest(json).to_vec())
+}
+
+/// 0 is the default of the proto field, which means no version is expected
+fn expected_version(version: i64) -> Option<i64> {
+    if version == 0 {
+        None
+    } else {
+        Some(version)
+    }
+}
+
+/// compare without returning early, so the time taken doesn't tell how much of a token matched
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn metadata<'a, T>(request: &'a tonic::Request<T>, key: &str) -> Option<&'a str> {
+    request.metadata().get(key).and_then(|v| v.to_str().ok())
 }
 
 #[tonic::async_trait]
 impl ReservationService for RsvpService {
-    type queryStream = ReservationStream;
-
-    type listenStream = ReservationStream;
+    type listenStream = ListenStream;
+    type exportStream = ExportStream;
+    type query_streamStream = QueryStream;
 
     /// make a reservation
     async fn reserve(
         &self,
         request: tonic::Request<ReserveRequest>,
     ) -> Result<tonic::Response<ReserveResponse>, tonic::Status> {
-        let req = request.into_inner();
-        if req.reservation.is_none() {
-            return Err(tonic::Status::invalid_argument("reservation is required"));
-        }
-        let rsvp = req.reservation.unwrap();
+        self.idempotent(request, "reserve", |manager, req| async move {
+            if req.reservation.is_none() {
+                return Err(tonic::Status::invalid_argument("reservation is required"));
+            }
+            let rsvp = req.reservation.unwrap();
 
-        let rsvp = self
-            .manager
-            .reserve(rsvp)
-            .await
-            .map_err(|e| tonic::Status::internal(format!("failed to reserve: {}", e)))?;
+            // a conflict or an invalid reservation keeps its own status code
+            let rsvp = manager.reserve(rsvp).await?;
 
-        Ok(tonic::Response::new(ReserveResponse {
-            reservation: Some(rsvp),
-        }))
+            Ok(tonic::Response::new(ReserveResponse {
+                reservation: Some(rsvp),
+            }))
+        })
+        .await
     }
-    /// confirm a pending reservation, if reservation is not pending, do nothing
+    /// confirm a pending reservation, a confirmed one is left alone, other status are rejected
     async fn confirm(
         &self,
-        _request: tonic::Request<ConfirmRequest>,
+        request: tonic::Request<ConfirmRequest>,
     ) -> Result<tonic::Response<ConfirmResponse>, tonic::Status> {
-        todo!()
+        self.idempotent(request, "confirm", |manager, req| async move {
+            let rsvp = manager
+                .change_status(
+                    req.id,
+                    ReservationStatus::Confirmed,
+                    expected_version(req.expected_version),
+                )
+                .await?;
+
+            Ok(tonic::Response::new(ConfirmResponse {
+                reservation: Some(rsvp),
+            }))
+        })
+        .await
     }
-    /// update the reservation note
+    /// update the fields of a reservation listed in the field mask, status changes must follow the lifecycle
     async fn update(
         &self,
-        _request: tonic::Request<UpdateRequest>,
+        request: tonic::Request<UpdateRequest>,
     ) -> Result<tonic::Response<UpdateResponse>, tonic::Status> {
-        todo!()
+        self.idempotent(request, "update", |manager, req| async move {
+            let rsvp = manager
+                .update(
+                    req.id,
+                    req.reservation.unwrap_or_default(),
+                    req.update_mask.unwrap_or_default(),
+                    expected_version(req.expected_version),
+                )
+                .await?;
+
+            Ok(tonic::Response::new(UpdateResponse {
+                reservation: Some(rsvp),
+            }))
+        })
+        .await
     }
-    /// cancel a reservation
+    /// cancel a reservation, the reservation is kept with CANCELLED status
     async fn cancel(
         &self,
-        _request: tonic::Request<CancelRequest>,
+        request: tonic::Request<CancelRequest>,
     ) -> Result<tonic::Response<CancelResponse>, tonic::Status> {
-        todo!()
+        self.idempotent(request, "cancel", |manager, req| async move {
+            let rsvp = manager
+                .cancel(
+                    req.id,
+                    req.reason,
+                    req.actor,
+                    expected_version(req.expected_version),
+                )
+                .await?;
+
+            Ok(tonic::Response::new(CancelResponse {
+                reservation: Some(rsvp),
+            }))
+        })
+        .await
+    }
+    /// remove a reservation physically, for admin only
+    async fn purge(
+        &self,
+        request: tonic::Request<PurgeRequest>,
+    ) -> Result<tonic::Response<PurgeResponse>, tonic::Status> {
+        self.check_admin(&request)?;
+        self.idempotent(request, "purge", |manager, req| async move {
+            let rsvp = manager.purge(req.id).await?;
+
+            Ok(tonic::Response::new(PurgeResponse {
+                reservation: Some(rsvp),
+            }))
+        })
+        .await
     }
     /// get a reservation by id
     async fn get(
         &self,
-        _request: tonic::Request<GetRequest>,
+        request: tonic::Request<GetRequest>,
     ) -> Result<tonic::Response<GetResponse>, tonic::Status> {
-        todo!()
+        let rsvp = self.manager.get(request.into_inner().id).await?;
+        Ok(tonic::Response::new(GetResponse {
+            reservation: Some(rsvp),
+        }))
+    }
+
+    /// get the change history of a reservation
+    async fn get_history(
+        &self,
+        request: tonic::Request<GetHistoryRequest>,
+    ) -> Result<tonic::Response<GetHistoryResponse>, tonic::Status> {
+        let changes = self.manager.get_history(request.into_inner().id).await?;
+
+        Ok(tonic::Response::new(GetHistoryResponse { changes }))
+    }
+    /// reserve several resources atomically under a shared group id
+    async fn reserve_batch(
+        &self,
+        request: tonic::Request<ReserveBatchRequest>,
+    ) -> Result<tonic::Response<ReserveBatchResponse>, tonic::Status> {
+        self.idempotent(request, "reserve_batch", |manager, req| async move {
+            let resp = match manager.reserve_group(req.reservations).await {
+                Ok(rsvps) => ReserveBatchResponse {
+                    group_id: rsvps[0].group_id.clone(),
+                    reservations: rsvps,
+                    failures: vec![],
+                },
+                Err(ReservationError::GroupReservationFailed(failures)) => ReserveBatchResponse {
+                    group_id: String::new(),
+                    reservations: vec![],
+                    failures: failures.into_iter().map(Into::into).collect(),
+                },
+                Err(e) => return Err(e.into()),
+            };
+
+            Ok(tonic::Response::new(resp))
+        })
+        .await
+    }
+    /// confirm all pending reservations of a group
+    async fn confirm_group(
+        &self,
+        request: tonic::Request<GroupRequest>,
+    ) -> Result<tonic::Response<GroupResponse>, tonic::Status> {
+        self.idempotent(request, "confirm_group", |manager, req| async move {
+            let group_id = req.group_id;
+            let rsvps = manager.confirm_group(&group_id).await?;
+
+            Ok(tonic::Response::new(GroupResponse {
+                group_id,
+                reservations: rsvps,
+            }))
+        })
+        .await
+    }
+    /// cancel all reservations of a group
+    async fn cancel_group(
+        &self,
+        request: tonic::Request<CancelGroupRequest>,
+    ) -> Result<tonic::Response<GroupResponse>, tonic::Status> {
+        self.idempotent(request, "cancel_group", |manager, req| async move {
+            let rsvps = manager
+                .cancel_group(&req.group_id, req.reason, req.actor)
+                .await?;
+
+            Ok(tonic::Response::new(GroupResponse {
+                group_id: req.group_id,
+                reservations: rsvps,
+            }))
+        })
+        .await
+    }
+    /// get all reservations of a group
+    async fn get_group(
+        &self,
+        request: tonic::Request<GroupRequest>,
+    ) -> Result<tonic::Response<GroupResponse>, tonic::Status> {
+        let group_id = request.into_inner().group_id;
+        let rsvps = self.manager.get_group(&group_id).await?;
+
+        Ok(tonic::Response::new(GroupResponse {
+            group_id,
+            reservations: rsvps,
+        }))
     }
 
     /// query reservations by resource id, user id, status, start time, end time
     async fn query(
         &self,
-        _request: tonic::Request<QueryRequest>,
-    ) -> Result<tonic::Response<Self::queryStream>, tonic::Status> {
-        todo!()
+        request: tonic::Request<QueryRequest>,
+    ) -> Result<tonic::Response<QueryResponse>, tonic::Status> {
+        let query = request.into_inner().query.unwrap_or_default();
+        let (reservations, next_page_token) = self.manager.query(query).await?;
+
+        Ok(tonic::Response::new(QueryResponse {
+            reservations,
+            next_page_token: next_page_token.unwrap_or_default(),
+        }))
+    }
+
+    /// stream all reservations matching a query, read page by page
+    async fn query_stream(
+        &self,
+        request: tonic::Request<QueryRequest>,
+    ) -> Result<tonic::Response<Self::query_streamStream>, tonic::Status> {
+        let query = ReservationQuery {
+            page: 0,
+            page_size: MAX_PAGE_SIZE as i32,
+            ..request.into_inner().query.unwrap_or_default()
+        };
+        let manager = self.manager.clone();
+        // the query of the next page, None after the last one
+        let pages = stream::try_unfold(Some(query), move |query| {
+            let manager = manager.clone();
+            async move {
+                let query = match query {
+                    Some(query) => query,
+                    None => return Ok::<_, ReservationError>(None),
+                };
+                let (rsvps, next_page_token) = manager.query(query.clone()).await?;
+                let next = next_page_token.map(|page_token| ReservationQuery {
+                    page_token,
+                    ..query
+                });
+                let rsvps = rsvps.into_iter().map(Ok::<_, ReservationError>);
+                Ok(Some((stream::iter(rsvps), next)))
+            }
+        });
+        let rsvps = pages.try_flatten().map_err(tonic::Status::from);
+        Ok(tonic::Response::new(Box::pin(rsvps)))
     }
     /// filter reservations, order by reservation id
     async fn filter(
         &self,
-        _request: tonic::Request<FilterRequest>,
+        request: tonic::Request<FilterRequest>,
     ) -> Result<tonic::Response<FilterResponse>, tonic::Status> {
-        todo!()
+        let filter = request.into_inner().filter.unwrap_or_default();
+        let (reservations, pager) = self.manager.filter(filter).await?;
+        Ok(tonic::Response::new(FilterResponse {
+            reservations,
+            pager: Some(pager),
+        }))
+    }
+
+    /// full text search over notes, tags and attribute values, best match first
+    async fn search(
+        &self,
+        request: tonic::Request<SearchRequest>,
+    ) -> Result<tonic::Response<SearchResponse>, tonic::Status> {
+        let search = request.into_inner().search.unwrap_or_default();
+        let results = self.manager.search(search).await?;
+        Ok(tonic::Response::new(SearchResponse { results }))
+    }
+
+    /// booked minutes, utilization, reservation count, cancellation rate and peak hour per resource and bucket
+    async fn analytics(
+        &self,
+        request: tonic::Request<AnalyticsRequest>,
+    ) -> Result<tonic::Response<AnalyticsResponse>, tonic::Status> {
+        let query = request.into_inner().query.unwrap_or_default();
+        let buckets = self.manager.analytics(query).await?;
+        Ok(tonic::Response::new(AnalyticsResponse { buckets }))
+    }
+
+    /// export the reservations matching a query as a csv or parquet file, streamed in chunks
+    async fn export(
+        &self,
+        request: tonic::Request<ExportRequest>,
+    ) -> Result<tonic::Response<Self::exportStream>, tonic::Status> {
+        let request = request.into_inner();
+        let format = ExportFormat::from_i32(request.format)
+            .ok_or(ReservationError::InvalidExportFormat(request.format))?;
+        let chunks = self
+            .manager
+            .export(request.query.unwrap_or_default(), format)
+            .map_ok(|data| ExportChunk { data })
+            .map_err(tonic::Status::from);
+        Ok(tonic::Response::new(Box::pin(chunks)))
+    }
+
+    /// check the reservations of a csv or json file and import them, for admin only
+    async fn import(
+        &self,
+        request: tonic::Request<ImportRequest>,
+    ) -> Result<tonic::Response<ImportResponse>, tonic::Status> {
+        self.check_admin(&request)?;
+        self.idempotent(request, "import", |manager, req| async move {
+            let resp = manager.import(req).await?;
+            Ok(tonic::Response::new(resp))
+        })
+        .await
+    }
+
+    /// create a calendar feed of a resource or user, for admin only
+    async fn create_feed(
+        &self,
+        request: tonic::Request<CreateFeedRequest>,
+    ) -> Result<tonic::Response<CreateFeedResponse>, tonic::Status> {
+        self.check_admin(&request)?;
+        // not idempotent, a stored response would keep the token in the clear
+        let request = request.into_inner();
+        let (feed, token) = self
+            .manager
+            .create_feed(request.get_kind()?, request.subject)
+            .await?;
+        Ok(tonic::Response::new(CreateFeedResponse {
+            feed: Some(feed),
+            path: abi::feed_path(&token),
+            token,
+        }))
+    }
+
+    /// revoke a calendar feed, for admin only
+    async fn revoke_feed(
+        &self,
+        request: tonic::Request<RevokeFeedRequest>,
+    ) -> Result<tonic::Response<RevokeFeedResponse>, tonic::Status> {
+        self.check_admin(&request)?;
+        let feed = self.manager.revoke_feed(request.into_inner().id).await?;
+        Ok(tonic::Response::new(RevokeFeedResponse {
+            feed: Some(feed),
+        }))
+    }
+
+    /// list calendar feeds, for admin only
+    async fn list_feeds(
+        &self,
+        request: tonic::Request<ListFeedsRequest>,
+    ) -> Result<tonic::Response<ListFeedsResponse>, tonic::Status> {
+        self.check_admin(&request)?;
+        let request = request.into_inner();
+        let feeds = self
+            .manager
+            .list_feeds(&request.subject, request.include_revoked)
+            .await?;
+        Ok(tonic::Response::new(ListFeedsResponse { feeds }))
+    }
+
+    /// set the time zone of a resource, for admin only
+    async fn set_time_zone(
+        &self,
+        request: tonic::Request<SetTimeZoneRequest>,
+    ) -> Result<tonic::Response<SetTimeZoneResponse>, tonic::Status> {
+        self.check_admin(&request)?;
+        let SetTimeZoneRequest {
+            resource_id,
+            time_zone,
+        } = request.into_inner();
+        self.manager
+            .set_time_zone(resource_id.clone(), time_zone.clone())
+            .await?;
+        Ok(tonic::Response::new(SetTimeZoneResponse {
+            resource_id,
+            time_zone,
+        }))
     }
 
     /// another system could monitor newly added/confirmed/cancelled reservations
     async fn listen(
         &self,
-        _request: tonic::Request<ListenRequest>,
+        request: tonic::Request<ListenRequest>,
     ) -> Result<tonic::Response<Self::listenStream>, tonic::Status> {
-        todo!()
+        let changes = self
+            .manager
+            .listen(request.into_inner())
+            .map_err(tonic::Status::from);
+        Ok(tonic::Response::new(Box::pin(changes)))
     }
 }
 
@@ -102,7 +512,9 @@ mod tests {
     use std::{ops::Deref, sync::Arc, thread};
 
     use abi::{
-        reservation_service_server::ReservationService, Config, Reservation, ReserveRequest,
+        reservation_service_server::ReservationService, CalendarFeedKind, Config, ConfirmRequest,
+        CreateFeedRequest, ImportFormat, ImportRequest, ListFeedsRequest, PurgeRequest,
+        Reservation, ReserveRequest, RevokeFeedRequest, SetTimeZoneRequest,
     };
     use sqlx::{types::Uuid, Connection, Executor};
     use tokio::runtime::Runtime;
@@ -216,4 +628,219 @@ mod tests {
         assert_eq!(rsvp1.end, rsvp2.end);
         assert_eq!(rsvp1.note, rsvp2.note);
     }
+
+    #[tokio::test]
+    async fn rpc_reserve_with_idempotency_key_should_return_original_response() {
+        let config = TestConfig::new();
+
+        let service = RsvpService::from_config(&config).await;
+
+        let rsvp = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-11-22T12:00:00-0700".parse().unwrap(),
+            "2022-11-24T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+        let request = |rsvp: Reservation| {
+            let mut req = tonic::Request::new(ReserveRequest {
+                reservation: Some(rsvp),
+            });
+            req.metadata_mut()
+                .insert("x-idempotency-key", "reserve-1".parse().unwrap());
+            req
+        };
+
+        let first = service.reserve(request(rsvp.clone())).await.unwrap();
+        // the retry would conflict with the first reservation if it was made again
+        let second = service.reserve(request(rsvp.clone())).await.unwrap();
+        assert_eq!(first.into_inner(), second.into_inner());
+
+        let err = service
+            .confirm(tonic::Request::new(ConfirmRequest {
+                id: 0,
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        // the same key for another request is rejected
+        let mut other = rsvp.clone();
+        other.note = "another note".into();
+        let err = service.reserve(request(other)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        // the same key for another call is rejected
+        let mut req = tonic::Request::new(ConfirmRequest {
+            id: 1,
+            ..Default::default()
+        });
+        req.metadata_mut()
+            .insert("x-idempotency-key", "reserve-1".parse().unwrap());
+        let err = service.confirm(req).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn rpc_purge_should_need_admin_token() {
+        let config = TestConfig::new();
+        let mut admin_config = Config::clone(&config);
+        admin_config.server.admin_token = "secret".into();
+
+        let service = RsvpService::from_config(&admin_config).await;
+        let rsvp = Reservation::new_pending(
+            "hyx",
+            "room-421",
+            "2022-11-22T12:00:00-0700".parse().unwrap(),
+            "2022-11-24T12:00:00-0700".parse().unwrap(),
+            "hello",
+        );
+        let rsvp = service
+            .reserve(tonic::Request::new(ReserveRequest {
+                reservation: Some(rsvp),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        let request = |token: Option<&str>| {
+            let mut req = tonic::Request::new(PurgeRequest { id: rsvp.id });
+            if let Some(token) = token {
+                req.metadata_mut()
+                    .insert("authorization", token.parse().unwrap());
+            }
+            req
+        };
+
+        for token in [None, Some("Bearer wrong"), Some("secret")] {
+            let err = service.purge(request(token)).await.unwrap_err();
+            assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        }
+        let purged = service.purge(request(Some("Bearer secret"))).await.unwrap();
+        assert_eq!(purged.into_inner().reservation.unwrap().id, rsvp.id);
+
+        // no admin call is allowed without an admin token in the config
+        let service = RsvpService::from_config(&config).await;
+        let err = service.purge(request(Some("Bearer "))).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn rpc_import_should_need_admin_token() {
+        let config = TestConfig::new();
+        let mut admin_config = Config::clone(&config);
+        admin_config.server.admin_token = "secret".into();
+        let service = RsvpService::from_config(&admin_config).await;
+        let request = |token: &str| {
+            let mut req = tonic::Request::new(ImportRequest {
+                data: b"[]".to_vec(),
+                format: ImportFormat::Json as i32,
+                dry_run: true,
+                ..Default::default()
+            });
+            req.metadata_mut()
+                .insert("authorization", token.parse().unwrap());
+            req
+        };
+
+        let err = service.import(request("Bearer wrong")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let resp = service.import(request("Bearer secret")).await.unwrap();
+        assert!(resp.into_inner().rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rpc_feeds_should_need_admin_token() {
+        let config = TestConfig::new();
+        let mut admin_config = Config::clone(&config);
+        admin_config.server.admin_token = "secret".into();
+        let service = RsvpService::from_config(&admin_config).await;
+        fn request<T>(message: T, token: &str) -> tonic::Request<T> {
+            let mut req = tonic::Request::new(message);
+            req.metadata_mut()
+                .insert("authorization", token.parse().unwrap());
+            req
+        }
+        let create = CreateFeedRequest {
+            kind: CalendarFeedKind::Resource as i32,
+            subject: "room-1".into(),
+        };
+        let list = ListFeedsRequest {
+            subject: "room-1".into(),
+            include_revoked: true,
+        };
+
+        let err = service
+            .create_feed(request(create.clone(), "Bearer wrong"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let feed = service
+            .create_feed(request(create, "Bearer secret"))
+            .await
+            .unwrap()
+            .into_inner()
+            .feed
+            .unwrap();
+
+        let err = service
+            .list_feeds(request(list.clone(), "Bearer wrong"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let feeds = service
+            .list_feeds(request(list, "Bearer secret"))
+            .await
+            .unwrap()
+            .into_inner()
+            .feeds;
+        assert_eq!(feeds, vec![feed.clone()]);
+
+        let revoke = RevokeFeedRequest { id: feed.id };
+        let err = service
+            .revoke_feed(request(revoke.clone(), "Bearer wrong"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        service
+            .revoke_feed(request(revoke, "Bearer secret"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rpc_set_time_zone_should_need_admin_token() {
+        let config = TestConfig::new();
+        let mut admin_config = Config::clone(&config);
+        admin_config.server.admin_token = "secret".into();
+        let service = RsvpService::from_config(&admin_config).await;
+        let request = |time_zone: &str, token: &str| {
+            let mut req = tonic::Request::new(SetTimeZoneRequest {
+                resource_id: "room-1".into(),
+                time_zone: time_zone.into(),
+            });
+            req.metadata_mut()
+                .insert("authorization", token.parse().unwrap());
+            req
+        };
+
+        let err = service
+            .set_time_zone(request("Asia/Shanghai", "Bearer wrong"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let err = service
+            .set_time_zone(request("Mars/Olympus_Mons", "Bearer secret"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        let resp = service
+            .set_time_zone(request("Asia/Shanghai", "Bearer secret"))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(resp.time_zone, "Asia/Shanghai");
+    }
 }